@@ -1,12 +1,16 @@
 // hello world
 use bevy::prelude::*;
 use bevy::math::primitives::Rectangle;
-use bevy_fontmesh::{TextMesh, TextMeshBundle, TextMeshStyle};
+use bevy_fontmesh::{JustifyText, TextAnchor, TextMesh, TextMeshBundle, TextMeshStyle};
 use crossbeam_channel::Receiver;
-use std::collections::{HashMap, VecDeque};
-use std::path::PathBuf;
-
-use crate::galaxy::{calculate_galaxy_position, FileStar};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::galaxy::FileStar;
+use crate::planet_material::PlanetMaterial;
+use crate::replay::EventLog;
+use crate::settings::{AgentSettings, Settings};
+use crate::visit_store::FileCounts;
 use crate::ws_client::AgentEvent;
 use crate::FileSystemState;
 
@@ -14,7 +18,8 @@ use crate::FileSystemState;
 
 #[derive(Debug, Clone)]
 pub enum AgentAction {
-    MoveTo { position: Vec3, node_index: usize },
+    /// Fly to a node's star; where it sits is looked up when the move starts
+    MoveTo { node_index: usize },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,16 +37,108 @@ pub struct Agent {
     pub state: AgentState,
     pub current_target_file: Option<usize>,
     pub current_action: Option<String>, // Description of what the agent is doing
+    pub status: Option<String>, // Phase from a status event, until the next tool use
     pub color: Color, // Unique color for this agent (used for UI and spaceship)
     pub greek_symbol: String, // Greek letter (α, β, γ, etc.)
+    pub history: VecDeque<FileEvent>, // Recent tool uses, oldest first (max AGENT_HISTORY_LEN)
+    pub model: Option<String>, // From session_start; None for auto-spawned agents
+    pub action_count: usize, // Total tool uses this session (history is capped, this isn't)
+    pub recent_actions: VecDeque<f32>, // When tool uses landed, secs since startup (last RECENT_ACTIVITY_WINDOW)
+    pub touched_files: HashSet<PathBuf>, // Distinct files this session has used a tool on
+    pub idle_phase: f32, // Per-agent offset so idle ships don't bob in unison
+    pub idle_bob: f32,   // Vertical bob currently applied to the transform
 }
 
-// --- Resources ---
+pub const AGENT_HISTORY_LEN: usize = 50;
+
+/// Tool uses within this many seconds count towards the idle timeout extension
+const RECENT_ACTIVITY_WINDOW: f32 = 60.0;
+
+/// Invisible sphere around a spaceship so it can be clicked
+#[derive(Component)]
+pub struct AgentPickCollider;
+
+const AGENT_PICK_RADIUS: f32 = 2.5;
+
+/// Agent whose action history panel is open
+#[derive(Resource, Default)]
+pub struct SelectedAgent(pub Option<Entity>);
 
+/// Session whose agent and files stay lit while every other agent and star dims
 #[derive(Resource, Default)]
+pub struct Spotlight(pub Option<String>);
+
+impl Spotlight {
+    /// Whether this agent is out of the spotlight
+    pub fn dims(&self, agent: &Agent) -> bool {
+        self.0.as_ref().is_some_and(|session_id| *session_id != agent.session_id)
+    }
+}
+
+// --- Resources ---
+
+#[derive(Resource)]
 pub struct AgentRegistry {
     pub map: HashMap<String, Entity>,
-    pub session_id_order: Vec<String>, // Track order of agents for Greek letter assignment
+    pub session_id_order: Vec<String>, // Sessions in the order their agents appeared
+    pub max_agents: usize, // Above this, idle agents are recycled or new sessions wait
+    pub waiting: VecDeque<AgentEvent>, // Events for sessions that couldn't get an agent yet
+    pub session_cwds: HashMap<String, PathBuf>, // Working directory from each session_start
+}
+
+pub const DEFAULT_MAX_AGENTS: usize = 8;
+/// Events parked while every agent is busy; past this the oldest are dropped
+const MAX_WAITING_EVENTS: usize = 1000;
+
+impl Default for AgentRegistry {
+    fn default() -> Self {
+        Self::with_max_agents(DEFAULT_MAX_AGENTS)
+    }
+}
+
+impl AgentRegistry {
+    pub fn with_max_agents(max_agents: usize) -> Self {
+        Self {
+            map: HashMap::new(),
+            session_id_order: Vec::new(),
+            max_agents: max_agents.max(1),
+            waiting: VecDeque::new(),
+            session_cwds: HashMap::new(),
+        }
+    }
+
+    pub fn has_free_slot(&self) -> bool {
+        self.map.len() < self.max_agents
+    }
+
+    /// Park an event until an agent frees up
+    pub fn wait_for_slot(&mut self, event: AgentEvent) {
+        if self.waiting.len() >= MAX_WAITING_EVENTS
+            && let Some(dropped) = self.waiting.pop_front()
+        {
+            eprintln!(
+                "[agent] Warning: too many events waiting for an agent, dropping one from session {}",
+                dropped.session_id()
+            );
+        }
+        self.waiting.push_back(event);
+    }
+}
+
+/// How wide action bubbles may grow before their text wraps
+#[derive(Resource)]
+pub struct ActionBubbleSettings {
+    pub max_width: f32,
+}
+
+pub const DEFAULT_BUBBLE_MAX_WIDTH: f32 = 6.0;
+
+impl Default for ActionBubbleSettings {
+    fn default() -> Self {
+        Self {
+            max_width: DEFAULT_BUBBLE_MAX_WIDTH,
+        }
+    }
 }
 
 #[derive(Resource)]
@@ -54,19 +151,120 @@ pub struct WsClientState {
 #[derive(Debug, Clone)]
 pub struct FileEvent {
     pub tool_name: String,
+    pub file_path: PathBuf,
     pub session_id: String,
     pub reason: Option<String>,
     pub timestamp: Option<String>,
+    pub repeat: u32, // Consecutive uses of the same tool folded into this entry
+}
+
+const FILE_HISTORY_LEN: usize = 10;
+
+/// Every tool use a file has seen this run, kept in full unlike the capped history
+#[derive(Debug, Clone, Default)]
+pub struct FileTotals {
+    pub by_tool: BTreeMap<String, usize>,
+    pub sessions: BTreeSet<String>,
+}
+
+impl FileTotals {
+    /// This run's visits (every tool use) and the Write/Edit subset of them
+    pub fn counts(&self) -> FileCounts {
+        FileCounts {
+            visits: self.by_tool.values().sum(),
+            edits: self
+                .by_tool
+                .iter()
+                .filter(|(tool, _)| is_edit_tool(tool))
+                .map(|(_, count)| count)
+                .sum(),
+        }
+    }
 }
 
 #[derive(Resource, Default)]
 pub struct FileEventHistory {
-    pub map: HashMap<usize, Vec<FileEvent>>, // node_index -> events (max 10)
+    pub map: HashMap<usize, Vec<FileEvent>>, // node_index -> events (max FILE_HISTORY_LEN)
+    pub totals: HashMap<PathBuf, FileTotals>, // file path -> tallies for the heatmap export
+    /// Seconds since each file's latest tool use, kept for RECENT_TOOL_USE_WINDOW
+    pub recent: HashMap<PathBuf, f32>,
+}
+
+impl FileEventHistory {
+    /// Append a tool use, or bump the count of the latest entry when it's the same
+    /// tool from the same session again
+    pub fn record(&mut self, node_index: usize, event: FileEvent) {
+        self.recent.insert(event.file_path.clone(), 0.0);
+        let totals = self.totals.entry(event.file_path.clone()).or_default();
+        *totals.by_tool.entry(event.tool_name.clone()).or_insert(0) += event.repeat as usize;
+        totals.sessions.insert(event.session_id.clone());
+
+        let events = self.map.entry(node_index).or_default();
+        if let Some(last) = events.last_mut()
+            && last.tool_name == event.tool_name
+            && last.session_id == event.session_id
+        {
+            last.repeat += event.repeat;
+            last.reason = event.reason;
+            last.timestamp = event.timestamp;
+            return;
+        }
+        events.push(event);
+        if events.len() > FILE_HISTORY_LEN {
+            events.remove(0);
+        }
+    }
+
+    /// Age the recent tool uses, forgetting those past the window
+    pub fn age_recent(&mut self, dt: f32) {
+        self.recent.retain(|_, age| {
+            *age += dt;
+            *age < RECENT_TOOL_USE_WINDOW
+        });
+    }
+
+    /// Whether an agent used a tool on the file in the last few seconds, so a
+    /// change the watcher reports for it is most likely that agent's own
+    pub fn recently_used(&self, path: &Path) -> bool {
+        self.recent.contains_key(path)
+    }
+
+    /// Whether the session has used any tool on the file this run
+    pub fn touched_by(&self, path: &Path, session_id: &str) -> bool {
+        self.totals
+            .get(path)
+            .is_some_and(|totals| totals.sessions.contains(session_id))
+    }
 }
 
 #[derive(Resource, Default)]
 pub struct HoveredFile(pub Option<usize>);
 
+// --- Tool uses waiting for their file to appear ---
+
+pub struct PendingToolUse {
+    pub event: AgentEvent,
+    pub age: f32,
+}
+
+/// Tool uses whose file isn't in the model yet (e.g. a Write that lands before
+/// the watcher's Created event), keyed by the canonical path they wait for
+#[derive(Resource, Default)]
+pub struct PendingToolUses {
+    pub map: HashMap<PathBuf, Vec<PendingToolUse>>,
+    /// Paths added to the model since the last retry; only these are looked up
+    pub created: Vec<PathBuf>,
+}
+
+impl PendingToolUses {
+    /// Note a path the model just gained, so tool uses waiting on it are retried
+    pub fn file_created(&mut self, path: &Path) {
+        if self.map.contains_key(path) {
+            self.created.push(path.to_path_buf());
+        }
+    }
+}
+
 // --- Messages ---
 
 #[derive(Message)]
@@ -74,11 +272,56 @@ pub struct AgentArrivedEvent {
     pub node_index: usize,
 }
 
+/// Sent when an agent leaves, whether from session_end or idle timeout
+#[derive(Message)]
+pub struct AgentFinishedEvent {
+    pub greek_symbol: String,
+    pub color: Color,
+    pub action_count: usize,
+}
+
 // --- Highlight component ---
 
+/// Glow on a star that was just worked on. Agent arrivals glow in the star's own
+/// color; `tint` overrides it, e.g. for edits made on disk outside any agent
 #[derive(Component)]
 pub struct FileHighlight {
     pub intensity: f32,
+    pub tint: Option<LinearRgba>,
+    /// Emissive before the highlight started, put back when it fades
+    pub base_emissive: Option<LinearRgba>,
+}
+
+impl FileHighlight {
+    pub fn new(intensity: f32, tint: Option<LinearRgba>) -> Self {
+        FileHighlight {
+            intensity,
+            tint,
+            base_emissive: None,
+        }
+    }
+}
+
+/// Sonar ring expanding out of a star an agent just worked on; each arrival
+/// spawns its own, so busy files send out overlapping rings
+#[derive(Component)]
+pub struct PulseRing {
+    pub age: f32,
+    pub color: LinearRgba,
+}
+
+/// Unit ring shared by every pulse, scaled out as it expands
+#[derive(Resource)]
+pub struct PulseRingMesh(Handle<Mesh>);
+
+impl FromWorld for PulseRingMesh {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        PulseRingMesh(meshes.add(Torus {
+            minor_radius: 0.02,
+            major_radius: 1.0,
+        }))
+    }
 }
 
 // --- Marker for newly spawned spaceships that need material processing ---
@@ -86,6 +329,19 @@ pub struct FileHighlight {
 #[derive(Component)]
 pub struct UnprocessedSpaceship;
 
+/// Remembers how a spaceship mesh was classified on first processing, so a
+/// recycled ship can be recolored without re-guessing from its tinted emissive
+#[derive(Component)]
+pub struct SpaceshipPart {
+    pub is_glow: bool,
+}
+
+/// The ship scene currently shown for an agent, on the `SceneRoot` child
+#[derive(Component)]
+pub struct ShipScene {
+    pub scene: &'static str,
+}
+
 #[derive(Component)]
 pub struct AgentNameplate {
     pub agent_entity: Entity,
@@ -96,31 +352,191 @@ pub struct AgentNameplate {
 pub struct AgentActionBubble {
     pub agent_entity: Entity,
     pub offset: Vec3,
+    /// Action the text and background were last laid out for
+    pub shown_action: Option<String>,
 }
 
 #[derive(Component)]
 pub struct AgentActionText;
 
+/// Halo that pulses around a ship while it reports a status instead of a tool use
+#[derive(Component)]
+pub struct AgentStatusRing {
+    pub agent_entity: Entity,
+}
+
+// --- Motion trails ---
+
+/// Emits trail points behind an agent while it's moving
+#[derive(Component)]
+pub struct AgentTrail {
+    pub timer: f32,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+#[derive(Component)]
+pub struct TrailPoint {
+    pub agent_entity: Entity,
+    pub age: f32,
+}
+
 #[derive(Component)]
 pub struct AgentActionBackground;
 
 // --- Constants ---
 
 const SPAWN_DURATION: f32 = 0.5;
+const SPAWN_HEIGHT: f32 = 15.0;
+const SPAWN_RING_RADIUS: f32 = 6.0;
 const DESPAWN_DURATION: f32 = 0.5;
-const IDLE_TIMEOUT: f32 = 5.0;
-const MOVE_SPEED: f32 = 1.2; // seconds per move
-const AGENT_SCALE: f32 = 100.0;
+const MAX_CATCH_UP_SPEEDUP: f32 = 3.0;
+const CATCH_UP_PER_QUEUED: f32 = 0.25; // extra speed per queued move
+const MAX_QUEUED_MOVES: usize = 20; // older moves are dropped so the ship never lags far behind
+const IDLE_BOB_HEIGHT: f32 = 0.25;
+const IDLE_BOB_SPEED: f32 = 2.0; // radians per second
+const IDLE_YAW_SPEED: f32 = 0.3; // radians per second
+const TURN_DAMPING: f32 = 8.0; // Higher turns toward the flight direction faster
 const NAMEPLATE_SCALE: f32 = 0.35;
 const ACTION_TEXT_SCALE: f32 = 0.24;
 const ACTION_BUBBLE_PADDING: f32 = 0.35;
 const ACTION_BUBBLE_HEIGHT: f32 = 0.55;
+const ACTION_BUBBLE_MAX_LINES: usize = 2;
+const ACTION_LINE_HEIGHT: f32 = 0.3; // extra bubble height per wrapped line
+const ACTION_CHAR_WIDTH: f32 = ACTION_TEXT_SCALE * 0.55; // rough monospace advance
 const ACTION_BUBBLE_Y_OFFSET: f32 = 3.6;
 const NAMEPLATE_Y_OFFSET: f32 = 2.6;
+const PENDING_TOOL_USE_TTL: f32 = 3.0;
+const RECENT_TOOL_USE_WINDOW: f32 = 3.0;
+const TRAIL_SPAWN_INTERVAL: f32 = 0.05;
+const TRAIL_LIFETIME: f32 = 1.0;
+const TRAIL_POINT_SIZE: f32 = 0.18;
+const TRAIL_EMISSIVE: f32 = 4.0;
+const PULSE_RING_DURATION: f32 = 1.6;
+const PULSE_RING_START_RADIUS: f32 = 0.6;
+const PULSE_RING_END_RADIUS: f32 = 3.5;
+const PULSE_RING_ALPHA: f32 = 0.8;
+const PULSE_RING_EMISSIVE: f32 = 3.0;
+const NAMEPLATE_EMISSIVE: f32 = 0.8;
+const SHIP_BODY_EMISSIVE: f32 = 8.0;
+const SHIP_GLOW_EMISSIVE: f32 = 2.0; // antennae and other parts that already glow
+const SPOTLIGHT_AGENT_DIM: f32 = 0.15; // brightness of agents outside the spotlight
+const STATUS_RING_COLOR: Color = Color::srgb(1.0, 0.7, 0.2); // amber, apart from every star palette
+const STATUS_RING_RADIUS: f32 = 1.6;
+const STATUS_RING_PULSE: f32 = 0.15; // fraction the radius swells by
+const STATUS_RING_SPEED: f32 = 3.0; // radians per second
+const STATUS_RING_EMISSIVE: f32 = 3.0;
+
+/// Ship scene for a model family, normalized to the default ship's size
+struct ShipModel {
+    family: &'static str, // matched against the model name without its "claude-" prefix
+    scene: &'static str,
+    scale: f32,
+}
+
+const SHIP_MODELS: &[ShipModel] = &[
+    ShipModel { family: "opus", scene: "1.glb#Scene0", scale: 0.0005 },
+    ShipModel { family: "sonnet", scene: "2.glb#Scene0", scale: 0.0009 },
+];
+const DEFAULT_SHIP: ShipModel = ShipModel { family: "", scene: "spaceships.glb#Scene0", scale: 1.0 };
 
 pub const GREEK_SYMBOLS: &[&str] = &["α", "β", "γ", "δ", "ε", "ζ", "η", "θ", "ι", "κ", "λ", "μ",
                                       "ν", "ξ", "ο", "π", "ρ", "σ", "τ", "υ", "φ", "χ", "ψ", "ω"];
 
+/// Present-tense verb for a tool, as shown in action bubbles and the hover panel.
+/// Bash's file_path is the directory the command runs in, so it reads "Running <dir>".
+pub fn action_verb(tool_name: &str) -> Option<&'static str> {
+    match tool_name {
+        "Read" => Some("Reading"),
+        "Write" => Some("Writing"),
+        "Edit" => Some("Editing"),
+        "Grep" => Some("Searching"),
+        "Glob" => Some("Finding"),
+        "Bash" => Some("Running"),
+        "Task" => Some("Delegating"),
+        "WebFetch" => Some("Fetching"),
+        _ => None,
+    }
+}
+
+/// Tools that change the file they touch
+pub fn is_edit_tool(tool_name: &str) -> bool {
+    matches!(tool_name, "Write" | "Edit" | "MultiEdit" | "NotebookEdit")
+}
+
+/// Pick a ship by model family, falling back to the default for unknown or missing models
+fn ship_for_model(model: Option<&str>) -> &'static ShipModel {
+    let Some(model) = model else {
+        return &DEFAULT_SHIP;
+    };
+    let name = model.strip_prefix("claude-").unwrap_or(model);
+    SHIP_MODELS
+        .iter()
+        .find(|ship| name.starts_with(ship.family))
+        .unwrap_or(&DEFAULT_SHIP)
+}
+
+/// Where a tool's file_path points inside the watched tree. `~` expands to the
+/// home directory, relative paths are taken against the session's cwd (or the
+/// watch root when no session_start said where it runs), and a session running
+/// in a different checkout of the project has that checkout's root swapped for
+/// the watched one.
+fn resolve_tool_path(file_path: &str, cwd: Option<&Path>, root: &Path) -> PathBuf {
+    let path = expand_home(file_path);
+    let path = if path.is_relative() {
+        cwd.unwrap_or(root).join(path)
+    } else {
+        path
+    };
+    let canonical = path.canonicalize().unwrap_or(path);
+    if canonical.starts_with(root) {
+        return canonical;
+    }
+    let Some(checkout) = cwd.map(checkout_root) else {
+        return canonical;
+    };
+    // Where the watched tree sits inside its own checkout; empty when it is the checkout
+    let project = root.strip_prefix(checkout_root(root)).unwrap_or(Path::new(""));
+    let Ok(relative) = canonical.strip_prefix(checkout.join(project)) else {
+        return canonical;
+    };
+    let remapped = root.join(relative);
+    remapped.canonicalize().unwrap_or(remapped)
+}
+
+/// The top of the git checkout holding `dir` (its nearest ancestor with a
+/// `.git` entry), or `dir` itself when it isn't in one
+fn checkout_root(dir: &Path) -> &Path {
+    dir.ancestors().find(|ancestor| ancestor.join(".git").exists()).unwrap_or(dir)
+}
+
+/// The path a not-yet-existing file will be announced under: its directory
+/// canonicalized, since the file itself can't be
+fn pending_path(path: PathBuf) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => dir.canonicalize().map(|dir| dir.join(name)).unwrap_or(path),
+        _ => path,
+    }
+}
+
+/// `~` and `~/rest` with the home directory substituted; anything else unchanged
+fn expand_home(file_path: &str) -> PathBuf {
+    let rest = match file_path.strip_prefix('~') {
+        Some("") => "",
+        Some(rest) if rest.starts_with(['/', '\\']) => &rest[1..],
+        _ => return PathBuf::from(file_path),
+    };
+    match crate::visit_store::home_dir() {
+        Some(home) => home.join(rest),
+        None => PathBuf::from(file_path),
+    }
+}
+
+/// Whether paths from a session running in `cwd` land in the watched tree as-is
+fn cwd_matches_root(cwd: &Path, root: &Path) -> bool {
+    cwd.starts_with(root) || root.starts_with(cwd)
+}
+
 // Ease-in-out cubic
 fn ease_in_out_cubic(t: f32) -> f32 {
     if t < 0.5 {
@@ -143,11 +559,84 @@ fn abbreviate_session_id(session_id: &str) -> String {
 }
 
 fn bubble_width_for_text(text: &str) -> f32 {
-    let char_count = text.chars().count().max(1) as f32;
-    let text_width = char_count * ACTION_TEXT_SCALE * 0.55;
+    let char_count = text.lines().map(|line| line.chars().count()).max().unwrap_or(0).max(1) as f32;
+    let text_width = char_count * ACTION_CHAR_WIDTH;
     (text_width + ACTION_BUBBLE_PADDING * 2.0).max(1.2)
 }
 
+fn bubble_height_for_text(text: &str) -> f32 {
+    let extra_lines = text.lines().count().saturating_sub(1) as f32;
+    ACTION_BUBBLE_HEIGHT + extra_lines * ACTION_LINE_HEIGHT
+}
+
+/// Word-wrap an action description to fit a bubble `max_width` wide, keeping
+/// at most ACTION_BUBBLE_MAX_LINES lines and ending with "…" if it was cut short
+fn wrap_action_text(text: &str, max_width: f32) -> String {
+    let max_chars = ((max_width - ACTION_BUBBLE_PADDING * 2.0) / ACTION_CHAR_WIDTH).floor().max(4.0) as usize;
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        // Break words that can't fit on a line of their own
+        while word.len() > max_chars {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            lines.push(word.drain(..max_chars).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if current.is_empty() {
+            current = word;
+        } else if current.chars().count() + 1 + word.chars().count() <= max_chars {
+            current.push(' ');
+            current.push_str(&word);
+        } else {
+            lines.push(std::mem::replace(&mut current, word));
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    if lines.len() > ACTION_BUBBLE_MAX_LINES {
+        lines.truncate(ACTION_BUBBLE_MAX_LINES);
+        let last = lines.last_mut().expect("at least one line");
+        let kept: String = last.chars().take(max_chars - 1).collect();
+        *last = format!("{}…", kept.trim_end());
+    }
+    lines.join("\n")
+}
+
+// Phase in [0, 2π) derived from the session_id, for desynchronized idle motion
+fn idle_phase_for(session_id: &str) -> f32 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    session_id.hash(&mut hasher);
+    (hasher.finish() % 1000) as f32 / 1000.0 * std::f32::consts::TAU
+}
+
+/// Greek letter derived from the session_id, so a session keeps its symbol across
+/// respawns; collisions probe forward to the next letter no live agent shows
+pub fn greek_symbol_for<'a>(session_id: &str, taken: impl IntoIterator<Item = &'a str>) -> String {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    session_id.hash(&mut hasher);
+    let start = (hasher.finish() % GREEK_SYMBOLS.len() as u64) as usize;
+
+    let taken: Vec<&str> = taken.into_iter().collect();
+    (0..GREEK_SYMBOLS.len())
+        .map(|offset| GREEK_SYMBOLS[(start + offset) % GREEK_SYMBOLS.len()])
+        .find(|symbol| !taken.contains(symbol))
+        // More live agents than letters: share one
+        .unwrap_or(GREEK_SYMBOLS[start])
+        .to_string()
+}
+
 // Generate a consistent color for an agent based on their session_id
 pub fn generate_agent_color(session_id: &str) -> Color {
     use std::collections::hash_map::DefaultHasher;
@@ -198,13 +687,18 @@ pub fn spawn_agent_entity(
     session_id: String,
     event_queue: VecDeque<AgentAction>,
     greek_symbol: String,
+    model: Option<String>,
 ) -> Entity {
-    // Load the spaceship GLB scene
-    let spaceship_scene = asset_server.load("spaceships.glb#Scene0");
+    // Load the spaceship GLB scene for this agent's model family
+    let ship = ship_for_model(model.as_deref());
+    let spaceship_scene = asset_server.load(ship.scene);
 
     // Generate consistent color for this agent
     let agent_color = generate_agent_color(&session_id);
 
+    let idle_phase = idle_phase_for(&session_id);
+    let origin = spawn_origin(&greek_symbol);
+
     // Create parent entity with Agent component
     let name_text = format!("Agent {}", greek_symbol);
     let agent_entity = commands
@@ -215,18 +709,52 @@ pub fn spawn_agent_entity(
                 state: AgentState::Spawning { timer: 0.0 },
                 current_target_file: None,
                 current_action: None,
+                status: None,
                 color: agent_color,
                 greek_symbol,
+                history: VecDeque::new(),
+                model,
+                action_count: 0,
+                recent_actions: VecDeque::new(),
+                touched_files: HashSet::new(),
+                idle_phase,
+                idle_bob: 0.0,
             },
-            Transform::from_translation(Vec3::new(0.0, 15.0, 0.0))
+            Transform::from_translation(origin)
                 .with_scale(Vec3::ZERO)
                 .with_rotation(Quat::from_rotation_y(std::f32::consts::PI)), // Rotate to face forward
             Visibility::default(),
             UnprocessedSpaceship, // Mark for material processing
+            AgentTrail {
+                timer: 0.0,
+                mesh: meshes.add(Sphere::new(TRAIL_POINT_SIZE)),
+                material: materials.add(StandardMaterial {
+                    base_color: agent_color,
+                    emissive: LinearRgba::from(agent_color) * TRAIL_EMISSIVE,
+                    unlit: true,
+                    ..default()
+                }),
+            },
         ))
         .with_children(|parent| {
             // Spawn the GLB scene as a child
-            parent.spawn(SceneRoot(spaceship_scene));
+            parent.spawn((
+                SceneRoot(spaceship_scene),
+                Transform::from_scale(Vec3::splat(ship.scale)),
+                ShipScene { scene: ship.scene },
+            ));
+
+            // Clickable hit area; clicks bubble up to the agent entity
+            parent.spawn((
+                Mesh3d(meshes.add(Sphere::new(AGENT_PICK_RADIUS))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: Color::NONE,
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                })),
+                AgentPickCollider,
+            ));
 
             // Add a bright point light to make the spaceship more visible
             parent.spawn((
@@ -254,7 +782,7 @@ pub fn spawn_agent_entity(
                 },
                 material: MeshMaterial3d(materials.add(StandardMaterial {
                     base_color: agent_color,
-                    emissive: LinearRgba::from(agent_color) * 0.8,
+                    emissive: LinearRgba::from(agent_color) * NAMEPLATE_EMISSIVE,
                     unlit: true,
                     ..default()
                 })),
@@ -283,6 +811,7 @@ pub fn spawn_agent_entity(
             AgentActionBubble {
                 agent_entity,
                 offset: Vec3::new(0.0, ACTION_BUBBLE_Y_OFFSET, 0.0),
+                shown_action: None,
             },
             Transform::from_translation(Vec3::new(0.0, ACTION_BUBBLE_Y_OFFSET, 0.0)),
             Visibility::Hidden,
@@ -304,7 +833,9 @@ pub fn spawn_agent_entity(
                         style: TextMeshStyle {
                             depth: 0.2,
                             subdivision: 8,
-                            ..default()
+                            // Wrapped lines stay centered in the bubble
+                            anchor: TextAnchor::Center,
+                            justify: JustifyText::Center,
                         },
                     },
                     material: MeshMaterial3d(materials.add(StandardMaterial {
@@ -337,10 +868,74 @@ pub fn process_ws_events(
     mut registry: ResMut<AgentRegistry>,
     mut agents: Query<&mut Agent>,
     mut event_history: ResMut<FileEventHistory>,
+    time: Res<Time>,
+    mut pending: ResMut<PendingToolUses>,
+    mut event_log: ResMut<EventLog>,
 ) {
-    while let Ok(event) = ws_state.receiver.try_recv() {
+    let now = time.elapsed_secs();
+    if event_log.is_replaying() {
+        // Keep recording while scrubbing; the events are applied once back to live
+        while let Ok(event) = ws_state.receiver.try_recv() {
+            event_log.record(now, &event);
+            event_log.hold(event);
+        }
+        return;
+    }
+
+    // Retry buffered tool uses whose file the watcher has reported since, and age the rest
+    let dt = time.delta_secs();
+    let mut ready = Vec::new();
+    for path in std::mem::take(&mut pending.created) {
+        if let Some(entries) = pending.map.remove(&path) {
+            ready.extend(entries.into_iter().map(|entry| entry.event));
+        }
+    }
+    pending.map.retain(|_, entries| {
+        entries.retain_mut(|entry| {
+            entry.age += dt;
+            entry.age < PENDING_TOOL_USE_TTL
+        });
+        !entries.is_empty()
+    });
+    // Only while there's something to age, so the history isn't marked changed every frame
+    if !event_history.recent.is_empty() {
+        event_history.age_recent(dt);
+    }
+
+    // Symbols handed out this frame, before their agents show up in the query
+    let mut new_symbols: Vec<String> = Vec::new();
+
+    let waiting: Vec<AgentEvent> = registry.waiting.drain(..).collect();
+    let held: Vec<AgentEvent> = event_log.held.drain(..).collect();
+    let incoming = std::iter::from_fn(|| ws_state.receiver.try_recv().ok())
+        .inspect(|event| event_log.record(now, event));
+    for event in ready.into_iter().chain(waiting).chain(held).chain(incoming) {
+        // Keep later events for a session that is still waiting behind it
+        let session_waiting = registry
+            .waiting
+            .iter()
+            .any(|e| e.session_id() == event.session_id());
+        if session_waiting && !matches!(event, AgentEvent::SessionEnd { .. }) {
+            registry.wait_for_slot(event);
+            continue;
+        }
+
         match event {
-            AgentEvent::SessionStart { session_id, .. } => {
+            AgentEvent::SessionStart { session_id, cwd, model } => {
+                if !cwd.is_empty() && !registry.session_cwds.contains_key(&session_id) {
+                    let session_cwd = PathBuf::from(&cwd);
+                    let session_cwd = session_cwd.canonicalize().unwrap_or(session_cwd);
+                    if !cwd_matches_root(&session_cwd, &fs_state.root_path) {
+                        eprintln!(
+                            "[agent] Warning: session {} runs in {}, not under the watched root {}; remapping its paths",
+                            session_id,
+                            session_cwd.display(),
+                            fs_state.root_path.display()
+                        );
+                    }
+                    registry.session_cwds.insert(session_id.clone(), session_cwd);
+                }
+
                 if registry.map.contains_key(&session_id) {
                     // Agent already exists, cancel despawn if needed
                     if let Some(&entity) = registry.map.get(&session_id) {
@@ -348,29 +943,86 @@ pub fn process_ws_events(
                             if matches!(agent.state, AgentState::Despawning { .. }) {
                                 agent.state = AgentState::Idle { timer: 0.0 };
                             }
+                            // An auto-spawned agent learns its model late
+                            agent.model = Some(model);
                         }
                     }
                     continue;
                 }
 
-                println!("[agent] Spawning agent for session {}", session_id);
-
-                // Assign Greek symbol based on order
-                let greek_symbol = GREEK_SYMBOLS[registry.session_id_order.len() % GREEK_SYMBOLS.len()].to_string();
+                let greek_symbol = next_greek_symbol(&session_id, &agents, &mut new_symbols);
+                let entity = if registry.has_free_slot() {
+                    println!("[agent] Spawning agent for session {}", session_id);
+
+                    spawn_agent_entity(
+                        &mut commands,
+                        &asset_server,
+                        &mut meshes,
+                        &mut materials,
+                        session_id.clone(),
+                        VecDeque::new(),
+                        greek_symbol,
+                        Some(model.clone()),
+                    )
+                } else if let Some((entity, mut agent)) = recyclable_agent(&registry, &mut agents) {
+                    recycle_agent(
+                        &mut commands,
+                        &mut registry,
+                        &mut agent,
+                        entity,
+                        session_id.clone(),
+                        greek_symbol,
+                        VecDeque::new(),
+                    );
+                    entity
+                } else {
+                    // At the cap with every agent busy: wait for a slot
+                    registry.wait_for_slot(AgentEvent::SessionStart { session_id, cwd, model });
+                    continue;
+                };
 
-                let entity = spawn_agent_entity(
-                    &mut commands,
-                    &asset_server,
-                    &mut meshes,
-                    &mut materials,
-                    session_id.clone(),
-                    VecDeque::new(),
-                    greek_symbol,
-                );
+                commands
+                    .entity(entity)
+                    .entry::<Agent>()
+                    .and_modify(move |mut agent| agent.model = Some(model));
 
                 registry.session_id_order.push(session_id.clone());
                 registry.map.insert(session_id, entity);
             }
+            AgentEvent::SessionEnd { session_id } => {
+                // A session that never got an agent just drops its waiting events
+                registry.waiting.retain(|e| e.session_id() != session_id);
+                registry.session_cwds.remove(&session_id);
+
+                // Unknown sessions (never spawned or already gone) are ignored
+                let Some(&entity) = registry.map.get(&session_id) else {
+                    continue;
+                };
+                if let Ok(mut agent) = agents.get_mut(entity) {
+                    println!("[agent] Session {} ended", session_id);
+                    agent.state = AgentState::Despawning { timer: 0.0 };
+                    agent.current_action = None;
+                    agent.status = None;
+                    agent.event_queue.clear();
+                }
+            }
+            AgentEvent::Status { session_id, status } => {
+                // Only sessions that already have a ship; there's no file to spawn one at
+                let Some(&entity) = registry.map.get(&session_id) else {
+                    continue;
+                };
+                // A ship already on its way out stays gone
+                if let Ok(mut agent) = agents.get_mut(entity)
+                    && !matches!(agent.state, AgentState::Despawning { .. })
+                {
+                    // Reporting in counts as activity, so a thinking agent isn't timed out
+                    if matches!(agent.state, AgentState::Idle { .. }) {
+                        agent.state = AgentState::Idle { timer: 0.0 };
+                    }
+                    agent.current_action = Some(status.clone());
+                    agent.status = Some(status);
+                }
+            }
             AgentEvent::ToolUse {
                 session_id,
                 file_path,
@@ -379,9 +1031,8 @@ pub fn process_ws_events(
                 timestamp,
             } => {
                 // Resolve file path to galaxy position
-                let canonical = PathBuf::from(&file_path)
-                    .canonicalize()
-                    .unwrap_or_else(|_| PathBuf::from(&file_path));
+                let cwd = registry.session_cwds.get(&session_id).map(PathBuf::as_path);
+                let canonical = resolve_tool_path(&file_path, cwd, &fs_state.root_path);
 
                 // Extract filename for display
                 let filename = canonical
@@ -393,49 +1044,44 @@ pub fn process_ws_events(
                 let action_desc = if let Some(explanation) = &reason {
                     explanation.clone()
                 } else {
-                    format!("{} {}",
-                        match tool_name.as_str() {
-                            "Read" => "Reading",
-                            "Write" => "Writing",
-                            "Edit" => "Editing",
-                            "Grep" => "Searching",
-                            "Glob" => "Finding",
-                            _ => "Working on",
-                        },
-                        filename
-                    )
+                    format!("{} {}", action_verb(&tool_name).unwrap_or("Working on"), filename)
                 };
 
-                let resolved = fs_state
-                    .model
-                    .get_node_by_path(&canonical)
-                    .map(|(idx, _)| (idx, calculate_galaxy_position(&fs_state.model, idx)));
+                let resolved = fs_state.model.get_node_by_path(&canonical).map(|(idx, _)| idx);
+
+                let needs_agent = !registry.map.contains_key(&session_id);
+                if resolved.is_some()
+                    && needs_agent
+                    && !registry.has_free_slot()
+                    && longest_idle_agent(&registry, &agents).is_none()
+                {
+                    // At the cap with every agent busy: wait for a slot
+                    registry.wait_for_slot(AgentEvent::ToolUse {
+                        session_id,
+                        tool_name,
+                        file_path,
+                        reason,
+                        timestamp,
+                    });
+                    continue;
+                }
 
-                if let Some((node_idx, position)) = resolved {
+                if let Some(node_idx) = resolved {
                     // Record event in history
-                    let events = event_history.map.entry(node_idx).or_default();
-                    events.push(FileEvent {
+                    let file_event = FileEvent {
                         tool_name: tool_name.clone(),
+                        file_path: canonical.clone(),
                         session_id: session_id.clone(),
                         reason: reason.clone(),
                         timestamp: timestamp.clone(),
-                    });
-                    if events.len() > 10 {
-                        events.remove(0);
-                    }
+                        repeat: 1,
+                    };
+                    event_history.record(node_idx, file_event.clone());
 
                     // Get or create agent
                     let entity = if let Some(&entity) = registry.map.get(&session_id) {
-                        // Cancel despawn if needed
                         if let Ok(mut agent) = agents.get_mut(entity) {
-                            if matches!(agent.state, AgentState::Despawning { .. }) {
-                                agent.state = AgentState::Idle { timer: 0.0 };
-                            }
-                            agent.event_queue.push_back(AgentAction::MoveTo {
-                                position,
-                                node_index: node_idx,
-                            });
-                            agent.current_action = Some(action_desc.clone());
+                            receive_tool_use(&mut agent, node_idx, action_desc.clone(), file_event, now);
                         }
                         Some(entity)
                     } else {
@@ -446,23 +1092,46 @@ pub fn process_ws_events(
                         );
 
                         let mut queue = VecDeque::new();
-                        queue.push_back(AgentAction::MoveTo {
-                            position,
-                            node_index: node_idx,
-                        });
-
-                        // Assign Greek symbol based on order
-                        let greek_symbol = GREEK_SYMBOLS[registry.session_id_order.len() % GREEK_SYMBOLS.len()].to_string();
-
-                        let entity = spawn_agent_entity(
-                            &mut commands,
-                            &asset_server,
-                            &mut meshes,
-                            &mut materials,
-                            session_id.clone(),
-                            queue,
-                            greek_symbol,
-                        );
+                        queue.push_back(AgentAction::MoveTo { node_index: node_idx });
+
+                        let greek_symbol = next_greek_symbol(&session_id, &agents, &mut new_symbols);
+                        let recycled = if registry.has_free_slot() {
+                            None
+                        } else {
+                            recyclable_agent(&registry, &mut agents)
+                        };
+                        let entity = match recycled {
+                            Some((entity, mut agent)) => {
+                                recycle_agent(
+                                    &mut commands,
+                                    &mut registry,
+                                    &mut agent,
+                                    entity,
+                                    session_id.clone(),
+                                    greek_symbol,
+                                    queue,
+                                );
+                                entity
+                            }
+                            None => {
+                                spawn_agent_entity(
+                                    &mut commands,
+                                    &asset_server,
+                                    &mut meshes,
+                                    &mut materials,
+                                    session_id.clone(),
+                                    queue,
+                                    greek_symbol,
+                                    None,
+                                )
+                            }
+                        };
+
+                        // The Agent component is only inserted once commands apply
+                        commands
+                            .entity(entity)
+                            .entry::<Agent>()
+                            .and_modify(move |mut agent| push_agent_history(&mut agent, file_event, now));
 
                         registry.session_id_order.push(session_id.clone());
                         registry.map.insert(session_id.clone(), entity);
@@ -476,21 +1145,164 @@ pub fn process_ws_events(
                         }
                     }
                 } else {
-                    // File is outside the watched directory — silently skip
+                    // Not in the model yet: the file may have just been created and the
+                    // watcher hasn't caught up. Retry for a few seconds, then drop it
+                    // (files outside the watched directory never resolve)
+                    pending.map.entry(pending_path(canonical)).or_default().push(PendingToolUse {
+                        event: AgentEvent::ToolUse {
+                            session_id,
+                            tool_name,
+                            file_path,
+                            reason,
+                            timestamp,
+                        },
+                        age: 0.0,
+                    });
                 }
             }
         }
     }
 }
 
+/// The longest-idle agent, ready to hand to recycle_agent
+fn recyclable_agent<'a>(
+    registry: &AgentRegistry,
+    agents: &'a mut Query<&mut Agent>,
+) -> Option<(Entity, Mut<'a, Agent>)> {
+    let entity = longest_idle_agent(registry, agents)?;
+    let agent = agents.get_mut(entity).ok()?;
+    Some((entity, agent))
+}
+
+/// The idle agent (empty queue) that has been waiting the longest
+fn longest_idle_agent(registry: &AgentRegistry, agents: &Query<&mut Agent>) -> Option<Entity> {
+    registry
+        .map
+        .values()
+        .filter_map(|&entity| {
+            let agent = agents.get(entity).ok()?;
+            match agent.state {
+                AgentState::Idle { timer } if agent.event_queue.is_empty() => Some((entity, timer)),
+                _ => None,
+            }
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+/// Where a new ship appears: a point on a ring above the galaxy picked by its
+/// symbol, which live agents never share. Stepping by the golden angle keeps
+/// neighbouring letters well apart around the ring.
+fn spawn_origin(greek_symbol: &str) -> Vec3 {
+    const GOLDEN_ANGLE: f32 = 2.399_963; // radians, π(3 - √5)
+    let slot = GREEK_SYMBOLS.iter().position(|s| *s == greek_symbol).unwrap_or(0);
+    let angle = slot as f32 * GOLDEN_ANGLE;
+    Vec3::new(angle.cos() * SPAWN_RING_RADIUS, SPAWN_HEIGHT, angle.sin() * SPAWN_RING_RADIUS)
+}
+
+/// Symbol for a new session, avoiding live agents and ones handed out earlier this frame
+fn next_greek_symbol(session_id: &str, agents: &Query<&mut Agent>, new_symbols: &mut Vec<String>) -> String {
+    let taken = agents
+        .iter()
+        .map(|agent| agent.greek_symbol.as_str())
+        .chain(new_symbols.iter().map(String::as_str));
+    let symbol = greek_symbol_for(session_id, taken);
+    new_symbols.push(symbol.clone());
+    symbol
+}
+
+/// Hand an idle agent's spaceship to a new session instead of spawning another
+fn recycle_agent(
+    commands: &mut Commands,
+    registry: &mut AgentRegistry,
+    agent: &mut Agent,
+    entity: Entity,
+    session_id: String,
+    greek_symbol: String,
+    event_queue: VecDeque<AgentAction>,
+) {
+    println!(
+        "[agent] Recycling agent of session {} for session {}",
+        agent.session_id, session_id
+    );
+    registry.map.remove(&agent.session_id);
+    registry.session_cwds.remove(&agent.session_id);
+
+    agent.color = generate_agent_color(&session_id);
+    agent.idle_phase = idle_phase_for(&session_id);
+    agent.greek_symbol = greek_symbol;
+    agent.session_id = session_id;
+    agent.event_queue = event_queue;
+    agent.state = AgentState::Spawning { timer: 0.0 };
+    agent.current_target_file = None;
+    agent.current_action = None;
+    agent.status = None;
+    agent.history.clear();
+    agent.action_count = 0;
+    agent.recent_actions.clear();
+    agent.touched_files.clear();
+    agent.model = None;
+
+    // Re-tint the ship, nameplate and trail with the new color
+    commands.entity(entity).insert(UnprocessedSpaceship);
+}
+
+/// Queue a move, skipping repeats of the last queued node and dropping the oldest
+/// moves once the queue is full
+fn enqueue_move(queue: &mut VecDeque<AgentAction>, node_index: usize) {
+    if let Some(AgentAction::MoveTo { node_index: last }) = queue.back()
+        && *last == node_index
+    {
+        return;
+    }
+    queue.push_back(AgentAction::MoveTo { node_index });
+    while queue.len() > MAX_QUEUED_MOVES {
+        queue.pop_front();
+    }
+}
+
+/// Hand a live agent its next tool use. New activity restarts the idle
+/// countdown and cancels a despawn.
+fn receive_tool_use(
+    agent: &mut Agent,
+    node_index: usize,
+    action_desc: String,
+    file_event: FileEvent,
+    now: f32,
+) {
+    if matches!(agent.state, AgentState::Idle { .. } | AgentState::Despawning { .. }) {
+        agent.state = AgentState::Idle { timer: 0.0 };
+    }
+    enqueue_move(&mut agent.event_queue, node_index);
+    agent.current_action = Some(action_desc);
+    agent.status = None;
+    push_agent_history(agent, file_event, now);
+}
+
+fn push_agent_history(agent: &mut Agent, event: FileEvent, now: f32) {
+    agent.action_count += 1;
+    agent.recent_actions.push_back(now);
+    while agent.recent_actions.front().is_some_and(|&at| now - at > RECENT_ACTIVITY_WINDOW) {
+        agent.recent_actions.pop_front();
+    }
+    agent.touched_files.insert(event.file_path.clone());
+    agent.history.push_back(event);
+    if agent.history.len() > AGENT_HISTORY_LEN {
+        agent.history.pop_front();
+    }
+}
+
 // --- System 2: Agent state machine ---
 
 pub fn agent_state_machine(
     time: Res<Time>,
+    settings: Res<Settings>,
+    fs_state: Res<FileSystemState>,
     mut agents: Query<(&mut Agent, &Transform)>,
     mut arrived_events: MessageWriter<AgentArrivedEvent>,
 ) {
     let dt = time.delta_secs();
+    let model = &fs_state.model;
 
     for (mut agent, transform) in agents.iter_mut() {
         match agent.state.clone() {
@@ -504,17 +1316,19 @@ pub fn agent_state_machine(
                 }
             }
             AgentState::Idle { timer } => {
-                // Try to pop next action
-                if let Some(action) = agent.event_queue.pop_front() {
+                // Try to pop next action, dropping moves to files deleted since they were queued
+                let next_action = std::iter::from_fn(|| agent.event_queue.pop_front()).find(|action| match action {
+                    AgentAction::MoveTo { node_index } => model.get_node(*node_index).is_some(),
+                });
+                if let Some(action) = next_action {
                     match action {
-                        AgentAction::MoveTo {
-                            position,
-                            node_index,
-                        } => {
+                        AgentAction::MoveTo { node_index } => {
+                            // Files inside a collapsed directory send the agent to that directory's star
+                            let to = model.position(model.visible_ancestor(node_index));
                             agent.current_target_file = Some(node_index);
                             agent.state = AgentState::Moving {
                                 from: transform.translation,
-                                to: position,
+                                to,
                                 progress: 0.0,
                                 target_node: node_index,
                             };
@@ -523,9 +1337,10 @@ pub fn agent_state_machine(
                 } else {
                     // No actions, increment idle timer
                     let new_timer = timer + dt;
-                    if new_timer >= IDLE_TIMEOUT {
+                    if new_timer >= idle_timeout(&settings.agents, &agent, time.elapsed_secs()) {
                         agent.state = AgentState::Despawning { timer: 0.0 };
                         agent.current_action = None; // Clear action when starting to despawn
+                        agent.status = None;
                     } else {
                         agent.state = AgentState::Idle { timer: new_timer };
                     }
@@ -537,7 +1352,14 @@ pub fn agent_state_machine(
                 progress,
                 target_node,
             } => {
-                let new_progress = progress + dt / MOVE_SPEED;
+                // Target deleted mid-flight: stop where we are and pick up the next action
+                if model.get_node(target_node).is_none() {
+                    agent.current_target_file = None;
+                    agent.state = AgentState::Idle { timer: 0.0 };
+                    continue;
+                }
+
+                let new_progress = progress + dt / move_duration(&settings.agents, agent.event_queue.len());
                 if new_progress >= 1.0 {
                     // Arrived
                     agent.current_target_file = Some(target_node);
@@ -587,18 +1409,55 @@ impl AgentState {
     }
 }
 
+/// Seconds an agent may idle before despawning: the base timeout plus a little
+/// for each tool use in the last RECENT_ACTIVITY_WINDOW, within the configured bounds
+fn idle_timeout(settings: &AgentSettings, agent: &Agent, now: f32) -> f32 {
+    let recent = agent
+        .recent_actions
+        .iter()
+        .filter(|&&at| now - at <= RECENT_ACTIVITY_WINDOW)
+        .count();
+    let extended = settings.idle_timeout + recent as f32 * settings.idle_timeout_per_action;
+    extended.clamp(settings.min_idle_timeout, settings.max_idle_timeout)
+}
+
+/// Seconds per hop: agents with a backlog fly faster (up to
+/// MAX_CATCH_UP_SPEEDUP) and slow back down as the queue drains
+fn move_duration(settings: &AgentSettings, queue_len: usize) -> f32 {
+    let speedup = (1.0 + queue_len as f32 * CATCH_UP_PER_QUEUED).min(MAX_CATCH_UP_SPEEDUP);
+    settings.move_duration / speedup
+}
+
 // --- System 3: Agent transform (position + scale interpolation) ---
 
-pub fn agent_transform_system(mut agents: Query<(&Agent, &mut Transform)>) {
-    for (agent, mut transform) in agents.iter_mut() {
+pub fn agent_transform_system(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut agents: Query<(&mut Agent, &mut Transform)>,
+) {
+    let agent_scale = settings.agents.scale;
+    for (mut agent, mut transform) in agents.iter_mut() {
+        // Bob is applied as a delta, so take the previous frame's back out first
+        // (outside Idle the offset settles back to zero)
+        let bob = if matches!(agent.state, AgentState::Idle { .. }) {
+            (time.elapsed_secs() * IDLE_BOB_SPEED + agent.idle_phase).sin() * IDLE_BOB_HEIGHT
+        } else {
+            0.0
+        };
+        if !matches!(agent.state, AgentState::Moving { .. }) {
+            transform.translation.y += bob - agent.idle_bob;
+        }
+        agent.idle_bob = bob;
+
         match &agent.state {
             AgentState::Spawning { timer } => {
                 let t = (*timer / SPAWN_DURATION).clamp(0.0, 1.0);
                 let eased = ease_in_out_cubic(t);
-                transform.scale = Vec3::splat(eased * AGENT_SCALE);
+                transform.scale = Vec3::splat(eased * agent_scale);
             }
             AgentState::Idle { .. } => {
-                transform.scale = Vec3::splat(AGENT_SCALE);
+                transform.scale = Vec3::splat(agent_scale);
+                transform.rotate_y(IDLE_YAW_SPEED * time.delta_secs());
             }
             AgentState::Moving {
                 from,
@@ -608,21 +1467,114 @@ pub fn agent_transform_system(mut agents: Query<(&Agent, &mut Transform)>) {
             } => {
                 let t = ease_in_out_cubic(*progress);
                 transform.translation = from.lerp(*to, t);
-                transform.scale = Vec3::splat(AGENT_SCALE);
+                transform.scale = Vec3::splat(agent_scale);
 
-                // Make spaceship face movement direction
-                let direction = (*to - *from).normalize();
+                // Turn smoothly toward the movement direction
+                let direction = (*to - *from).normalize_or_zero();
                 if direction.length_squared() > 0.001 {
-                    // Calculate rotation to face direction (assuming spaceship faces +Z by default)
-                    let target_rotation = Quat::from_rotation_arc(Vec3::Z, direction);
-                    transform.rotation = target_rotation;
+                    // Yaw and pitch only, so the ship never rolls (it faces +Z by default)
+                    let yaw = direction.x.atan2(direction.z);
+                    let pitch = -direction.y.clamp(-1.0, 1.0).asin();
+                    let target_rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+                    let blend = 1.0 - (-TURN_DAMPING * time.delta_secs()).exp();
+                    transform.rotation = transform.rotation.slerp(target_rotation, blend);
                 }
             }
             AgentState::Despawning { timer } => {
                 let t = (*timer / DESPAWN_DURATION).clamp(0.0, 1.0);
                 let eased = ease_in_out_cubic(t);
-                transform.scale = Vec3::splat((1.0 - eased) * AGENT_SCALE);
+                transform.scale = Vec3::splat((1.0 - eased) * agent_scale);
+            }
+        }
+    }
+}
+
+// --- System 3a: Intent lines ---
+
+const TARGET_LINE_ALPHA: f32 = 0.35;
+
+/// Faint line from each agent to the file it's heading for
+pub fn draw_agent_target_lines(
+    mut gizmos: Gizmos,
+    fs_state: Res<FileSystemState>,
+    spotlight: Res<Spotlight>,
+    agents: Query<(&Agent, &Transform)>,
+    stars: Query<&Transform, With<FileStar>>,
+) {
+    for (agent, transform) in agents.iter() {
+        if spotlight.dims(agent) {
+            continue;
+        }
+        let target = match agent.state {
+            AgentState::Moving { target_node, .. } => Some(target_node),
+            AgentState::Spawning { .. } | AgentState::Idle { .. } if !agent.event_queue.is_empty() => {
+                agent.current_target_file
             }
+            _ => None,
+        };
+        let Some(star_transform) = target
+            .and_then(|idx| fs_state.entity_map.get(&idx))
+            .and_then(|&entity| stars.get(entity).ok())
+        else {
+            continue;
+        };
+
+        gizmos.line(
+            transform.translation,
+            star_transform.translation,
+            agent.color.with_alpha(TARGET_LINE_ALPHA),
+        );
+    }
+}
+
+// --- System 3b: Motion trails ---
+
+pub fn spawn_agent_trails(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut agents: Query<(Entity, &Agent, &Transform, &mut AgentTrail)>,
+) {
+    let dt = time.delta_secs();
+
+    for (entity, agent, transform, mut trail) in agents.iter_mut() {
+        if !matches!(agent.state, AgentState::Moving { .. }) {
+            trail.timer = 0.0;
+            continue;
+        }
+
+        trail.timer += dt;
+        if trail.timer < TRAIL_SPAWN_INTERVAL {
+            continue;
+        }
+        trail.timer = 0.0;
+
+        commands.spawn((
+            TrailPoint {
+                agent_entity: entity,
+                age: 0.0,
+            },
+            Mesh3d(trail.mesh.clone()),
+            MeshMaterial3d(trail.material.clone()),
+            Transform::from_translation(transform.translation),
+        ));
+    }
+}
+
+pub fn fade_trail_points(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut points: Query<(Entity, &mut TrailPoint, &mut Transform)>,
+) {
+    let dt = time.delta_secs();
+
+    for (entity, mut point, mut transform) in points.iter_mut() {
+        point.age += dt;
+        if point.age >= TRAIL_LIFETIME {
+            commands.entity(entity).despawn();
+        } else {
+            // Shrink away over the trail's lifetime
+            let remaining = 1.0 - point.age / TRAIL_LIFETIME;
+            transform.scale = Vec3::splat(remaining * remaining);
         }
     }
 }
@@ -633,13 +1585,20 @@ pub fn agent_despawn_system(
     mut commands: Commands,
     agents: Query<(Entity, &Agent)>,
     mut registry: ResMut<AgentRegistry>,
+    mut finished_events: MessageWriter<AgentFinishedEvent>,
 ) {
     for (entity, agent) in agents.iter() {
         if let AgentState::Despawning { timer } = &agent.state {
             if *timer >= DESPAWN_DURATION {
                 println!("[agent] Despawning agent for session {}", agent.session_id);
                 registry.map.remove(&agent.session_id);
+                registry.session_cwds.remove(&agent.session_id);
                 commands.entity(entity).despawn();
+                finished_events.write(AgentFinishedEvent {
+                    greek_symbol: agent.greek_symbol.clone(),
+                    color: agent.color,
+                    action_count: agent.action_count,
+                });
             }
         }
     }
@@ -652,37 +1611,108 @@ pub fn file_highlight_system(
     mut arrived_events: MessageReader<AgentArrivedEvent>,
     mut commands: Commands,
     fs_state: Res<FileSystemState>,
-    _stars: Query<(Entity, &FileStar, &MeshMaterial3d<StandardMaterial>)>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut highlights: Query<(Entity, &mut FileHighlight, &MeshMaterial3d<StandardMaterial>)>,
+    mut planet_materials: ResMut<Assets<PlanetMaterial>>,
+    mut highlights: Query<(Entity, &mut FileHighlight, &MeshMaterial3d<PlanetMaterial>)>,
 ) {
     let dt = time.delta_secs();
 
     // Boost stars on arrival
     for event in arrived_events.read() {
         if let Some(&star_entity) = fs_state.entity_map.get(&event.node_index) {
-            // Add or refresh highlight
+            // Add or refresh highlight, keeping the emissive captured when it started
             if let Ok((_entity, mut highlight, _mat)) = highlights.get_mut(star_entity) {
                 highlight.intensity = 6.0;
+                highlight.tint = None;
             } else {
-                commands.entity(star_entity).insert(FileHighlight { intensity: 6.0 });
+                commands.entity(star_entity).insert(FileHighlight::new(6.0, None));
             }
         }
     }
 
     // Decay highlights
     for (entity, mut highlight, mat_handle) in highlights.iter_mut() {
+        let Some(material) = planet_materials.get_mut(&mat_handle.0) else {
+            continue;
+        };
+        let base_emissive = *highlight.base_emissive.get_or_insert(material.base.emissive);
+
         highlight.intensity -= dt * 1.5;
         if highlight.intensity <= 0.0 {
-            // Remove highlight and restore original material
+            // Remove highlight and restore original glow
+            material.base.emissive = base_emissive;
             commands.entity(entity).remove::<FileHighlight>();
         } else {
             // Boost emissive on the material
-            if let Some(material) = materials.get_mut(mat_handle) {
-                let base = material.base_color;
-                let base_linear = LinearRgba::from(base);
-                material.emissive = base_linear * (2.0 + highlight.intensity);
-            }
+            let color = highlight.tint.unwrap_or(LinearRgba::from(material.base.base_color));
+            material.base.emissive = color * (2.0 + highlight.intensity);
+        }
+    }
+}
+
+// --- System 5b: Pulse rings ---
+
+pub fn spawn_pulse_rings(
+    mut commands: Commands,
+    mut arrived_events: MessageReader<AgentArrivedEvent>,
+    fs_state: Res<FileSystemState>,
+    stars: Query<(&Transform, &MeshMaterial3d<PlanetMaterial>), With<FileStar>>,
+    planet_materials: Res<Assets<PlanetMaterial>>,
+    ring_mesh: Res<PulseRingMesh>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for event in arrived_events.read() {
+        let Some((star_transform, star_material)) = fs_state
+            .entity_map
+            .get(&event.node_index)
+            .and_then(|&entity| stars.get(entity).ok())
+        else {
+            continue;
+        };
+        let color = planet_materials
+            .get(&star_material.0)
+            .map_or(Color::WHITE, |material| material.base.base_color);
+
+        commands.spawn((
+            PulseRing {
+                age: 0.0,
+                color: LinearRgba::from(color),
+            },
+            Mesh3d(ring_mesh.0.clone()),
+            // Each ring fades on its own, so it needs its own material
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: color.with_alpha(PULSE_RING_ALPHA),
+                emissive: LinearRgba::from(color) * PULSE_RING_EMISSIVE,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })),
+            Transform::from_translation(star_transform.translation)
+                .with_scale(Vec3::splat(PULSE_RING_START_RADIUS)),
+        ));
+    }
+}
+
+pub fn animate_pulse_rings(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut rings: Query<(Entity, &mut PulseRing, &mut Transform, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, mut ring, mut transform, mat_handle) in rings.iter_mut() {
+        ring.age += time.delta_secs();
+        let t = ring.age / PULSE_RING_DURATION;
+        if t >= 1.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        // Fast at first, easing out as it reaches full size, fading all the way
+        let eased = 1.0 - (1.0 - t).powi(3);
+        transform.scale = Vec3::splat(PULSE_RING_START_RADIUS.lerp(PULSE_RING_END_RADIUS, eased));
+        if let Some(material) = materials.get_mut(&mat_handle.0) {
+            let fade = 1.0 - t;
+            material.base_color = Color::from(ring.color).with_alpha(PULSE_RING_ALPHA * fade);
+            material.emissive = ring.color * PULSE_RING_EMISSIVE * fade;
         }
     }
 }
@@ -691,22 +1721,37 @@ pub fn file_highlight_system(
 
 pub fn process_spaceship_materials(
     mut commands: Commands,
-    unprocessed: Query<(Entity, &Children, &Agent), With<UnprocessedSpaceship>>,
+    unprocessed: Query<(Entity, &Children, &Agent, &AgentTrail), With<UnprocessedSpaceship>>,
     children_query: Query<&Children>,
-    mut mesh_query: Query<&mut MeshMaterial3d<StandardMaterial>>,
+    mut mesh_query: Query<(&mut MeshMaterial3d<StandardMaterial>, Option<&SpaceshipPart>)>,
+    nameplates: Query<(&AgentNameplate, &MeshMaterial3d<StandardMaterial>)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    for (entity, children, agent) in unprocessed.iter() {
+    for (entity, children, agent, trail) in unprocessed.iter() {
         // Get the agent's unique color
         let agent_color = LinearRgba::from(agent.color);
 
+        // Nameplate and trail carry the color too (matters when a ship is recycled)
+        for (nameplate, mat_handle) in nameplates.iter() {
+            if nameplate.agent_entity == entity
+                && let Some(material) = materials.get_mut(&mat_handle.0)
+            {
+                material.base_color = agent.color;
+                material.emissive = agent_color * NAMEPLATE_EMISSIVE;
+            }
+        }
+        if let Some(material) = materials.get_mut(&trail.material) {
+            material.base_color = agent.color;
+            material.emissive = agent_color * TRAIL_EMISSIVE;
+        }
+
         // Recursively traverse all descendants
         let mut stack: Vec<Entity> = children.to_vec();
         let mut processed_any = false;
 
         while let Some(child) = stack.pop() {
             // Check if this child has a material
-            if let Ok(mut mat_handle) = mesh_query.get_mut(child) {
+            if let Ok((mut mat_handle, part)) = mesh_query.get_mut(child) {
                 if let Some(original_material) = materials.get(&mat_handle.0) {
                     // Clone the material to create a unique instance for this agent
                     let mut new_material = original_material.clone();
@@ -720,15 +1765,19 @@ pub fn process_spaceship_materials(
                     // Set emissive to make it glow, but reduce bloom on very bright parts (antennae)
                     // If the material already had high emissive (antennae), reduce it to 3x
                     // Otherwise use 5x for the body to make it bright
-                    let current_emissive_intensity =
-                        new_material.emissive.red.max(new_material.emissive.green).max(new_material.emissive.blue);
+                    let is_glow = part.map(|p| p.is_glow).unwrap_or_else(|| {
+                        let current_emissive_intensity =
+                            new_material.emissive.red.max(new_material.emissive.green).max(new_material.emissive.blue);
+                        current_emissive_intensity > 5.0
+                    });
+                    commands.entity(child).insert(SpaceshipPart { is_glow });
 
-                    let emissive_multiplier = if current_emissive_intensity > 5.0 {
+                    let emissive_multiplier = if is_glow {
                         // This is likely an antenna or other glowing part - tone it down
-                        2.0
+                        SHIP_GLOW_EMISSIVE
                     } else {
                         // Regular body - make it bright
-                        8.0
+                        SHIP_BODY_EMISSIVE
                     };
 
                     new_material.emissive = agent_color * emissive_multiplier;
@@ -754,24 +1803,119 @@ pub fn process_spaceship_materials(
     }
 }
 
+// --- System 6b: Spotlight ---
+
+/// Dim every agent outside the spotlight: ship, nameplate and trail. Runs when the
+/// spotlight moves or a ship's materials are (re)built.
+pub fn apply_agent_spotlight(
+    mut spotlight: ResMut<Spotlight>,
+    agents: Query<(Entity, &Agent, &AgentTrail)>,
+    rebuilt_parts: Query<(), Changed<SpaceshipPart>>,
+    children_query: Query<&Children>,
+    parts: Query<(&SpaceshipPart, &MeshMaterial3d<StandardMaterial>)>,
+    nameplates: Query<(&AgentNameplate, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // The spotlit session ended (or its ship was recycled for another one)
+    let stale = spotlight
+        .0
+        .as_ref()
+        .is_some_and(|session_id| !agents.iter().any(|(_, agent, _)| agent.session_id == *session_id));
+    if stale {
+        spotlight.0 = None;
+    }
+    if !spotlight.is_changed() && rebuilt_parts.is_empty() {
+        return;
+    }
+
+    for (entity, agent, trail) in agents.iter() {
+        let scale = if spotlight.dims(agent) { SPOTLIGHT_AGENT_DIM } else { 1.0 };
+        let agent_color = LinearRgba::from(agent.color);
+        let base_color = Color::from(agent_color * scale);
+
+        if let Some(material) = materials.get_mut(&trail.material) {
+            material.base_color = base_color;
+            material.emissive = agent_color * TRAIL_EMISSIVE * scale;
+        }
+        for (nameplate, mat_handle) in nameplates.iter() {
+            if nameplate.agent_entity == entity
+                && let Some(material) = materials.get_mut(&mat_handle.0)
+            {
+                material.base_color = base_color;
+                material.emissive = agent_color * NAMEPLATE_EMISSIVE * scale;
+            }
+        }
+        for child in children_query.iter_descendants(entity) {
+            let Ok((part, mat_handle)) = parts.get(child) else {
+                continue;
+            };
+            if let Some(material) = materials.get_mut(&mat_handle.0) {
+                let multiplier = if part.is_glow { SHIP_GLOW_EMISSIVE } else { SHIP_BODY_EMISSIVE };
+                material.base_color = base_color;
+                material.emissive = agent_color * multiplier * scale;
+            }
+        }
+    }
+}
+
+/// Swap in the matching ship when an agent learns its model late
+/// (auto-spawned before its session_start, or recycled for a new session)
+pub fn update_spaceship_scenes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    agents: Query<(Entity, &Agent, &Children), Changed<Agent>>,
+    mut scenes: Query<(&mut SceneRoot, &mut Transform, &mut ShipScene)>,
+) {
+    for (entity, agent, children) in agents.iter() {
+        let ship = ship_for_model(agent.model.as_deref());
+        for &child in children {
+            let Ok((mut scene_root, mut transform, mut current)) = scenes.get_mut(child) else {
+                continue;
+            };
+            if current.scene == ship.scene {
+                continue;
+            }
+            scene_root.0 = asset_server.load(ship.scene);
+            transform.scale = Vec3::splat(ship.scale);
+            current.scene = ship.scene;
+            // The new scene's meshes need the agent's color too
+            commands.entity(entity).insert(UnprocessedSpaceship);
+        }
+    }
+}
+
 pub fn update_agent_nameplates(
     camera_query: Query<&GlobalTransform, With<Camera3d>>,
-    agents: Query<&GlobalTransform, With<Agent>>,
-    mut nameplates: Query<(&mut Transform, &AgentNameplate)>,
+    agents: Query<(&Agent, &GlobalTransform)>,
+    mut nameplates: Query<(&mut Transform, &mut TextMesh, &AgentNameplate)>,
 ) {
     let Ok(camera_transform) = camera_query.single() else {
         return;
     };
     let (_, camera_rotation, _) = camera_transform.to_scale_rotation_translation();
 
-    for (mut transform, nameplate) in nameplates.iter_mut() {
-        if let Ok(agent_transform) = agents.get(nameplate.agent_entity) {
+    for (mut transform, mut text_mesh, nameplate) in nameplates.iter_mut() {
+        if let Ok((agent, agent_transform)) = agents.get(nameplate.agent_entity) {
             transform.translation = agent_transform.translation() + nameplate.offset;
             transform.rotation = camera_rotation;
+
+            // Only touch the text when it changes, since that rebuilds the mesh
+            let text = nameplate_text(agent);
+            if text_mesh.text != text {
+                text_mesh.text = text;
+            }
         }
     }
 }
 
+fn nameplate_text(agent: &Agent) -> String {
+    format!(
+        "Agent {} ({})",
+        agent.greek_symbol,
+        agent.model.as_deref().unwrap_or("unknown model")
+    )
+}
+
 pub fn update_agent_action_bubble_transforms(
     camera_query: Query<&GlobalTransform, With<Camera3d>>,
     agents: Query<(&Agent, &GlobalTransform)>,
@@ -798,12 +1942,13 @@ pub fn update_agent_action_bubble_transforms(
 }
 
 pub fn update_agent_action_bubble_content(
+    settings: Res<ActionBubbleSettings>,
     agents: Query<&Agent>,
-    bubbles: Query<(&AgentActionBubble, &Children)>,
+    mut bubbles: Query<(&mut AgentActionBubble, &Children)>,
     mut text_query: Query<&mut TextMesh, With<AgentActionText>>,
     mut bg_query: Query<&mut Transform, With<AgentActionBackground>>,
 ) {
-    for (bubble, children) in bubbles.iter() {
+    for (mut bubble, children) in bubbles.iter_mut() {
         let Ok(agent) = agents.get(bubble.agent_entity) else {
             continue;
         };
@@ -811,27 +1956,87 @@ pub fn update_agent_action_bubble_content(
         let Some(action) = agent.current_action.as_deref() else {
             continue;
         };
+        // Wrapping and measuring only needs redoing when the text or the width limit changes
+        if bubble.shown_action.as_deref() == Some(action) && !settings.is_changed() {
+            continue;
+        }
+        bubble.shown_action = Some(action.to_string());
 
-        let width = bubble_width_for_text(action);
+        let text = wrap_action_text(action, settings.max_width);
+        let width = bubble_width_for_text(&text);
+        let height = bubble_height_for_text(&text);
 
         for child in children.iter() {
             if let Ok(mut text_mesh) = text_query.get_mut(child) {
-                if text_mesh.text != action {
-                    text_mesh.text = action.to_string();
-                }
+                text_mesh.text = text.clone();
             }
             if let Ok(mut bg_transform) = bg_query.get_mut(child) {
-                bg_transform.scale = Vec3::new(width, ACTION_BUBBLE_HEIGHT, 1.0);
+                bg_transform.scale = Vec3::new(width, height, 1.0);
             }
         }
     }
 }
 
+/// Give each new ship a hidden status ring, on the unit ring the pulses share
+pub fn spawn_agent_status_rings(
+    mut commands: Commands,
+    new_agents: Query<Entity, Added<Agent>>,
+    ring_mesh: Res<PulseRingMesh>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for agent_entity in new_agents.iter() {
+        commands.spawn((
+            AgentStatusRing { agent_entity },
+            Mesh3d(ring_mesh.0.clone()),
+            // Each ring pulses on its own, so it needs its own material
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: STATUS_RING_COLOR,
+                emissive: LinearRgba::from(STATUS_RING_COLOR) * STATUS_RING_EMISSIVE,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })),
+            Transform::from_scale(Vec3::splat(STATUS_RING_RADIUS)),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+/// Show and pulse each ship's status ring while it has a status
+pub fn update_agent_status_rings(
+    time: Res<Time>,
+    agents: Query<(&Agent, &GlobalTransform)>,
+    mut rings: Query<(&AgentStatusRing, &mut Transform, &mut Visibility, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (ring, mut transform, mut visibility, mat_handle) in rings.iter_mut() {
+        let Ok((agent, agent_transform)) = agents.get(ring.agent_entity) else {
+            continue;
+        };
+        if agent.status.is_none() {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Visible;
+
+        let pulse = (time.elapsed_secs() * STATUS_RING_SPEED + agent.idle_phase).sin() * 0.5 + 0.5;
+        transform.translation = agent_transform.translation();
+        transform.scale = Vec3::splat(STATUS_RING_RADIUS * (1.0 + STATUS_RING_PULSE * pulse));
+        if let Some(material) = materials.get_mut(&mat_handle.0) {
+            let strength = 0.4 + 0.6 * pulse;
+            material.base_color = STATUS_RING_COLOR.with_alpha(strength);
+            material.emissive = LinearRgba::from(STATUS_RING_COLOR) * STATUS_RING_EMISSIVE * strength;
+        }
+    }
+}
+
 pub fn cleanup_agent_labels(
     mut commands: Commands,
     agents: Query<Entity, With<Agent>>,
     nameplates: Query<(Entity, &AgentNameplate)>,
     bubbles: Query<(Entity, &AgentActionBubble)>,
+    status_rings: Query<(Entity, &AgentStatusRing)>,
+    trail_points: Query<(Entity, &TrailPoint)>,
     children_query: Query<&Children>,
 ) {
     for (entity, nameplate) in nameplates.iter() {
@@ -855,6 +2060,18 @@ pub fn cleanup_agent_labels(
             commands.entity(entity).despawn();
         }
     }
+
+    for (entity, point) in trail_points.iter() {
+        if agents.get(point.agent_entity).is_err() {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for (entity, ring) in status_rings.iter() {
+        if agents.get(ring.agent_entity).is_err() {
+            commands.entity(entity).despawn();
+        }
+    }
 }
 
 // --- Picking observers for file star hover ---
@@ -881,3 +2098,290 @@ pub fn on_file_star_out(
         }
     }
 }
+
+// --- Picking observer for opening an agent's history panel ---
+
+pub fn on_agent_click(
+    event: On<Pointer<Click>>,
+    agents: Query<(), With<Agent>>,
+    mut selected: ResMut<SelectedAgent>,
+) {
+    if event.button != PointerButton::Primary {
+        return;
+    }
+    // Clicks on the collider or the ship model bubble up to the agent entity
+    if agents.contains(event.entity) {
+        selected.0 = Some(event.entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_model::{FileSystemModel, GitignoreChecker};
+    use crate::watcher::FileWatcherHandle;
+    use bevy::ecs::message::Messages;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    /// A world with just what agent_state_machine reads, over /repo/a.rs
+    fn state_machine_world() -> World {
+        let mut model = FileSystemModel::new();
+        model.add_node(PathBuf::from("/repo"), true);
+        model.add_node(PathBuf::from("/repo/a.rs"), false);
+
+        let root = PathBuf::from("/repo");
+        let (event_receiver, watcher_handle) = FileWatcherHandle::detached();
+
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(Settings::default());
+        world.insert_resource(FileSystemState {
+            model,
+            event_receiver,
+            entity_map: HashMap::new(),
+            gitignore_checker: GitignoreChecker::new(&root),
+            root_path: root,
+            _watcher_handle: watcher_handle,
+        });
+        world.init_resource::<Messages<AgentArrivedEvent>>();
+        world
+    }
+
+    fn test_agent(state: AgentState) -> Agent {
+        Agent {
+            session_id: "s1".to_string(),
+            event_queue: VecDeque::new(),
+            state,
+            current_target_file: None,
+            current_action: None,
+            status: None,
+            color: Color::WHITE,
+            greek_symbol: "α".to_string(),
+            history: VecDeque::new(),
+            model: None,
+            action_count: 0,
+            recent_actions: VecDeque::new(),
+            touched_files: HashSet::new(),
+            idle_phase: 0.0,
+            idle_bob: 0.0,
+        }
+    }
+
+    /// Advance the clock by `seconds` in `steps` frames, running the state machine each frame
+    fn run_for(world: &mut World, seconds: f32, steps: u32) {
+        for _ in 0..steps {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds / steps as f32));
+            world.run_system_once(agent_state_machine).unwrap();
+        }
+    }
+
+    fn read_event(session_id: &str) -> FileEvent {
+        FileEvent {
+            tool_name: "Read".to_string(),
+            file_path: PathBuf::from("/repo/src/main.rs"),
+            session_id: session_id.to_string(),
+            reason: None,
+            timestamp: None,
+            repeat: 1,
+        }
+    }
+
+    #[test]
+    fn same_tool_from_two_sessions_keeps_both_entries() {
+        let mut history = FileEventHistory::default();
+        history.record(3, read_event("a"));
+        history.record(3, read_event("b"));
+
+        let events = &history.map[&3];
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].session_id.as_str(), events[0].repeat), ("a", 1));
+        assert_eq!((events[1].session_id.as_str(), events[1].repeat), ("b", 1));
+    }
+
+    #[test]
+    fn same_tool_from_one_session_folds_into_one_entry() {
+        let mut history = FileEventHistory::default();
+        history.record(3, read_event("a"));
+        history.record(3, read_event("a"));
+
+        let events = &history.map[&3];
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].repeat, 2);
+    }
+
+    #[test]
+    fn a_session_still_touches_files_its_events_rolled_off() {
+        let mut history = FileEventHistory::default();
+        history.record(3, read_event("a"));
+        for i in 0..FILE_HISTORY_LEN {
+            history.record(3, read_event(&i.to_string()));
+        }
+
+        assert!(history.map[&3].iter().all(|e| e.session_id != "a"));
+        assert!(history.touched_by(Path::new("/repo/src/main.rs"), "a"));
+        assert!(!history.touched_by(Path::new("/repo/src/main.rs"), "b"));
+    }
+
+    #[test]
+    fn a_tool_use_counts_as_recent_for_a_few_seconds() {
+        let mut history = FileEventHistory::default();
+        history.record(3, read_event("a"));
+        assert!(history.recently_used(Path::new("/repo/src/main.rs")));
+
+        history.age_recent(RECENT_TOOL_USE_WINDOW);
+        assert!(!history.recently_used(Path::new("/repo/src/main.rs")));
+    }
+
+    #[test]
+    fn bash_tool_use_reads_as_running() {
+        assert_eq!(action_verb("Bash"), Some("Running"));
+    }
+
+    #[test]
+    fn task_and_webfetch_have_verbs() {
+        assert_eq!(action_verb("Task"), Some("Delegating"));
+        assert_eq!(action_verb("WebFetch"), Some("Fetching"));
+        assert_eq!(action_verb("SomethingNew"), None);
+    }
+
+    #[test]
+    fn deleting_the_target_mid_flight_returns_agent_to_idle() {
+        let mut world = state_machine_world();
+        let mut agent = test_agent(AgentState::Idle { timer: 0.0 });
+        enqueue_move(&mut agent.event_queue, 1);
+        let agent = world.spawn((agent, Transform::default())).id();
+
+        run_for(&mut world, 0.1, 2);
+        assert!(matches!(
+            world.get::<Agent>(agent).unwrap().state,
+            AgentState::Moving { target_node: 1, .. }
+        ));
+
+        world
+            .resource_mut::<FileSystemState>()
+            .model
+            .remove_node(Path::new("/repo/a.rs"));
+        // Long enough that the hop would have landed
+        run_for(&mut world, 2.0, 20);
+
+        let state = world.get::<Agent>(agent).unwrap();
+        assert!(matches!(state.state, AgentState::Idle { .. }));
+        assert_eq!(state.current_target_file, None);
+        assert!(world.resource::<Messages<AgentArrivedEvent>>().is_empty());
+    }
+
+    #[test]
+    fn event_just_before_timeout_keeps_agent_alive() {
+        let mut world = state_machine_world();
+        let agent = world
+            .spawn((test_agent(AgentState::Idle { timer: 0.0 }), Transform::default()))
+            .id();
+        let timeout = Settings::default().agents.idle_timeout;
+
+        run_for(&mut world, timeout - 0.1, 10);
+        let now = world.resource::<Time>().elapsed_secs();
+        let mut entity = world.entity_mut(agent);
+        let mut state = entity.get_mut::<Agent>().unwrap();
+        assert!(matches!(state.state, AgentState::Idle { .. }));
+        receive_tool_use(&mut state, 1, "Reading a.rs".to_string(), read_event("s1"), now);
+
+        // Past the original deadline: flying to the file, then idling afresh
+        run_for(&mut world, 1.0, 10);
+        assert!(!matches!(world.get::<Agent>(agent).unwrap().state, AgentState::Despawning { .. }));
+        run_for(&mut world, timeout, 50);
+        assert!(!matches!(world.get::<Agent>(agent).unwrap().state, AgentState::Despawning { .. }));
+
+        // With nothing more to do it leaves eventually
+        run_for(&mut world, 5.0, 10);
+        assert!(matches!(world.get::<Agent>(agent).unwrap().state, AgentState::Despawning { .. }));
+    }
+
+    #[test]
+    fn only_recent_tool_uses_extend_the_idle_timeout() {
+        let settings = Settings::default().agents;
+        let mut agent = test_agent(AgentState::Idle { timer: 0.0 });
+        for _ in 0..AGENT_HISTORY_LEN {
+            push_agent_history(&mut agent, read_event("s1"), 10.0);
+        }
+
+        assert_eq!(idle_timeout(&settings, &agent, 10.0), settings.max_idle_timeout);
+        let long_after = 10.0 + RECENT_ACTIVITY_WINDOW + 1.0;
+        assert_eq!(idle_timeout(&settings, &agent, long_after), settings.idle_timeout);
+    }
+
+    #[test]
+    fn pending_tool_use_waits_under_its_canonical_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let not_yet_written = dir.path().join(".").join("new.rs");
+        assert_eq!(pending_path(not_yet_written), dir.path().canonicalize().unwrap().join("new.rs"));
+    }
+
+    #[test]
+    fn only_created_paths_with_waiting_tool_uses_are_retried() {
+        let mut pending = PendingToolUses::default();
+        pending.map.insert(PathBuf::from("/repo/new.rs"), Vec::new());
+
+        pending.file_created(Path::new("/repo/other.rs"));
+        pending.file_created(Path::new("/repo/new.rs"));
+        assert_eq!(pending.created, vec![PathBuf::from("/repo/new.rs")]);
+    }
+
+    #[test]
+    fn waiting_queue_drops_the_oldest_events_past_its_cap() {
+        let mut registry = AgentRegistry::with_max_agents(1);
+        for i in 0..MAX_WAITING_EVENTS + 2 {
+            registry.wait_for_slot(AgentEvent::SessionEnd { session_id: i.to_string() });
+        }
+        assert_eq!(registry.waiting.len(), MAX_WAITING_EVENTS);
+        assert_eq!(registry.waiting.front().map(|e| e.session_id()), Some("2"));
+    }
+
+    /// Two checkouts of one project side by side, each with src/main.rs
+    fn two_checkouts() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().canonicalize().unwrap();
+        for checkout in ["watched", "other"] {
+            std::fs::create_dir_all(base.join(checkout).join(".git")).unwrap();
+            std::fs::create_dir_all(base.join(checkout).join("src")).unwrap();
+            std::fs::write(base.join(checkout).join("src/main.rs"), "").unwrap();
+        }
+        (dir, base.join("watched"), base.join("other"))
+    }
+
+    #[test]
+    fn relative_tool_paths_resolve_against_the_session_cwd() {
+        let (_dir, root, _) = two_checkouts();
+        let src = root.join("src");
+        assert_eq!(resolve_tool_path("main.rs", Some(&src), &root), src.join("main.rs"));
+        assert_eq!(resolve_tool_path("src/main.rs", None, &root), src.join("main.rs"));
+    }
+
+    #[test]
+    fn another_checkout_is_remapped_from_its_root() {
+        let (_dir, root, other) = two_checkouts();
+        let cwd = other.join("src");
+
+        // The session runs below the other checkout's root; the whole checkout maps over
+        let absolute = other.join("src/main.rs");
+        assert_eq!(resolve_tool_path(absolute.to_str().unwrap(), Some(&cwd), &root), root.join("src/main.rs"));
+        assert_eq!(resolve_tool_path("main.rs", Some(&cwd), &root), root.join("src/main.rs"));
+
+        // Paths outside that checkout are left alone
+        let outside = root.parent().unwrap().join("elsewhere.rs");
+        assert_eq!(resolve_tool_path(outside.to_str().unwrap(), Some(&cwd), &root), outside);
+    }
+
+    #[test]
+    fn a_watched_subdirectory_maps_to_the_same_subdirectory_elsewhere() {
+        let (_dir, root, other) = two_checkouts();
+        let watched_src = root.join("src");
+        let absolute = other.join("src/main.rs");
+        assert_eq!(
+            resolve_tool_path(absolute.to_str().unwrap(), Some(&other), &watched_src),
+            watched_src.join("main.rs")
+        );
+    }
+}
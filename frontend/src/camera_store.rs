@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::CameraMode;
+use crate::visit_store::{load_project, save_project};
+
+/// The camera view a project was last left in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct SavedCamera {
+    pub mode: CameraMode,
+    pub orbit_distance: f32,
+    pub orbit_angle: f32,
+    pub orbit_height: f32,
+    pub top_down_height: f32,
+    pub pan_target: [f32; 3],
+}
+
+impl SavedCamera {
+    /// Whether every number is usable; a hand-edited file could hold NaN or infinity
+    pub fn is_finite(&self) -> bool {
+        [self.orbit_distance, self.orbit_angle, self.orbit_height, self.top_down_height]
+            .into_iter()
+            .chain(self.pan_target)
+            .all(f32::is_finite)
+    }
+}
+
+const CAMERA_FILE: &str = "camera.json";
+
+/// The camera saved by the last run on this project, if any
+pub fn load_camera(root_path: &Path) -> Option<SavedCamera> {
+    load_project(CAMERA_FILE, root_path)
+}
+
+/// Replace this project's saved camera, leaving other projects untouched
+pub fn save_camera(root_path: &Path, camera: SavedCamera) {
+    save_project(CAMERA_FILE, root_path, camera);
+}
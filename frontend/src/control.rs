@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use crossbeam_channel::{Receiver, unbounded};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// A command from the --stdin-control channel, for driving the view from a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlCommand {
+    /// `focus <path>`: frame the camera on a file or directory
+    Focus(PathBuf),
+    /// `follow <session_id>`: select that session's agent and follow it
+    Follow(String),
+}
+
+#[derive(Resource)]
+pub struct ControlChannel {
+    pub receiver: Receiver<ControlCommand>,
+}
+
+/// Parse one command line; blank lines and `#` comments yield `None`
+fn parse_command(line: &str) -> Option<Result<ControlCommand, String>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (verb, arg) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let arg = arg.trim();
+    let command = match verb {
+        "focus" | "follow" if arg.is_empty() => Err(format!("{} needs an argument", verb)),
+        "focus" => Ok(ControlCommand::Focus(PathBuf::from(arg))),
+        "follow" => Ok(ControlCommand::Follow(arg.to_string())),
+        _ => Err(format!("unknown command {:?} (expected focus or follow)", verb)),
+    };
+    Some(command)
+}
+
+/// Read commands from stdin on a background thread until it closes
+pub fn start_stdin_control() -> ControlChannel {
+    let (tx, rx) = unbounded();
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let command = match parse_command(&line) {
+                Some(Ok(command)) => command,
+                Some(Err(e)) => {
+                    eprintln!("[control] {}", e);
+                    continue;
+                }
+                None => continue,
+            };
+            // The app has exited
+            if tx.send(command).is_err() {
+                break;
+            }
+        }
+    });
+    ControlChannel { receiver: rx }
+}
+
+/// A focus path as the file model keys it: relative paths are taken from the watched root
+pub fn resolve_focus_path(path: &Path, root: &Path) -> PathBuf {
+    let path = if path.is_relative() { root.join(path) } else { path.to_path_buf() };
+    path.canonicalize().unwrap_or(path)
+}
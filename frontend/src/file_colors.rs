@@ -0,0 +1,168 @@
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use ron::extensions::Extensions;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::fs_model::FileNode;
+use crate::galaxy::FileCategory;
+use crate::theme::Theme;
+
+/// Star colors by file extension, loaded from `assets/file_colors.ron` at startup
+#[derive(Resource, Debug, Clone)]
+pub struct FileColors {
+    pub directory: Color,
+    /// Files whose extension isn't listed
+    pub other: Color,
+    pub extensions: HashMap<String, Color>,
+}
+
+/// sRGB components as written in the RON file
+type Rgb = (f32, f32, f32);
+
+/// On-disk shape of file_colors.ron; every section is optional
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FileColorsFile {
+    directory: Option<Rgb>,
+    other: Option<Rgb>,
+    extensions: HashMap<String, Rgb>,
+}
+
+/// Extensions colored like their legend category
+const CATEGORY_EXTENSIONS: &[(&str, FileCategory)] = &[
+    ("rs", FileCategory::Rust),
+    ("toml", FileCategory::Config),
+    ("yaml", FileCategory::Config),
+    ("yml", FileCategory::Config),
+    ("json", FileCategory::Config),
+    ("md", FileCategory::Docs),
+    ("txt", FileCategory::Docs),
+    ("js", FileCategory::JavaScript),
+    ("ts", FileCategory::JavaScript),
+    ("tsx", FileCategory::JavaScript),
+    ("py", FileCategory::Python),
+    ("html", FileCategory::Web),
+    ("css", FileCategory::Web),
+    ("java", FileCategory::Compiled),
+    ("cpp", FileCategory::Compiled),
+    ("c", FileCategory::Compiled),
+    ("go", FileCategory::Go),
+];
+
+/// Languages with a color of their own
+const LANGUAGE_COLORS: &[(&str, Color)] = &[
+    ("rb", Color::srgb(1.0, 0.6, 0.65)),    // Pastel ruby
+    ("php", Color::srgb(0.75, 0.75, 1.0)),  // Pastel periwinkle
+    ("sh", Color::srgb(0.75, 1.0, 0.75)),   // Pastel mint
+    ("lua", Color::srgb(0.6, 0.7, 1.0)),    // Pastel blue
+    ("kt", Color::srgb(0.95, 0.7, 1.0)),    // Pastel orchid
+    ("swift", Color::srgb(1.0, 0.8, 0.55)), // Pastel peach
+];
+
+impl Default for FileColors {
+    fn default() -> Self {
+        let extensions = CATEGORY_EXTENSIONS
+            .iter()
+            .map(|&(ext, category)| (ext.to_string(), category.color()))
+            .chain(LANGUAGE_COLORS.iter().map(|&(ext, color)| (ext.to_string(), color)))
+            .collect();
+        FileColors {
+            directory: FileCategory::Directory.color(),
+            other: FileCategory::Other.color(),
+            extensions,
+        }
+    }
+}
+
+impl FileColors {
+    pub fn color_of(&self, node: &FileNode) -> Color {
+        if node.is_dir() {
+            return self.directory;
+        }
+        node.path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|ext| self.extensions.get(&ext.to_ascii_lowercase()))
+            .copied()
+            .unwrap_or(self.other)
+    }
+
+    /// Legend swatch for a category: the color its first extension is drawn
+    /// with, so the legend follows whatever file_colors.ron sets
+    pub fn category_color(&self, category: FileCategory) -> Color {
+        match category {
+            FileCategory::Directory => self.directory,
+            FileCategory::Other => self.other,
+            _ => CATEGORY_EXTENSIONS
+                .iter()
+                .find(|&&(_, c)| c == category)
+                .and_then(|(ext, _)| self.extensions.get(*ext))
+                .copied()
+                .unwrap_or(self.other),
+        }
+    }
+}
+
+fn colors_path() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets").join("file_colors.ron")
+}
+
+/// Built-in colors overlaid with whatever file_colors.ron sets, then tinted for
+/// the theme; a missing or broken file leaves the built-in colors in place
+pub fn load_file_colors(theme: Theme) -> FileColors {
+    let mut colors = read_colors_file(FileColors::default());
+    colors.directory = theme.tint(colors.directory);
+    colors.other = theme.tint(colors.other);
+    for color in colors.extensions.values_mut() {
+        *color = theme.tint(*color);
+    }
+    colors
+}
+
+fn read_colors_file(mut colors: FileColors) -> FileColors {
+    let path = colors_path();
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return colors;
+    };
+    // Let `directory: (r, g, b)` stand in for `directory: Some((r, g, b))`
+    let options = ron::Options::default().with_default_extension(Extensions::IMPLICIT_SOME);
+    let file: FileColorsFile = match options.from_str(&contents) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("[colors] Ignoring unreadable {}: {}", path.display(), e);
+            return colors;
+        }
+    };
+
+    let to_color = |(r, g, b): Rgb| Color::srgb(r, g, b);
+    if let Some(rgb) = file.directory {
+        colors.directory = to_color(rgb);
+    }
+    if let Some(rgb) = file.other {
+        colors.other = to_color(rgb);
+    }
+    for (ext, rgb) in file.extensions {
+        colors.extensions.insert(ext.trim_start_matches('.').to_ascii_lowercase(), to_color(rgb));
+    }
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legend_swatches_follow_the_loaded_colors() {
+        let mut colors = FileColors::default();
+        let custom = Color::srgb(0.1, 0.2, 0.3);
+        colors.extensions.insert("rs".to_string(), custom);
+        colors.other = custom;
+
+        assert_eq!(colors.category_color(FileCategory::Rust), custom);
+        assert_eq!(colors.category_color(FileCategory::Other), custom);
+        assert_eq!(colors.category_color(FileCategory::Directory), colors.directory);
+    }
+}
@@ -1,32 +1,81 @@
 // hello world
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use ignore::WalkBuilder;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use bevy::math::Vec3;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
 
 pub struct GitignoreChecker {
     root_path: PathBuf,
+    // One matcher per directory that has a .gitignore, shallowest first
+    matchers: Vec<Gitignore>,
 }
 
 impl GitignoreChecker {
     pub fn new(root_path: &PathBuf) -> Self {
-        Self {
+        let mut checker = Self {
             root_path: root_path.clone(),
+            matchers: Vec::new(),
+        };
+        checker.rebuild();
+        checker
+    }
+
+    /// Re-read every .gitignore (plus .git/info/exclude) under the root.
+    /// Call this whenever a .gitignore is created, modified or deleted.
+    pub fn rebuild(&mut self) {
+        let mut dirs: Vec<PathBuf> = get_valid_paths(&self.root_path)
+            .into_iter()
+            .filter(|p| p.file_name().map(|n| n == ".gitignore").unwrap_or(false))
+            .filter_map(|p| p.parent().map(Path::to_path_buf))
+            .collect();
+        dirs.sort_by_key(|d| d.components().count());
+
+        let mut matchers = Vec::new();
+
+        let exclude = self.root_path.join(".git").join("info").join("exclude");
+        if exclude.is_file() {
+            let mut builder = GitignoreBuilder::new(&self.root_path);
+            builder.add(&exclude);
+            if let Ok(gitignore) = builder.build() {
+                matchers.push(gitignore);
+            }
         }
+
+        for dir in dirs {
+            let mut builder = GitignoreBuilder::new(&dir);
+            if let Some(err) = builder.add(dir.join(".gitignore")) {
+                eprintln!("Failed to parse {}: {}", dir.join(".gitignore").display(), err);
+            }
+            match builder.build() {
+                Ok(gitignore) => matchers.push(gitignore),
+                Err(err) => eprintln!("Failed to build gitignore for {}: {}", dir.display(), err),
+            }
+        }
+
+        self.matchers = matchers;
     }
 
-    /// Check if a path is ignored by git, using `git check-ignore`.
-    /// This handles all .gitignore files (nested, global, .git/info/exclude).
+    /// Check if a path is ignored by any .gitignore (nested ones take precedence)
+    /// or by .git/info/exclude.
     pub fn is_ignored(&self, path: &PathBuf) -> bool {
-        Command::new("git")
-            .args(["check-ignore", "-q"])
-            .arg(path)
-            .current_dir(&self.root_path)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false)
+        let is_dir = path.is_dir();
+        let mut ignored = false;
+        for gitignore in &self.matchers {
+            if !path.starts_with(gitignore.path()) {
+                continue;
+            }
+            match gitignore.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => ignored = true,
+                Match::Whitelist(_) => ignored = false,
+                Match::None => {}
+            }
+        }
+        ignored
     }
 }
 
@@ -43,14 +92,53 @@ pub fn get_valid_paths(root_path: &PathBuf) -> HashSet<PathBuf> {
         .collect()
 }
 
+/// What a node stands for in the galaxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    File,
+    Directory,
+    /// "…N more" stand-in for the entries past a directory's child cap. It has
+    /// no path of its own, so it never shows up in `path_to_index`.
+    Aggregate { hidden: Vec<PathBuf> },
+}
+
 #[derive(Debug, Clone)]
 pub struct FileNode {
-    pub path: PathBuf,
+    pub path: PathBuf, // Empty for aggregates
     pub name: String,
-    pub is_dir: bool,
+    pub kind: NodeKind,
     pub depth: usize,
     pub children: Vec<usize>,
     pub parent: Option<usize>,
+    pub modified: Option<SystemTime>,
+    pub size_bytes: u64, // 0 for directories
+    pub collapsed: bool, // Directory folded down to just its own star
+    pub is_removed: bool, // Tombstone left by remove_node; the slot is never reused
+}
+
+impl FileNode {
+    pub fn is_dir(&self) -> bool {
+        self.kind == NodeKind::Directory
+    }
+
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self.kind, NodeKind::Aggregate { .. })
+    }
+}
+
+/// Caps applied while walking the tree so huge repos stay readable
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ModelLimits {
+    /// Deepest directory level included (the root is depth 0)
+    pub max_depth: Option<usize>,
+    /// Children shown per directory; the rest fold into one "…N more" node
+    pub max_children: Option<usize>,
+}
+
+impl ModelLimits {
+    fn is_active(&self) -> bool {
+        self.max_depth.is_some() || self.max_children.is_some()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -58,6 +146,21 @@ pub struct FileSystemModel {
     pub nodes: Vec<FileNode>,
     pub path_to_index: HashMap<PathBuf, usize>,
     pub root: Option<usize>,
+    // Star positions: the active layout, plus nodes placed as they were added
+    positions: HashMap<usize, Vec3>,
+    // Nodes moved by a structural change since take_moved; their stars need moving
+    moved: Vec<usize>,
+    pub limits: ModelLimits,
+    // Lazily filled by line_count(); keyed by path so it survives remove/re-add
+    line_counts: Mutex<HashMap<PathBuf, usize>>,
+}
+
+fn count_file_lines(path: &Path) -> usize {
+    if let Ok(file) = File::open(path) {
+        BufReader::new(file).lines().count()
+    } else {
+        0
+    }
 }
 
 impl FileSystemModel {
@@ -65,59 +168,145 @@ impl FileSystemModel {
         Self::default()
     }
 
-    pub fn build_initial(root_path: PathBuf) -> Self {
+    pub fn build_initial(root_path: PathBuf, limits: ModelLimits) -> Self {
         let mut model = FileSystemModel::new();
+        model.limits = limits;
+        model.add_tree(&root_path, 0);
+        model.place_from(0);
+        model
+    }
 
-        // Walk the directory tree, respecting .gitignore
-        for result in WalkBuilder::new(&root_path)
+    /// Walk `root` (which sits at `base_depth` in the model) and add it and
+    /// everything under it, honoring the depth and per-directory limits.
+    fn add_tree(&mut self, root: &Path, base_depth: usize) {
+        let mut builder = WalkBuilder::new(root);
+        builder
             .hidden(false)           // Show hidden files/folders (except those in .gitignore)
             .git_ignore(true)        // Respect .gitignore files
             .git_exclude(true)       // Respect .git/info/exclude
-            .follow_links(false)     // Don't follow symlinks
-            .build()
-        {
-            if let Ok(entry) = result {
-                let path = entry.path().to_path_buf();
-                let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
-                let depth = entry.depth();
+            .follow_links(false);    // Don't follow symlinks
+        if let Some(max_depth) = self.limits.max_depth {
+            builder.max_depth(Some(max_depth.saturating_sub(base_depth)));
+        }
+
+        // Children past the cap, per directory, in walk order
+        let mut overflow: BTreeMap<usize, Vec<PathBuf>> = BTreeMap::new();
 
-                let name = entry
-                    .file_name()
-                    .to_string_lossy()
-                    .to_string();
+        // Walk the directory tree, respecting .gitignore
+        for entry in builder.build().flatten() {
+            let path = entry.path().to_path_buf();
+            let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+            let parent = path
+                .parent()
+                .and_then(|p| self.path_to_index.get(p).copied());
 
-                model.add_node_internal(path, name, is_dir, depth);
+            if entry.depth() > 0 {
+                // Anything below a folded directory stays out until it's expanded
+                let Some(parent_idx) = parent else {
+                    continue;
+                };
+                if let Some(cap) = self.limits.max_children
+                    && self.nodes[parent_idx].children.len() >= cap
+                {
+                    overflow.entry(parent_idx).or_default().push(path);
+                    continue;
+                }
             }
+
+            let name = entry
+                .file_name()
+                .to_string_lossy()
+                .to_string();
+
+            let kind = if is_dir { NodeKind::Directory } else { NodeKind::File };
+            self.add_node_internal(path, name, kind, base_depth + entry.depth());
         }
 
-        model
+        for (parent_idx, hidden) in overflow {
+            self.add_aggregate(parent_idx, hidden);
+        }
+    }
+
+    fn add_aggregate(&mut self, parent_idx: usize, hidden: Vec<PathBuf>) -> usize {
+        self.push_node(FileNode {
+            path: PathBuf::new(),
+            name: format!("…{} more", hidden.len()),
+            kind: NodeKind::Aggregate { hidden },
+            depth: self.nodes[parent_idx].depth + 1,
+            children: Vec::new(),
+            parent: Some(parent_idx),
+            modified: None,
+            size_bytes: 0,
+            collapsed: false,
+            is_removed: false,
+        })
+    }
+
+    pub fn is_aggregate(&self, index: usize) -> bool {
+        self.get_node(index).is_some_and(FileNode::is_aggregate)
+    }
+
+    /// Replace a "…N more" node with the entries it stood for (each still
+    /// subject to the limits below it). Returns the indices of the new nodes.
+    pub fn expand_aggregate(&mut self, index: usize) -> Vec<usize> {
+        let Some(NodeKind::Aggregate { hidden }) = self.get_node(index).map(|node| &node.kind) else {
+            return Vec::new();
+        };
+        let hidden = hidden.clone();
+        let depth = self.nodes[index].depth;
+        let parent = self.nodes[index].parent;
+        self.remove_subtree(index);
+
+        let before = parent.map_or_else(Vec::new, |parent_idx| self.file_offsets(parent_idx));
+        let first_new = self.nodes.len();
+        for path in hidden {
+            if !self.path_to_index.contains_key(&path) {
+                self.add_tree(&path, depth);
+            }
+        }
+        // Placed once everything is in, so the new files fit the final entry count
+        self.place_from(first_new);
+        self.shift_files(before);
+        (first_new..self.nodes.len()).collect()
     }
 
     fn add_node_internal(
         &mut self,
         path: PathBuf,
         name: String,
-        is_dir: bool,
+        kind: NodeKind,
         depth: usize,
     ) -> usize {
-        let index = self.nodes.len();
-
         // Find parent
         let parent = path.parent().and_then(|p| {
             self.path_to_index.get(p).copied()
         });
 
-        let node = FileNode {
-            path: path.clone(),
+        let metadata = std::fs::metadata(&path).ok();
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+        let size_bytes = metadata.filter(|m| m.is_file()).map_or(0, |m| m.len());
+
+        self.push_node(FileNode {
+            path,
             name,
-            is_dir,
+            kind,
             depth,
             children: Vec::new(),
             parent,
-        };
+            modified,
+            size_bytes,
+            collapsed: false,
+            is_removed: false,
+        })
+    }
 
+    fn push_node(&mut self, node: FileNode) -> usize {
+        let index = self.nodes.len();
+        let parent = node.parent;
+        if !node.is_aggregate() {
+            self.path_to_index.insert(node.path.clone(), index);
+        }
         self.nodes.push(node);
-        self.path_to_index.insert(path, index);
 
         // Update parent's children
         if let Some(parent_idx) = parent {
@@ -142,34 +331,204 @@ impl FileSystemModel {
             .unwrap_or_default();
 
         // Calculate depth based on parent
-        let depth = if let Some(parent_path) = path.parent() {
-            self.path_to_index
-                .get(parent_path)
-                .map(|&idx| self.nodes[idx].depth + 1)
-                .unwrap_or(0)
-        } else {
-            0
-        };
+        let parent = path
+            .parent()
+            .and_then(|parent_path| self.path_to_index.get(parent_path))
+            .copied();
+        let parent_depth = parent.map(|idx| self.nodes[idx].depth);
+        let depth = parent_depth.map_or(0, |d| d + 1);
+
+        // With limits on, the parent may be folded away or below max_depth
+        if self.limits.is_active() {
+            if parent_depth.is_none() && self.root.is_some() {
+                return None;
+            }
+            if self.limits.max_depth.is_some_and(|max| depth > max) {
+                return None;
+            }
+        }
 
-        Some(self.add_node_internal(path, name, is_dir, depth))
+        let before = parent.map_or_else(Vec::new, |parent_idx| self.file_offsets(parent_idx));
+        let kind = if is_dir { NodeKind::Directory } else { NodeKind::File };
+        let index = self.add_node_internal(path, name, kind, depth);
+        self.place_from(index);
+        self.shift_files(before);
+        Some(index)
     }
 
-    pub fn remove_node(&mut self, path: &PathBuf) -> Option<usize> {
-        let index = self.path_to_index.remove(path)?;
+    /// Place every node from `first` on at its spiral offset from its parent.
+    /// Parents always come before their children, so one pass in index order
+    /// sees each parent already placed.
+    fn place_from(&mut self, first: usize) {
+        for idx in first..self.nodes.len() {
+            if !self.nodes[idx].is_removed {
+                let position = crate::galaxy::unplaced_position(self, idx);
+                self.positions.insert(idx, position);
+            }
+        }
+    }
 
+    /// Spiral offsets of a directory's files, taken before its entries change
+    fn file_offsets(&self, parent_idx: usize) -> Vec<(usize, Vec3)> {
+        self.nodes[parent_idx]
+            .children
+            .iter()
+            .filter(|&&idx| !self.nodes[idx].is_dir())
+            .map(|&idx| (idx, crate::galaxy::spiral_offset(self, idx)))
+            .collect()
+    }
+
+    /// Move a directory's files by however much their spiral offsets changed
+    /// since `before`, so the cluster makes room or closes up around its new
+    /// entry count. Whatever the active layout did to them is kept, and sibling
+    /// directories stay put so a new file doesn't drag whole subtrees along.
+    fn shift_files(&mut self, before: Vec<(usize, Vec3)>) {
+        for (idx, old_offset) in before {
+            if self.nodes[idx].is_removed {
+                continue;
+            }
+            let shift = crate::galaxy::spiral_offset(self, idx) - old_offset;
+            if shift == Vec3::ZERO {
+                continue;
+            }
+            if let Some(position) = self.positions.get_mut(&idx) {
+                *position += shift;
+                self.moved.push(idx);
+            }
+        }
+    }
+
+    /// Nodes placed again since the last call, whose stars should move to `position()`
+    pub fn take_moved(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.moved)
+    }
+
+    /// Remove a node and everything under it, returning the removed indices
+    /// (the node itself first; empty when the path isn't tracked).
+    ///
+    /// Indices are stable: removed nodes stay in `nodes` as tombstones with
+    /// `is_removed` set and their slots are never handed out again, so a stale
+    /// index held by an agent or `entity_map` can only ever miss, never point
+    /// at a different file. `get_node` and `live_indices` skip tombstones.
+    pub fn remove_node(&mut self, path: &Path) -> Vec<usize> {
+        match self.path_to_index.get(path) {
+            Some(&index) => self.remove_subtree(index),
+            None => Vec::new(),
+        }
+    }
+
+    fn remove_subtree(&mut self, index: usize) -> Vec<usize> {
         // Remove from parent's children
-        if let Some(parent_idx) = self.nodes[index].parent {
+        let parent = self.nodes[index].parent;
+        let before = parent.map_or_else(Vec::new, |parent_idx| self.file_offsets(parent_idx));
+        if let Some(parent_idx) = parent {
             self.nodes[parent_idx].children.retain(|&idx| idx != index);
         }
 
-        // Mark as removed (we keep the slot to maintain indices)
-        self.nodes[index].children.clear();
+        let mut removed = Vec::new();
+        let mut stack = vec![index];
+        while let Some(idx) = stack.pop() {
+            let node = &mut self.nodes[idx];
+            node.is_removed = true;
+            stack.append(&mut node.children);
+            if !node.is_aggregate() {
+                self.path_to_index.remove(&node.path);
+            }
+            self.positions.remove(&idx);
+            removed.push(idx);
+        }
+
+        self.shift_files(before);
+        removed
+    }
+
+    /// Rename a node in place, keeping its index. Directory renames carry
+    /// their descendants along. Returns None when the old path isn't tracked
+    /// or the node moved to a different parent (caller should remove + add).
+    pub fn rename_node(&mut self, old_path: &PathBuf, new_path: PathBuf) -> Option<usize> {
+        let index = *self.path_to_index.get(old_path)?;
+        if old_path.parent() != new_path.parent() || self.path_to_index.contains_key(&new_path) {
+            return None;
+        }
+
+        // Rewrite this node and every descendant whose path starts with old_path
+        let mut stack = vec![index];
+        while let Some(idx) = stack.pop() {
+            // Aggregates have no path, but the entries they hide move too
+            if let NodeKind::Aggregate { hidden } = &mut self.nodes[idx].kind {
+                for path in hidden {
+                    if let Ok(suffix) = path.strip_prefix(old_path) {
+                        *path = new_path.join(suffix);
+                    }
+                }
+                continue;
+            }
+            let Ok(suffix) = self.nodes[idx].path.strip_prefix(old_path) else {
+                continue;
+            };
+            let updated = if suffix.as_os_str().is_empty() {
+                new_path.clone()
+            } else {
+                new_path.join(suffix)
+            };
+            let previous = std::mem::replace(&mut self.nodes[idx].path, updated.clone());
+            self.path_to_index.remove(&previous);
+            self.path_to_index.insert(updated, idx);
+            self.invalidate_line_count(&previous);
+            stack.extend(self.nodes[idx].children.iter().copied());
+        }
+
+        self.nodes[index].name = new_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
 
         Some(index)
     }
 
+    /// Record that the watcher just saw this path change, re-reading its size
+    pub fn mark_modified(&mut self, path: &Path) {
+        if let Some(&index) = self.path_to_index.get(path) {
+            let node = &mut self.nodes[index];
+            node.modified = Some(SystemTime::now());
+            if !node.is_dir() {
+                node.size_bytes = std::fs::metadata(path).map_or(0, |m| m.len());
+            }
+        }
+    }
+
     pub fn get_node(&self, index: usize) -> Option<&FileNode> {
-        self.nodes.get(index)
+        self.nodes.get(index).filter(|node| !node.is_removed)
+    }
+
+    /// Indices of every node that hasn't been removed, in insertion order
+    pub fn live_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|&idx| !self.nodes[idx].is_removed)
+    }
+
+    /// Fold or unfold a directory; returns the new state (files never collapse)
+    pub fn toggle_collapsed(&mut self, index: usize) -> bool {
+        let Some(node) = self.nodes.get_mut(index) else {
+            return false;
+        };
+        if node.is_dir() {
+            node.collapsed = !node.collapsed;
+        }
+        node.collapsed
+    }
+
+    /// The outermost collapsed directory above a node, or the node itself when
+    /// nothing above it is collapsed. This is the star that stands in for it.
+    pub fn visible_ancestor(&self, index: usize) -> usize {
+        let mut visible = index;
+        let mut current = self.nodes.get(index).and_then(|n| n.parent);
+        while let Some(idx) = current {
+            if self.nodes[idx].collapsed {
+                visible = idx;
+            }
+            current = self.nodes[idx].parent;
+        }
+        visible
     }
 
     pub fn get_node_by_path(&self, path: &PathBuf) -> Option<(usize, &FileNode)> {
@@ -177,7 +536,269 @@ impl FileSystemModel {
         Some((index, &self.nodes[index]))
     }
 
+    /// Line count of a file node, read from disk on first use and cached afterwards
+    pub fn line_count(&self, index: usize) -> usize {
+        let Some(node) = self.nodes.get(index) else {
+            return 0;
+        };
+        if node.kind != NodeKind::File {
+            return 0;
+        }
+
+        let mut cache = self.line_counts.lock().unwrap_or_else(|e| e.into_inner());
+        *cache
+            .entry(node.path.clone())
+            .or_insert_with(|| count_file_lines(&node.path))
+    }
+
+    /// Forget the cached line count so the next line_count() re-reads the file
+    pub fn invalidate_line_count(&self, path: &Path) {
+        let mut cache = self.line_counts.lock().unwrap_or_else(|e| e.into_inner());
+        cache.remove(path);
+    }
+
+    /// Where a node's star sits. Every caller (star spawning, agent targeting,
+    /// camera framing) goes through here. A node is placed when it's added and
+    /// keeps its spot until the layout is replaced or it's removed; files only
+    /// shift as entries come and go in their directory.
+    pub fn position(&self, index: usize) -> Vec3 {
+        self.positions.get(&index).copied().unwrap_or_default()
+    }
+
+    /// Replace every position with a freshly computed layout
+    pub fn set_layout(&mut self, positions: HashMap<usize, Vec3>) {
+        self.positions = positions;
+    }
+
+    /// Slots in `nodes`, tombstones included; the bound for node indices
     pub fn total_nodes(&self) -> usize {
         self.nodes.len()
     }
+
+    /// Files and directories currently in the model
+    pub fn node_count(&self) -> usize {
+        self.path_to_index.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_with_root() -> FileSystemModel {
+        let mut model = FileSystemModel::new();
+        model.add_node(PathBuf::from("/repo"), true);
+        model
+    }
+
+    #[test]
+    fn repeated_add_remove_cycles_leave_tombstones_behind() {
+        let mut model = model_with_root();
+        let path = PathBuf::from("/repo/main.rs");
+        let mut previous = Vec::new();
+
+        for _ in 0..5 {
+            let index = model.add_node(path.clone(), false).expect("path was removed");
+            assert!(!previous.contains(&index), "re-added path got a recycled index");
+            assert_eq!(model.get_node_by_path(&path).map(|(idx, _)| idx), Some(index));
+            assert_eq!(model.node_count(), 2);
+            assert_eq!(model.live_indices().collect::<Vec<_>>(), vec![0, index]);
+
+            assert_eq!(model.remove_node(&path), vec![index]);
+            assert!(model.get_node(index).is_none());
+            assert_eq!(model.node_count(), 1);
+            assert_eq!(model.live_indices().collect::<Vec<_>>(), vec![0]);
+            previous.push(index);
+        }
+
+        // Every slot handed out is still there, as a tombstone
+        assert_eq!(model.total_nodes(), 1 + previous.len());
+        assert!(previous.iter().all(|&idx| model.nodes[idx].is_removed));
+    }
+
+    #[test]
+    fn removing_a_directory_tombstones_its_subtree() {
+        let mut model = model_with_root();
+        let dir = PathBuf::from("/repo/src");
+
+        for _ in 0..3 {
+            let dir_idx = model.add_node(dir.clone(), true).unwrap();
+            let file_idx = model.add_node(dir.join("lib.rs"), false).unwrap();
+
+            let removed = model.remove_node(&dir);
+            assert_eq!(removed, vec![dir_idx, file_idx]);
+            assert!(model.get_node(file_idx).is_none());
+            assert!(model.get_node_by_path(&dir.join("lib.rs")).is_none());
+            assert!(model.nodes[0].children.is_empty());
+            assert_eq!(model.live_indices().count(), model.node_count());
+        }
+    }
+
+    /// /repo with `files` files directly under it and one subdirectory holding two more
+    fn small_tree(files: usize) -> FileSystemModel {
+        let mut model = model_with_root();
+        for i in 0..files {
+            model.add_node(PathBuf::from(format!("/repo/file{}.rs", i)), false);
+        }
+        model.add_node(PathBuf::from("/repo/src"), true);
+        model.add_node(PathBuf::from("/repo/src/a.rs"), false);
+        model.add_node(PathBuf::from("/repo/src/b.rs"), false);
+        model
+    }
+
+    #[test]
+    fn root_sits_at_origin() {
+        let model = small_tree(3);
+        assert_eq!(model.position(0), Vec3::ZERO);
+    }
+
+    #[test]
+    fn positions_are_deterministic() {
+        let model = small_tree(5);
+        let again = small_tree(5);
+        for idx in model.live_indices() {
+            assert_eq!(model.position(idx), model.position(idx));
+            assert_eq!(model.position(idx), again.position(idx));
+        }
+    }
+
+    #[test]
+    fn force_layout_is_deterministic() {
+        use crate::galaxy::{LayoutMode, compute_layout};
+        let first = compute_layout(&small_tree(40), LayoutMode::ForceDirected);
+        let second = compute_layout(&small_tree(40), LayoutMode::ForceDirected);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn files_stay_within_their_cluster_radius() {
+        let model = small_tree(30);
+        for idx in model.live_indices() {
+            let node = &model.nodes[idx];
+            let Some(parent_idx) = node.parent.filter(|_| !node.is_dir()) else {
+                continue;
+            };
+            let siblings = model.nodes[parent_idx].children.len();
+            let offset = model.position(idx) - model.position(parent_idx);
+            let horizontal = offset.with_y(0.0).length();
+            assert!(
+                horizontal <= crate::galaxy::file_cluster_radius(siblings) + 1e-4,
+                "{} is {} from its folder",
+                node.path.display(),
+                horizontal
+            );
+            assert!(offset.y < 0.0, "files hang below their folder");
+        }
+    }
+
+    #[test]
+    fn adding_a_file_places_its_siblings_again() {
+        let mut model = model_with_root();
+        let first = model.add_node(PathBuf::from("/repo/a.rs"), false).unwrap();
+        let before = model.position(first);
+        model.take_moved();
+
+        model.add_node(PathBuf::from("/repo/b.rs"), false);
+        assert_eq!(model.take_moved(), vec![first]);
+
+        // Same spot a model built with both files from the start gives it
+        let mut fresh = model_with_root();
+        fresh.add_node(PathBuf::from("/repo/a.rs"), false);
+        fresh.add_node(PathBuf::from("/repo/b.rs"), false);
+        assert_ne!(model.position(first), before);
+        assert_eq!(model.position(first), fresh.position(first));
+    }
+
+    #[test]
+    fn a_new_file_keeps_what_the_layout_did_to_its_siblings() {
+        let mut model = small_tree(3);
+        let file = model.get_node_by_path(&PathBuf::from("/repo/file0.rs")).unwrap().0;
+        let mut layout: HashMap<usize, Vec3> = model.live_indices().map(|idx| (idx, model.position(idx))).collect();
+        *layout.get_mut(&file).unwrap() += Vec3::X * 5.0;
+        model.set_layout(layout);
+        model.take_moved();
+
+        model.add_node(PathBuf::from("/repo/new.rs"), false);
+        assert!(model.take_moved().contains(&file));
+        let nudge = model.position(file) - crate::galaxy::unplaced_position(&model, file);
+        assert!((nudge - Vec3::X * 5.0).length() < 1e-4, "nudge lost: {}", nudge);
+    }
+
+    /// A checkout on disk with `count` files at its top level, built with at
+    /// most `cap` children per directory
+    fn capped_checkout(count: usize, cap: usize) -> (tempfile::TempDir, FileSystemModel) {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..count {
+            std::fs::write(dir.path().join(format!("file{}.rs", i)), "").unwrap();
+        }
+        let limits = ModelLimits { max_depth: None, max_children: Some(cap) };
+        let model = FileSystemModel::build_initial(dir.path().to_path_buf(), limits);
+        (dir, model)
+    }
+
+    fn aggregates_under(model: &FileSystemModel, parent: usize) -> Vec<usize> {
+        model.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .filter(|&idx| model.is_aggregate(idx))
+            .collect()
+    }
+
+    #[test]
+    fn children_past_the_cap_fold_into_one_aggregate() {
+        let (_dir, model) = capped_checkout(5, 2);
+        let root = model.root.unwrap();
+        assert_eq!(model.nodes[root].children.len(), 3);
+
+        let aggregates = aggregates_under(&model, root);
+        assert_eq!(aggregates.len(), 1);
+        let aggregate = &model.nodes[aggregates[0]];
+        assert_eq!(aggregate.name, "…3 more");
+        assert!(aggregate.path.as_os_str().is_empty());
+
+        // Only real files and folders are reachable by path
+        assert_eq!(model.node_count(), 3);
+        assert!(model.path_to_index.values().all(|&idx| !model.is_aggregate(idx)));
+    }
+
+    #[test]
+    fn expanding_an_aggregate_adds_the_entries_it_hid() {
+        let (dir, mut model) = capped_checkout(5, 2);
+        let root = model.root.unwrap();
+        let aggregate = aggregates_under(&model, root)[0];
+
+        let added = model.expand_aggregate(aggregate);
+        assert_eq!(added.len(), 3);
+        assert!(model.get_node(aggregate).is_none());
+        assert!(aggregates_under(&model, root).is_empty());
+        assert_eq!(model.nodes[root].children.len(), 5);
+        for i in 0..5 {
+            let path = dir.path().join(format!("file{}.rs", i));
+            assert!(model.get_node_by_path(&path).is_some(), "{} missing", path.display());
+        }
+
+        assert!(model.expand_aggregate(aggregate).is_empty());
+
+        // Every file sits in the cluster sized for all five
+        let radius = crate::galaxy::file_cluster_radius(5);
+        for &idx in &model.nodes[root].children {
+            let offset = model.position(idx) - model.position(root);
+            assert!(offset.with_y(0.0).length() <= radius + 1e-4);
+            assert_eq!(model.position(idx), crate::galaxy::unplaced_position(&model, idx));
+        }
+    }
+
+    #[test]
+    fn max_depth_leaves_deeper_entries_out() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/deep")).unwrap();
+        std::fs::write(dir.path().join("src/deep/x.rs"), "").unwrap();
+        let limits = ModelLimits { max_depth: Some(1), max_children: None };
+        let mut model = FileSystemModel::build_initial(dir.path().to_path_buf(), limits);
+
+        assert!(model.get_node_by_path(&dir.path().join("src")).is_some());
+        assert!(model.get_node_by_path(&dir.path().join("src/deep")).is_none());
+        assert_eq!(model.add_node(dir.path().join("src/deep/y.rs"), false), None);
+    }
 }
@@ -1,9 +1,15 @@
 // hello world
+use bevy::color::Mix;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_fontmesh::{TextMesh, TextMeshBundle, TextMeshStyle};
+use crate::file_colors::FileColors;
 use crate::fs_model::{FileNode, FileSystemModel};
 use crate::planet_material::{PlanetMaterial, PlanetMaterialExtension};
+use crate::settings::Settings;
+use std::collections::{BTreeMap, HashMap};
 use std::f32::consts::PI;
+use std::path::Path;
 
 #[derive(Component)]
 pub struct FileStar {
@@ -13,12 +19,164 @@ pub struct FileStar {
 #[derive(Component)]
 pub struct StarGlow;
 
+/// A star created while running, growing in from nothing; removed once fully shown
+#[derive(Component, Default)]
+pub struct StarSpawnAnim {
+    pub progress: f32,
+}
+
+/// A deleted star (or its label) fading out; despawned once the timer runs out.
+/// The node is already gone from the model, so nothing else targets it meanwhile.
+#[derive(Component, Default)]
+pub struct StarDespawning {
+    pub timer: f32,
+}
+
+/// How stars are colored
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StarColorMode {
+    #[default]
+    FileType,
+    Directory,
+    Recency,
+}
+
+/// What a file star's radius reflects
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StarSizeMode {
+    #[default]
+    Lines,
+    /// Byte size, which also gives binary assets (zero lines) a meaningful size
+    Bytes,
+}
+
+/// Rough bytes per line of source, keeping both size modes on a similar scale
+const BYTES_PER_LINE: f32 = 32.0;
+
+/// Files edited within RECENCY_HOT_SECS glow this color, cooling back to
+/// their type color by RECENCY_COOL_SECS
+pub const RECENCY_HOT_COLOR: Color = Color::srgb(1.0, 0.55, 0.15);
+pub const RECENCY_HOT_SECS: f32 = 60.0;
+pub const RECENCY_COOL_SECS: f32 = 3600.0;
+
+/// How star positions are laid out
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutMode {
+    #[default]
+    Spiral,
+    ForceDirected,
+}
+
+const FORCE_LAYOUT_ITERATIONS: usize = 200;
+/// Node-steps the relaxation may spend; past this, big trees get fewer iterations
+const FORCE_LAYOUT_BUDGET: usize = 400_000;
+const FORCE_LAYOUT_MIN_ITERATIONS: usize = 20;
+const FORCE_SIBLING_REPULSION: f32 = 40.0;
+/// Siblings further apart than this don't push each other; it's also the
+/// cell size of the grid that finds the ones close enough
+const FORCE_REPULSION_CUTOFF: f32 = 15.0;
+const FORCE_EDGE_STIFFNESS: f32 = 0.1;
+const FORCE_MAX_STEP: f32 = 2.0;
+
 #[derive(Component)]
 pub struct FileLabel {
     pub star_entity: Entity,
     pub offset: Vec3,
 }
 
+/// Short file-type glyph under a star. It's a FileLabel too, so it billboards,
+/// follows and fades with its star like the name label does.
+#[derive(Component)]
+pub struct StarGlyph;
+
+/// White unlit material every name label and glyph starts out with
+#[derive(Resource)]
+pub struct LabelMaterial(pub Handle<StandardMaterial>);
+
+impl FromWorld for LabelMaterial {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        LabelMaterial(materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            unlit: true,
+            ..default()
+        }))
+    }
+}
+
+/// Glyphs for common extensions; anything else shows its first letter
+const EXTENSION_GLYPHS: &[(&str, &str)] = &[
+    ("rs", "Rs"),
+    ("py", "Py"),
+    ("js", "JS"),
+    ("jsx", "JSX"),
+    ("ts", "TS"),
+    ("tsx", "TSX"),
+    ("go", "Go"),
+    ("rb", "Rb"),
+    ("c", "C"),
+    ("h", "H"),
+    ("cpp", "C++"),
+    ("java", "J"),
+    ("kt", "Kt"),
+    ("swift", "Sw"),
+    ("lua", "Lu"),
+    ("php", "Php"),
+    ("json", "{}"),
+    ("toml", "{}"),
+    ("yaml", "{}"),
+    ("yml", "{}"),
+    ("html", "<>"),
+    ("css", "#"),
+    ("sh", "$"),
+    ("md", "M"),
+    ("txt", "T"),
+];
+
+/// What a star's glyph reads: "/" for directories, else by extension
+pub fn star_glyph(node: &FileNode) -> String {
+    if node.is_dir() {
+        return "/".to_string();
+    }
+    let Some(ext) = node.path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase) else {
+        return "·".to_string();
+    };
+    EXTENSION_GLYPHS
+        .iter()
+        .find(|(known, _)| *known == ext)
+        .map(|(_, glyph)| glyph.to_string())
+        .unwrap_or_else(|| ext.chars().take(1).flat_map(char::to_uppercase).collect())
+}
+
+/// Glyphs hang just below their star
+pub fn glyph_offset(star_size: f32) -> Vec3 {
+    Vec3::new(0.0, -(star_size + 0.9), 0.0)
+}
+
+/// Spiral ring spacing per directory depth
+const DIR_RING_SPACING: f32 = 8.0;
+/// Extra spiral radius per earlier sibling directory
+const DIR_SIBLING_STEP: f32 = 1.5;
+/// File cluster radius for folders up to FILE_CLUSTER_BASE_COUNT entries
+const FILE_CLUSTER_RADIUS: f32 = 3.5;
+const FILE_CLUSTER_BASE_COUNT: f32 = 8.0;
+/// File spheres are squashed vertically to keep the depth layering readable
+const FILE_CLUSTER_FLATTEN: f32 = 0.5;
+
+/// Ring spacing for a directory among `sibling_count` entries; past ten
+/// siblings the rings spread out so wide trees don't pile up
+fn dir_ring_spacing(sibling_count: usize) -> f32 {
+    let extra = sibling_count.saturating_sub(10) as f32 / 10.0;
+    DIR_RING_SPACING * (1.0 + extra.ln_1p() * 0.5)
+}
+
+/// Radius of the file cluster around a folder with `entry_count` entries. It
+/// grows with the square root of the count so stars keep roughly the same
+/// spacing however big the folder is.
+pub fn file_cluster_radius(entry_count: usize) -> f32 {
+    FILE_CLUSTER_RADIUS * (entry_count as f32 / FILE_CLUSTER_BASE_COUNT).sqrt().max(1.0)
+}
+
 /// Calculate position for a node - folders in spiral, files cluster around parent
 pub fn calculate_galaxy_position(model: &FileSystemModel, node_idx: usize) -> Vec3 {
     let node = &model.nodes[node_idx];
@@ -30,22 +188,21 @@ pub fn calculate_galaxy_position(model: &FileSystemModel, node_idx: usize) -> Ve
 
     let golden_ratio = 1.618033988749;
 
-    // Get index within parent's children
-    let index_in_parent = if let Some(parent_idx) = node.parent {
-        model.nodes[parent_idx]
-            .children
-            .iter()
-            .position(|&idx| idx == node_idx)
-            .unwrap_or(0)
+    // Get index within parent's children, and how many children share the parent
+    let (index_in_parent, sibling_count) = if let Some(parent_idx) = node.parent {
+        let siblings = &model.nodes[parent_idx].children;
+        let index = siblings.iter().position(|&idx| idx == node_idx).unwrap_or(0);
+        (index, siblings.len().max(1))
     } else {
-        0
+        (0, 1)
     };
 
-    if node.is_dir {
+    if node.is_dir() {
         // Directories: spiral pattern based on depth
         // Higher in the tree (lower depth) = slightly higher in space
         let angle = (node_idx as f32 * golden_ratio * 2.0 * PI) + (index_in_parent as f32 * 0.5);
-        let radius = (node.depth as f32) * 8.0 + (index_in_parent as f32) * 1.5;
+        let radius = (node.depth as f32) * dir_ring_spacing(sibling_count)
+            + (index_in_parent as f32) * DIR_SIBLING_STEP;
 
         // Root slightly above origin, everything else slightly below
         // Much smaller variation: root at ~2, depth 1 at ~0, depth 2 at ~-2, etc.
@@ -56,17 +213,21 @@ pub fn calculate_galaxy_position(model: &FileSystemModel, node_idx: usize) -> Ve
 
         Vec3::new(x, y, z)
     } else {
-        // Files: cluster around and below parent folder, more spread out
+        // Files: a small flattened sphere hanging below the parent folder,
+        // sized by how many entries share it
         if let Some(parent_idx) = node.parent {
             let parent_pos = calculate_galaxy_position(model, parent_idx);
+            let cluster_radius = file_cluster_radius(sibling_count);
 
-            // Distribute files in a circle around parent, more spread out
+            // Fibonacci sphere: evenly spaced from the top of the sphere to the bottom
+            let height = 1.0 - 2.0 * (index_in_parent as f32 + 0.5) / sibling_count as f32;
+            let ring = (1.0 - height * height).sqrt();
             let angle = index_in_parent as f32 * golden_ratio * 2.0 * PI;
-            let cluster_radius = 3.5; // Increased from 2.0 for more spread
 
-            let offset_x = cluster_radius * angle.cos();
-            let offset_z = cluster_radius * angle.sin();
-            let offset_y = -2.0 - (index_in_parent as f32 * 0.3).min(3.0); // Below parent, more vertical spread
+            let offset_x = cluster_radius * ring * angle.cos();
+            let offset_z = cluster_radius * ring * angle.sin();
+            // Top of the sphere sits 2 units below the parent
+            let offset_y = -2.0 - cluster_radius * FILE_CLUSTER_FLATTEN * (1.0 - height);
 
             Vec3::new(
                 parent_pos.x + offset_x,
@@ -80,9 +241,126 @@ pub fn calculate_galaxy_position(model: &FileSystemModel, node_idx: usize) -> Ve
     }
 }
 
-/// Calculate star size based on node properties
-pub fn calculate_star_size(node: &FileNode) -> f32 {
-    if node.is_dir {
+/// Where the spiral layout puts a node relative to its parent
+pub fn spiral_offset(model: &FileSystemModel, node_idx: usize) -> Vec3 {
+    let spiral = calculate_galaxy_position(model, node_idx);
+    match model.nodes[node_idx].parent {
+        Some(parent_idx) => spiral - calculate_galaxy_position(model, parent_idx),
+        None => spiral,
+    }
+}
+
+/// Position for a node the active layout didn't place (added after it ran):
+/// its spiral offset from wherever the parent actually sits
+pub fn unplaced_position(model: &FileSystemModel, node_idx: usize) -> Vec3 {
+    let parent_position = model.nodes[node_idx].parent.map_or(Vec3::ZERO, |parent_idx| model.position(parent_idx));
+    parent_position + spiral_offset(model, node_idx)
+}
+
+/// Relaxation steps for a tree of `node_count` nodes, fewer on big trees so
+/// startup and the L toggle stay quick
+fn force_layout_iterations(node_count: usize) -> usize {
+    (FORCE_LAYOUT_BUDGET / node_count.max(1)).clamp(FORCE_LAYOUT_MIN_ITERATIONS, FORCE_LAYOUT_ITERATIONS)
+}
+
+/// Grid cell of the sibling repulsion lookup
+fn repulsion_cell(position: Vec3) -> (i32, i32) {
+    (
+        (position.x / FORCE_REPULSION_CUTOFF).floor() as i32,
+        (position.z / FORCE_REPULSION_CUTOFF).floor() as i32,
+    )
+}
+
+/// Compute positions for every live node in the model under the given layout
+pub fn compute_layout(model: &FileSystemModel, mode: LayoutMode) -> HashMap<usize, Vec3> {
+    let mut positions: HashMap<usize, Vec3> = model
+        .live_indices()
+        .map(|idx| (idx, calculate_galaxy_position(model, idx)))
+        .collect();
+
+    if mode == LayoutMode::Spiral {
+        return positions;
+    }
+
+    // Relax the spiral: siblings push apart, parent-child edges act as springs.
+    // Only X/Z move so the depth layering on Y stays intact. Nodes are visited
+    // in index order so the same tree always settles the same way.
+    let mut order: Vec<usize> = positions.keys().copied().collect();
+    order.sort_unstable();
+    let iterations = force_layout_iterations(positions.len());
+    for step in 0..iterations {
+        let cooling = 1.0 - step as f32 / iterations as f32;
+        let mut forces: BTreeMap<usize, Vec3> = BTreeMap::new();
+
+        for &idx in &order {
+            let position = positions[&idx];
+            let node = &model.nodes[idx];
+
+            for &child in &node.children {
+                let Some(&child_pos) = positions.get(&child) else {
+                    continue;
+                };
+                // Spring towards a rest length that matches the spiral spacing
+                let rest_length = if model.nodes[child].is_dir() {
+                    dir_ring_spacing(node.children.len())
+                } else {
+                    file_cluster_radius(node.children.len())
+                };
+                let delta = position - child_pos;
+                let stretch = delta.length() - rest_length;
+                let pull = delta.normalize_or_zero() * stretch * FORCE_EDGE_STIFFNESS;
+                *forces.entry(child).or_default() += pull;
+                *forces.entry(idx).or_default() -= pull;
+            }
+
+            // Bucket the children into a grid so each one only checks its neighbors
+            let mut grid: BTreeMap<(i32, i32), Vec<(usize, Vec3)>> = BTreeMap::new();
+            for &child in &node.children {
+                if let Some(&child_pos) = positions.get(&child) {
+                    grid.entry(repulsion_cell(child_pos)).or_default().push((child, child_pos));
+                }
+            }
+            for (&(cell_x, cell_z), cell) in &grid {
+                for (a, pos_a) in cell {
+                    for (dx, dz) in (-1..=1).flat_map(|dx| (-1..=1).map(move |dz| (dx, dz))) {
+                        let Some(neighbors) = grid.get(&(cell_x + dx, cell_z + dz)) else {
+                            continue;
+                        };
+                        // Each pair once: the lower index applies the push to both
+                        for (b, pos_b) in neighbors.iter().filter(|(b, _)| a < b) {
+                            let delta = (*pos_a - *pos_b).with_y(0.0);
+                            if delta.length_squared() > FORCE_REPULSION_CUTOFF * FORCE_REPULSION_CUTOFF {
+                                continue;
+                            }
+                            let distance_sq = delta.length_squared().max(0.25);
+                            let push = delta.normalize_or(Vec3::X) * FORCE_SIBLING_REPULSION / distance_sq;
+                            *forces.entry(*a).or_default() += push;
+                            *forces.entry(*b).or_default() -= push;
+                        }
+                    }
+                }
+            }
+        }
+
+        for (idx, force) in forces {
+            // Root stays pinned at the center
+            if model.nodes[idx].parent.is_none() {
+                continue;
+            }
+            if let Some(position) = positions.get_mut(&idx) {
+                *position += force.with_y(0.0).clamp_length_max(FORCE_MAX_STEP * cooling);
+            }
+        }
+    }
+
+    positions
+}
+
+/// Calculate star size based on node properties, times the configured star scale
+pub fn calculate_star_size(model: &FileSystemModel, node_idx: usize, size_mode: StarSizeMode, scale: f32) -> f32 {
+    let node = &model.nodes[node_idx];
+
+    let radius = if node.is_dir() {
         // Directories are larger, and slightly bigger the higher they are in the tree (lower depth)
         let depth_size_bonus = if node.depth == 0 {
             0.3 // Root is slightly bigger
@@ -97,87 +375,211 @@ pub fn calculate_star_size(node: &FileNode) -> f32 {
 
         base_size + children_bonus
     } else {
-        // Files: size based on line count
-        let line_count = count_file_lines(&node.path);
+        // Files: size based on line count, or on bytes converted to roughly equivalent lines
+        let line_count = match size_mode {
+            StarSizeMode::Lines => model.line_count(node_idx) as f32,
+            StarSizeMode::Bytes => node.size_bytes as f32 / BYTES_PER_LINE,
+        };
         let base_size = 0.2;
 
         // Scale size based on line count (logarithmic scaling)
         // 0 lines = 0.2, 100 lines = 0.3, 1000 lines = 0.5, 10000 lines = 0.7
-        let size_bonus = if line_count > 0 {
-            ((line_count as f32).log10() * 0.15).min(0.5)
+        let size_bonus = if line_count >= 1.0 {
+            (line_count.log10() * 0.15).min(0.5)
         } else {
             0.0
         };
 
         base_size + size_bonus
-    }
+    };
+    radius * scale
 }
 
-fn count_file_lines(path: &std::path::Path) -> usize {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
-
-    if let Ok(file) = File::open(path) {
-        BufReader::new(file).lines().count()
-    } else {
-        0
-    }
+/// Broad file categories used for coloring and the legend filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileCategory {
+    Directory,
+    Rust,
+    Config,
+    Docs,
+    JavaScript,
+    Python,
+    Web,
+    Compiled,
+    Go,
+    Other,
 }
 
-/// Calculate star color based on node properties - HackMIT color scheme
-pub fn calculate_star_color(node: &FileNode) -> Color {
-    if node.is_dir {
-        // Directories are warm whitish-yellow
-        Color::srgb(1.0, 0.95, 0.7) // Whitish yellow
-    } else {
-        // Files colored by extension - pastel but vibrant
+impl FileCategory {
+    pub const ALL: [FileCategory; 10] = [
+        FileCategory::Directory,
+        FileCategory::Rust,
+        FileCategory::Config,
+        FileCategory::Docs,
+        FileCategory::JavaScript,
+        FileCategory::Python,
+        FileCategory::Web,
+        FileCategory::Compiled,
+        FileCategory::Go,
+        FileCategory::Other,
+    ];
+
+    pub fn of(node: &FileNode) -> Self {
+        if node.is_dir() {
+            return FileCategory::Directory;
+        }
+
         let extension = node.path.extension()
             .and_then(|e| e.to_str())
             .unwrap_or("");
 
         match extension {
-            "rs" => Color::srgb(1.0, 0.75, 0.6),      // Rust - pastel coral
-            "toml" | "yaml" | "yml" | "json" => Color::srgb(1.0, 0.95, 0.6), // Config - pastel yellow
-            "md" | "txt" => Color::srgb(0.9, 0.8, 1.0), // Text - pastel lavender
-            "js" | "ts" => Color::srgb(1.0, 0.98, 0.7), // JS - pastel cream yellow
-            "py" => Color::srgb(0.7, 0.85, 1.0),      // Python - pastel sky blue
-            "html" | "css" => Color::srgb(1.0, 0.7, 0.85), // Web - pastel pink
-            "java" | "cpp" | "c" => Color::srgb(0.85, 0.75, 1.0), // Compiled - pastel purple
-            "go" => Color::srgb(0.7, 0.9, 1.0),      // Go - pastel cyan
-            _ => Color::srgb(0.9, 0.8, 0.95),         // Unknown - pastel lilac
+            "rs" => FileCategory::Rust,
+            "toml" | "yaml" | "yml" | "json" => FileCategory::Config,
+            "md" | "txt" => FileCategory::Docs,
+            "js" | "ts" | "tsx" => FileCategory::JavaScript,
+            "py" => FileCategory::Python,
+            "html" | "css" => FileCategory::Web,
+            "java" | "cpp" | "c" | "kt" | "swift" => FileCategory::Compiled,
+            "go" => FileCategory::Go,
+            _ => FileCategory::Other,
         }
     }
+
+    /// Legend label
+    pub fn label(self) -> &'static str {
+        match self {
+            FileCategory::Directory => "Directories",
+            FileCategory::Rust => "Rust (.rs)",
+            FileCategory::Config => "Config",
+            FileCategory::Docs => "Docs (.md)",
+            FileCategory::JavaScript => "JavaScript",
+            FileCategory::Python => "Python (.py)",
+            FileCategory::Web => "Web (html/css)",
+            FileCategory::Compiled => "Compiled",
+            FileCategory::Go => "Go",
+            FileCategory::Other => "Other",
+        }
+    }
+
+    /// HackMIT color scheme - pastel but vibrant
+    pub fn color(self) -> Color {
+        match self {
+            FileCategory::Directory => Color::srgb(1.0, 0.95, 0.7),  // Whitish yellow
+            FileCategory::Rust => Color::srgb(1.0, 0.75, 0.6),       // Pastel coral
+            FileCategory::Config => Color::srgb(1.0, 0.95, 0.6),     // Pastel yellow
+            FileCategory::Docs => Color::srgb(0.9, 0.8, 1.0),        // Pastel lavender
+            FileCategory::JavaScript => Color::srgb(1.0, 0.98, 0.7), // Pastel cream yellow
+            FileCategory::Python => Color::srgb(0.7, 0.85, 1.0),     // Pastel sky blue
+            FileCategory::Web => Color::srgb(1.0, 0.7, 0.85),        // Pastel pink
+            FileCategory::Compiled => Color::srgb(0.85, 0.75, 1.0),  // Pastel purple
+            FileCategory::Go => Color::srgb(0.7, 0.9, 1.0),          // Pastel cyan
+            FileCategory::Other => Color::srgb(0.9, 0.8, 0.95),      // Pastel lilac
+        }
+    }
+}
+
+/// Calculate star color based on node properties, from the configured extension colors
+pub fn calculate_star_color(node: &FileNode, colors: &FileColors) -> Color {
+    colors.color_of(node)
+}
+
+/// Consistent tint for a directory derived from a hash of its path
+fn directory_tint(path: &Path) -> Color {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    // Keep saturation low and lightness high so folders stay in the bright "directory" band
+    let hue = (hash % 360) as f32;
+    let saturation = 0.55 + ((hash >> 8) % 20) as f32 / 100.0; // 0.55-0.75
+    Color::hsl(hue, saturation, 0.8)
+}
+
+/// 1.0 for a node modified in the last minute, fading to 0.0 at an hour
+pub fn recency_heat(node: &FileNode) -> f32 {
+    let Some(age) = node.modified.and_then(|m| m.elapsed().ok()) else {
+        return 0.0;
+    };
+    let age = age.as_secs_f32();
+    1.0 - ((age - RECENCY_HOT_SECS) / (RECENCY_COOL_SECS - RECENCY_HOT_SECS)).clamp(0.0, 1.0)
+}
+
+/// Calculate star color for the given color mode
+pub fn calculate_star_color_for_mode(
+    model: &FileSystemModel,
+    node_idx: usize,
+    mode: StarColorMode,
+    colors: &FileColors,
+) -> Color {
+    let node = &model.nodes[node_idx];
+    match mode {
+        StarColorMode::FileType => calculate_star_color(node, colors),
+        StarColorMode::Directory => {
+            if node.is_dir() {
+                directory_tint(&node.path)
+            } else if let Some(parent_idx) = node.parent {
+                // Files keep their type color with a subtle tint from their folder
+                let parent_tint = directory_tint(&model.nodes[parent_idx].path);
+                calculate_star_color(node, colors).mix(&parent_tint, 0.35)
+            } else {
+                calculate_star_color(node, colors)
+            }
+        }
+        StarColorMode::Recency => {
+            calculate_star_color(node, colors).mix(&RECENCY_HOT_COLOR, recency_heat(node))
+        }
+    }
+}
+
+/// Emissive multiplier for a star's base color
+pub fn calculate_star_emissive_strength(node: &FileNode) -> f32 {
+    if node.is_dir() {
+        // Directories are bright stars with strong bloom
+        6.0 + (node.children.len() as f32 * 0.5).min(10.0)
+    } else {
+        // Files have moderate emissive for subtle bloom
+        2.5
+    }
+}
+
+/// Asset stores, colors and modes spawn_star needs, bundled to keep spawning
+/// systems' signatures short
+#[derive(SystemParam)]
+pub struct StarAssets<'w> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    planet_materials: ResMut<'w, Assets<PlanetMaterial>>,
+    label_material: Res<'w, LabelMaterial>,
+    asset_server: Res<'w, AssetServer>,
+    file_colors: Res<'w, FileColors>,
+    settings: Res<'w, Settings>,
+    color_mode: Res<'w, StarColorMode>,
+    size_mode: Res<'w, StarSizeMode>,
 }
 
 /// Spawn a star entity for a file system node
 pub fn spawn_star(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-    planet_materials: &mut ResMut<Assets<PlanetMaterial>>,
-    asset_server: &Res<AssetServer>,
+    assets: &mut StarAssets,
     model: &FileSystemModel,
     node_idx: usize,
 ) -> Entity {
     let node = &model.nodes[node_idx];
-    let position = calculate_galaxy_position(model, node_idx);
-    let size = calculate_star_size(node);
-    let color = calculate_star_color(node);
+    let position = model.position(node_idx);
+    let size = calculate_star_size(model, node_idx, *assets.size_mode, assets.settings.stars.scale);
+    let color = calculate_star_color_for_mode(model, node_idx, *assets.color_mode, &assets.file_colors);
 
     // Create sphere - both folders and files bloom
-    let mesh = meshes.add(Sphere::new(size));
+    let mesh = assets.meshes.add(Sphere::new(size));
 
     // Directories get higher emissive, files get moderate emissive
-    let emissive_strength = if node.is_dir {
-        // Directories are bright stars with strong bloom
-        6.0 + (node.children.len() as f32 * 0.5).min(10.0)
-    } else {
-        // Files have moderate emissive for subtle bloom
-        2.5
-    };
+    let emissive_strength = calculate_star_emissive_strength(node);
 
     // Use planet material with crescent shadow effect
-    let material = planet_materials.add(PlanetMaterial {
+    let material = assets.planet_materials.add(PlanetMaterial {
         base: StandardMaterial {
             base_color: color,
             emissive: LinearRgba::from(color) * emissive_strength,
@@ -208,18 +610,14 @@ pub fn spawn_star(
         TextMeshBundle {
             text_mesh: TextMesh {
                 text: node.name.clone(),
-                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font: assets.asset_server.load("fonts/FiraMono-Medium.ttf"),
                 style: TextMeshStyle {
                     depth: 0.2,
                     subdivision: 10,
                     ..default()
                 },
             },
-            material: MeshMaterial3d(materials.add(StandardMaterial {
-                base_color: Color::WHITE,
-                unlit: true,
-                ..default()
-            })),
+            material: MeshMaterial3d(assets.label_material.0.clone()),
             transform: Transform::from_translation(label_pos)
                 .with_scale(Vec3::splat(0.5)),
             ..default()
@@ -233,16 +631,43 @@ pub fn spawn_star(
     star_entity
 }
 
-/// Spawn all stars for the initial file system
-pub fn spawn_galaxy(
+/// Spawn the glyph under a star, hidden until apply_label_lod finds it in range
+pub fn spawn_star_glyph(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-    planet_materials: &mut ResMut<Assets<PlanetMaterial>>,
-    asset_server: &Res<AssetServer>,
+    assets: &StarAssets,
     model: &FileSystemModel,
+    node_idx: usize,
+    star_entity: Entity,
 ) {
-    for node_idx in 0..model.total_nodes() {
-        spawn_star(commands, meshes, materials, planet_materials, asset_server, model, node_idx);
+    let size = calculate_star_size(model, node_idx, *assets.size_mode, assets.settings.stars.scale);
+    commands.spawn((
+        TextMeshBundle {
+            text_mesh: TextMesh {
+                text: star_glyph(&model.nodes[node_idx]),
+                font: assets.asset_server.load("fonts/FiraMono-Medium.ttf"),
+                style: TextMeshStyle {
+                    depth: 0.1,
+                    subdivision: 6,
+                    ..default()
+                },
+            },
+            material: MeshMaterial3d(assets.label_material.0.clone()),
+            transform: Transform::from_translation(model.position(node_idx) + glyph_offset(size))
+                .with_scale(Vec3::splat(0.4)),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        FileLabel {
+            star_entity,
+            offset: glyph_offset(size),
+        },
+        StarGlyph,
+    ));
+}
+
+/// Spawn all stars for the initial file system
+pub fn spawn_galaxy(commands: &mut Commands, assets: &mut StarAssets, model: &FileSystemModel) {
+    for node_idx in model.live_indices() {
+        spawn_star(commands, assets, model, node_idx);
     }
 }
@@ -0,0 +1,71 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::agent::FileTotals;
+use crate::visit_store::Visits;
+
+/// One file's row in the exported report
+#[derive(Serialize)]
+struct FileReport<'a> {
+    /// Relative to the watched root when it's inside it
+    path: String,
+    /// Tool uses this run
+    events: usize,
+    by_tool: &'a BTreeMap<String, usize>,
+    sessions: &'a BTreeSet<String>,
+    /// Ship arrivals, including counts saved by earlier runs
+    visits: usize,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    root: String,
+    generated_at_ms: u128,
+    files: Vec<FileReport<'a>>,
+}
+
+fn now_ms() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0)
+}
+
+/// `space-agents-heatmap-<timestamp>.json` in the working directory, next to screenshots
+pub fn default_report_path() -> PathBuf {
+    PathBuf::from(format!("space-agents-heatmap-{}.json", now_ms()))
+}
+
+/// Write how often each file was touched, by which tools and sessions, busiest first
+pub fn write_report(
+    path: &Path,
+    root: &Path,
+    totals: &HashMap<PathBuf, FileTotals>,
+    visits: &Visits,
+) -> io::Result<()> {
+    let mut files: Vec<FileReport> = totals
+        .iter()
+        .map(|(file, totals)| FileReport {
+            path: file.strip_prefix(root).unwrap_or(file).display().to_string(),
+            events: totals.by_tool.values().sum(),
+            by_tool: &totals.by_tool,
+            sessions: &totals.sessions,
+            visits: visits.get(file).map_or(0, |counts| counts.visits),
+        })
+        .collect();
+    files.sort_by(|a, b| b.events.cmp(&a.events).then_with(|| a.path.cmp(&b.path)));
+
+    let report = Report {
+        root: root.display().to_string(),
+        generated_at_ms: now_ms(),
+        files,
+    };
+    let json = serde_json::to_string_pretty(&report).map_err(io::Error::other)?;
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, json)
+}
@@ -1,26 +1,51 @@
 // hello world
 mod agent;
+mod camera_store;
+mod control;
+mod file_colors;
 mod fs_model;
 mod galaxy;
+mod heatmap;
+mod minimap;
 mod planet_material;
+mod replay;
+mod settings;
+mod theme;
+mod tips;
+mod visit_store;
 mod watcher;
 mod ws_client;
 
 use agent::{
-    AgentArrivedEvent, AgentRegistry, FileEventHistory, HoveredFile, WsClientState,
-    agent_despawn_system, agent_state_machine, agent_transform_system, cleanup_agent_labels,
-    file_highlight_system, on_file_star_out, on_file_star_over, process_spaceship_materials,
-    process_ws_events, update_agent_action_bubble_content, update_agent_action_bubble_transforms,
-    update_agent_nameplates,
+    ActionBubbleSettings, AgentArrivedEvent, AgentFinishedEvent, AgentRegistry, FileEventHistory,
+    HoveredFile, PendingToolUses, PulseRingMesh, SelectedAgent, Spotlight, WsClientState,
+    agent_despawn_system, agent_state_machine, agent_transform_system, animate_pulse_rings,
+    apply_agent_spotlight, cleanup_agent_labels, draw_agent_target_lines, fade_trail_points,
+    file_highlight_system, on_agent_click, on_file_star_out, on_file_star_over,
+    process_spaceship_materials, process_ws_events, spawn_agent_status_rings, spawn_agent_trails,
+    spawn_pulse_rings, update_agent_action_bubble_content, update_agent_action_bubble_transforms,
+    update_agent_nameplates, update_agent_status_rings, update_spaceship_scenes,
 };
+use bevy::picking::hover::HoverMap;
 use bevy::picking::mesh_picking::MeshPickingPlugin;
+use bevy::picking::pointer::PointerId;
 use bevy::post_process::bloom::{Bloom, BloomCompositeMode, BloomPrefilter};
 use bevy::post_process::effect_stack::ChromaticAberration;
+use bevy::color::Mix;
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::asset::RenderAssetUsages;
+use bevy::camera::ScalingMode;
 use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
-use bevy::window::WindowResolution;
-use bevy_fontmesh::FontMeshPlugin;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk};
+use bevy::window::{PresentMode, WindowResolution};
+use bevy::winit::{UpdateMode, WinitSettings};
+use bevy_fontmesh::{FontMeshPlugin, TextMesh};
+use clap::Parser;
 use planet_material::PlanetMaterial;
 
 #[derive(Component)]
@@ -32,34 +57,275 @@ struct AmbientStar {
     orbit_speed: f32,
 }
 
+/// Directional light that slowly circles the galaxy when lighting animation is on
+#[derive(Component)]
+struct KeyLight;
+
+/// Colored point light whose hue drifts around its base color
+#[derive(Component)]
+struct AccentLight {
+    base_color: Color,
+    phase: f32,
+}
+
+#[derive(Resource)]
+struct LightingCycle {
+    enabled: bool,
+}
+
+const LIGHT_CYCLE_SECS: f32 = 180.0;
+
+const RECENCY_REFRESH_SECS: f32 = 1.0;
+
+const HIERARCHY_LINE_ALPHA: f32 = 0.25;
+const HIERARCHY_LINE_FADE_DISTANCE: f32 = 150.0;
+
+/// File-type glyphs under the stars, toggled with I (off by default: one more
+/// text mesh per star, so they only exist while shown)
+#[derive(Resource, Default)]
+struct StarGlyphs {
+    visible: bool,
+}
+
+/// Faint reference grid on the Y=0 plane, toggled with G
+#[derive(Resource, Default)]
+struct GroundGrid {
+    visible: bool,
+}
+
+const GRID_HALF_EXTENT: f32 = 80.0;
+const GRID_SPACING: f32 = 5.0;
+const GRID_ALPHA: f32 = 0.12;
+const GRID_FADE_DISTANCE: f32 = 120.0;
+
 #[derive(Component)]
 struct OrbitCircle {
     fade_speed: f32,
     phase_offset: f32,
 }
 use crossbeam_channel::Receiver;
-use fs_model::{FileSystemModel, GitignoreChecker, get_valid_paths};
-use galaxy::{FileLabel, FileStar, spawn_star};
-use std::collections::HashMap;
-use std::env;
-use std::path::PathBuf;
+use fs_model::{FileNode, FileSystemModel, GitignoreChecker, ModelLimits, get_valid_paths};
+use file_colors::FileColors;
+use galaxy::{
+    FileCategory, FileLabel, FileStar, StarGlyph, LabelMaterial, LayoutMode, StarAssets, StarColorMode,
+    StarDespawning, StarSizeMode, StarSpawnAnim,
+    spawn_star, spawn_star_glyph,
+};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use watcher::{FileSystemEvent, start_file_watcher, watch_directory};
-use ws_client::start_ws_client;
+use settings::{BLOOM_MAX, Settings};
+use theme::Theme;
+use visit_store::Visits;
+use ws_client::{AgentEvent, ConnectionStatus, start_ws_client};
+
+#[derive(Parser)]
+#[command(about = "Space Agents! codebase visualizer")]
+struct Args {
+    /// Directory to watch (defaults to the current directory)
+    path: Option<PathBuf>,
+
+    /// Fly the camera in from far away at startup
+    #[arg(long)]
+    fly_in: bool,
+
+    /// Color theme for the background, lights and file palette
+    #[arg(long, value_enum, default_value_t = Theme::default())]
+    theme: Theme,
+
+    /// Slowly rotate the key light and shift accent light colors over time
+    #[arg(long)]
+    animate_lighting: bool,
+
+    /// Don't open the welcome tips at startup (the ? button still shows them)
+    #[arg(long)]
+    no_tips: bool,
+
+    /// Start the Agent Activity panel grouped by top-level directory
+    #[arg(long)]
+    group_activity: bool,
+
+    /// Run the simulation (file model, WebSocket ingestion, agent state) without
+    /// a window, renderer or UI; for CI smoke runs
+    #[arg(long)]
+    headless: bool,
+
+    /// Maximum spaceships on screen; beyond this idle ones are reused or new sessions wait
+    #[arg(long, default_value_t = agent::DEFAULT_MAX_AGENTS)]
+    max_agents: usize,
+
+    /// Start with the force-directed layout instead of the spiral (toggle with L)
+    #[arg(long)]
+    force_layout: bool,
+
+    /// Only include directories this many levels below the root
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Show at most this many children per directory; the rest fold into a "…N more" star (click to expand)
+    #[arg(long)]
+    max_children: Option<usize>,
+
+    /// Hide file labels farther than this from the camera
+    #[arg(long, default_value_t = DEFAULT_LABEL_DISTANCE)]
+    label_distance: f32,
+
+    /// Directory labels stay visible out to this distance, so zoomed-out views show only directories
+    #[arg(long, default_value_t = DEFAULT_DIRECTORY_LABEL_DISTANCE)]
+    directory_label_distance: f32,
+
+    /// Widest an agent's action bubble gets (world units) before its text wraps onto a second line
+    #[arg(long, default_value_t = agent::DEFAULT_BUBBLE_MAX_WIDTH)]
+    bubble_max_width: f32,
+
+    /// Ask the server for the compact positional event format
+    #[arg(long)]
+    compact_events: bool,
+
+    /// Read `focus <path>` and `follow <session_id>` commands from stdin, one per line,
+    /// so a script can drive the camera during presentations
+    #[arg(long)]
+    stdin_control: bool,
+
+    /// Write the per-file heatmap report (also saved any time with H) here on exit
+    #[arg(long, value_name = "PATH")]
+    heatmap_on_exit: Option<PathBuf>,
+
+    /// WebSocket URL of the agent event server
+    #[arg(long, env = "AGENT_VIS_WS_URL", default_value = ws_client::DEFAULT_WS_URL)]
+    ws_url: String,
+}
 
 #[derive(Component)]
 struct CameraModeButton {
     mode: CameraMode,
 }
 
+#[derive(Component)]
+struct ResetViewButton;
+
 #[derive(Component)]
 struct AgentActionsContainer;
 
+/// Live agents ranked by how many files they've touched
+#[derive(Component)]
+struct LeaderboardContainer;
+
+/// (Greek symbol, color, files touched, tool uses)
+type LeaderboardRow = (String, Color, usize, usize);
+
+/// Switches the Agent Activity panel between a flat list and per-directory groups
+#[derive(Component)]
+struct ActivityGroupToggle;
+
+/// Directory header in the grouped Agent Activity panel; click to fold it
+#[derive(Component)]
+struct ActivityGroupHeader(String);
+
+#[derive(Resource, Default)]
+struct ActivityGrouping {
+    by_directory: bool,
+    /// Directory groups folded down to their header
+    collapsed: HashSet<String>,
+}
+
+/// (session id, action, color, Greek symbol, top-level directory)
+type ActivityRow = (String, String, Color, String, String);
+
+/// Everything the Agent Activity panel was last built from
+#[derive(PartialEq)]
+struct ActivityPanelKey {
+    rows: Vec<ActivityRow>,
+    by_directory: bool,
+    collapsed: Vec<String>,
+    font_size: f32,
+}
+
+/// What the Agent Activity panel needs to group agents and skip unchanged rebuilds
+#[derive(SystemParam)]
+struct ActivityPanel<'w, 's> {
+    grouping: Res<'w, ActivityGrouping>,
+    fs_state: Res<'w, FileSystemState>,
+    shown: Local<'s, Option<ActivityPanelKey>>,
+}
+
+impl ActivityPanel<'_, '_> {
+    /// Top-level directory of the agent's current file, e.g. "src/";
+    /// files in the project root (or no file yet) group under "./"
+    fn directory_of(&self, agent: &agent::Agent) -> String {
+        let model = &self.fs_state.model;
+        let mut node = agent.current_target_file.and_then(|idx| model.get_node(idx));
+        while let Some(current) = node
+            && current.depth > 1
+        {
+            node = current.parent.and_then(|idx| model.get_node(idx));
+        }
+        match node {
+            Some(top) if top.depth == 1 && top.is_dir() => format!("{}/", top.name),
+            _ => "./".to_string(),
+        }
+    }
+}
+
 #[derive(Component)]
 struct FileStatsContainer;
 
 #[derive(Component)]
 struct ColorLegendContainer;
 
+/// HUD panel that a number key shows and hides
+#[derive(Component)]
+struct HudToggle {
+    key: KeyCode,
+}
+
+/// Camera distances beyond which star labels are hidden
+#[derive(Resource)]
+struct LabelLod {
+    file_distance: f32,
+    directory_distance: f32,
+}
+
+const DEFAULT_LABEL_DISTANCE: f32 = 60.0;
+const DEFAULT_DIRECTORY_LABEL_DISTANCE: f32 = 150.0;
+
+/// Screen box (pixels) around a shown label in which farther labels are hidden
+const LABEL_DECLUTTER_WIDTH: f32 = 80.0;
+const LABEL_DECLUTTER_HEIGHT: f32 = 16.0;
+
+/// Legend row that toggles a file category on and off
+#[derive(Component)]
+struct LegendToggle {
+    category: FileCategory,
+}
+
+/// File categories currently shown; toggled from the color legend
+#[derive(Resource)]
+struct VisibleTypes {
+    enabled: HashSet<FileCategory>,
+}
+
+impl Default for VisibleTypes {
+    fn default() -> Self {
+        Self {
+            enabled: FileCategory::ALL.into_iter().collect(),
+        }
+    }
+}
+
+impl VisibleTypes {
+    fn is_visible(&self, category: FileCategory) -> bool {
+        self.enabled.contains(&category)
+    }
+
+    fn toggle(&mut self, category: FileCategory) {
+        if !self.enabled.remove(&category) {
+            self.enabled.insert(category);
+        }
+    }
+}
+
 #[derive(Component)]
 struct PromptContainer;
 
@@ -69,6 +335,45 @@ struct PromptInputField;
 #[derive(Component)]
 struct PromptSubmitButton;
 
+#[derive(Component)]
+struct SearchInputField;
+
+/// Marks a star whose name matches the current search query
+#[derive(Component)]
+struct SearchMatch;
+
+/// Panel listing the selected agent's recent tool uses
+#[derive(Component)]
+struct AgentHistoryPanel;
+
+#[derive(Component)]
+struct AgentHistoryCloseButton;
+
+/// Toggles the spotlight on the agent whose history panel is open
+#[derive(Component)]
+struct AgentSpotlightButton;
+
+/// Rows shown in the agent history panel (the agent keeps more)
+const AGENT_HISTORY_PANEL_ROWS: usize = 15;
+
+/// Column of "agent finished" toasts in the bottom right, above the help button
+#[derive(Component)]
+struct ToastContainer;
+
+/// Bottom left column right of the camera mode panel: the minimap, with the
+/// connection indicator stacked above it
+#[derive(Component)]
+pub struct MinimapColumn;
+
+#[derive(Component)]
+struct Toast {
+    age: f32,
+}
+
+const TOAST_LIFETIME: f32 = 4.0;
+const TOAST_FADE_IN: f32 = 0.3;
+const TOAST_FADE_OUT: f32 = 1.0;
+
 #[derive(Component)]
 struct HelpButton;
 
@@ -88,6 +393,80 @@ struct IdleSpaceship {
 struct PromptInputState {
     text: String,
     is_focused: bool,
+    /// Previously submitted prompts, oldest first
+    history: Vec<String>,
+    /// Index into `history` while browsing with Up/Down; None means editing the draft
+    history_cursor: Option<usize>,
+    /// In-progress text saved when history browsing starts, restored past the newest entry
+    draft: String,
+}
+
+/// File search box state; the query is matched case-insensitively against star names
+#[derive(Resource, Default)]
+struct SearchState {
+    query: String,
+    is_focused: bool,
+}
+
+/// Whether the prompt or search box has the keyboard; hotkeys and camera keys
+/// stand down while it does
+#[derive(SystemParam)]
+struct TextInputFocus<'w> {
+    prompt: Res<'w, PromptInputState>,
+    search: Res<'w, SearchState>,
+}
+
+impl TextInputFocus<'_> {
+    fn is_typing(&self) -> bool {
+        self.prompt.is_focused || self.search.is_focused
+    }
+}
+
+/// Run condition for keyboard shortcuts that would clash with typing
+fn not_typing(text_focus: TextInputFocus) -> bool {
+    !text_focus.is_typing()
+}
+
+const SEARCH_MATCH_BOOST: f32 = 3.0;
+const SEARCH_DIM_FACTOR: f32 = 0.15;
+const SPOTLIGHT_TOUCHED_BOOST: f32 = 2.5;
+const SPOTLIGHT_DIM_FACTOR: f32 = 0.1;
+
+impl SearchState {
+    fn matches(&self, name: &str) -> bool {
+        !self.query.is_empty() && name.to_lowercase().contains(&self.query)
+    }
+
+    /// Multiplier applied to a star's emissive while a search is active
+    fn emissive_scale(&self, name: &str) -> f32 {
+        if self.query.is_empty() {
+            1.0
+        } else if self.matches(name) {
+            SEARCH_MATCH_BOOST
+        } else {
+            SEARCH_DIM_FACTOR
+        }
+    }
+}
+
+/// Search and spotlight together decide how brightly each star glows
+#[derive(SystemParam)]
+struct StarEmphasis<'w> {
+    search: Res<'w, SearchState>,
+    spotlight: Res<'w, Spotlight>,
+    history: Res<'w, FileEventHistory>,
+}
+
+impl StarEmphasis<'_> {
+    /// Multiplier applied to a star's emissive
+    fn emissive_scale(&self, node: &FileNode) -> f32 {
+        let spotlight_scale = match &self.spotlight.0 {
+            None => 1.0,
+            Some(session_id) if self.history.touched_by(&node.path, session_id) => SPOTLIGHT_TOUCHED_BOOST,
+            Some(_) => SPOTLIGHT_DIM_FACTOR,
+        };
+        self.search.emissive_scale(&node.name) * spotlight_scale
+    }
 }
 
 #[derive(Component)]
@@ -102,17 +481,97 @@ struct PendingAgentTask {
     task_description: String,
 }
 
+/// Sends typed prompts to the server's /launch endpoint and collects the replies
+#[derive(Resource)]
+struct LaunchClient {
+    server_address: String,
+    sender: crossbeam_channel::Sender<ws_client::LaunchResult>,
+    receiver: Receiver<ws_client::LaunchResult>,
+}
+
+/// F3 performance readout: FPS and entity/asset counts
+#[derive(Component)]
+struct DebugOverlay;
+
+/// Space freezes all motion; file-system and WebSocket events keep buffering
+#[derive(Resource, Default)]
+struct Paused(bool);
+
+#[derive(Component)]
+struct PausedIndicator;
+
+/// WebSocket connection state as last reported by the client thread
+#[derive(Resource)]
+struct ServerConnection {
+    receiver: Receiver<ConnectionStatus>,
+    status: ConnectionStatus,
+}
+
+/// Colored dot of the connection indicator above the minimap
+#[derive(Component)]
+struct ConnectionDot;
+
+#[derive(Component)]
+struct ConnectionLabel;
+
+/// O swaps the camera to an orthographic projection for distortion-free recordings
+#[derive(Resource, Default)]
+struct OrthographicView(bool);
+
+/// Bloom and chromatic aberration readout, shown for a moment after [ ] - = adjust them
+#[derive(Component, Default)]
+struct PostFxReadout {
+    remaining: f32, // seconds left on screen
+}
+
+const BLOOM_STEP: f32 = 0.05;
+const ABERRATION_STEP: f32 = 0.002;
+const ABERRATION_MAX: f32 = 0.03;
+const POST_FX_READOUT_SECS: f32 = 2.0;
+
+/// Run condition for systems that animate the scene
+fn is_running(paused: Res<Paused>) -> bool {
+    !paused.0
+}
+
 #[derive(Resource)]
 struct TipsState {
     visible: bool,
+    /// Dismissed at least once, in this run or an earlier one
     has_been_shown: bool,
+    tips: Vec<String>,
 }
 
+/// Visit and edit counts from earlier runs on this project; this run's are
+/// counted as tool uses come in, in FileEventHistory's totals
 #[derive(Resource, Default)]
 struct FileStats {
-    visits: HashMap<PathBuf, usize>,
+    saved: Visits,
+}
+
+impl FileStats {
+    /// Earlier runs' counts with this run's added on
+    fn counts(&self, history: &FileEventHistory) -> Visits {
+        let mut counts = self.saved.clone();
+        for (path, totals) in &history.totals {
+            let run = totals.counts();
+            let counts = counts.entry(path.clone()).or_default();
+            counts.visits += run.visits;
+            counts.edits += run.edits;
+        }
+        counts
+    }
+}
+
+/// Where the heatmap report goes when the app closes, if anywhere
+#[derive(Resource)]
+struct HeatmapExport {
+    exit_path: Option<PathBuf>,
 }
 
+/// How often changed visit counts are written out, on top of the save at exit
+const VISIT_SAVE_INTERVAL: f32 = 30.0;
+
 #[derive(Component)]
 struct FileHoverPanel;
 
@@ -138,11 +597,67 @@ struct FileSystemState {
     _watcher_handle: watcher::FileWatcherHandle,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Last star click, used to detect double-clicks
+#[derive(Resource, Default)]
+struct LastStarClick {
+    node_index: Option<usize>,
+    time: f32,
+}
+
+/// A directory star was folded or unfolded
+#[derive(Message)]
+struct DirectoryToggled;
+
+/// "…N more" stars clicked this frame, expanded by expand_aggregates
+#[derive(Resource, Default)]
+struct PendingExpansions {
+    nodes: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 enum CameraMode {
     Auto,
     Manual,
     Follow,
+    TopDown,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CameraPose {
+    distance: f32,
+    angle: f32,
+    height: f32,
+}
+
+const DEFAULT_CAMERA_POSE: CameraPose = CameraPose {
+    distance: 40.0,
+    angle: 0.0,
+    height: 20.0,
+};
+const DEFAULT_TOP_DOWN_HEIGHT: f32 = 90.0;
+
+/// The starting (and Reset view) pose, pulled inside the configured camera limits
+fn default_camera_pose(camera: &settings::CameraSettings) -> CameraPose {
+    CameraPose {
+        distance: camera.clamp_distance(DEFAULT_CAMERA_POSE.distance),
+        angle: DEFAULT_CAMERA_POSE.angle,
+        height: camera.clamp_height(DEFAULT_CAMERA_POSE.height),
+    }
+}
+
+const FLY_IN_START_POSE: CameraPose = CameraPose {
+    distance: 160.0,
+    angle: -1.2,
+    height: 90.0,
+};
+const FLY_IN_DURATION: f32 = 2.5;
+
+/// One-shot eased camera move between two poses
+struct CameraTransition {
+    from: CameraPose,
+    to: CameraPose,
+    elapsed: f32,
+    duration: f32,
 }
 
 #[derive(Resource)]
@@ -151,33 +666,119 @@ struct CameraController {
     orbit_distance: f32,
     orbit_angle: f32,
     orbit_height: f32,
+    // Freezes the camera in place while everything else keeps animating
+    camera_frozen: bool,
+    // Active transition takes priority over the normal camera modes
+    transition: Option<CameraTransition>,
+    // Top-down mode state
+    pan_target: Vec3,
+    top_down_height: f32,
     // Manual mode state
     is_dragging: bool,
     last_mouse_pos: Option<Vec2>,
 }
 
+impl CameraController {
+    fn pose(&self) -> CameraPose {
+        CameraPose {
+            distance: self.orbit_distance,
+            angle: self.orbit_angle,
+            height: self.orbit_height,
+        }
+    }
+
+    fn set_pose(&mut self, pose: CameraPose) {
+        self.orbit_distance = pose.distance;
+        self.orbit_angle = pose.angle;
+        self.orbit_height = pose.height;
+    }
+
+    /// Ease from the current pose to `to` over `duration` seconds
+    fn start_transition(&mut self, to: CameraPose, duration: f32) {
+        self.transition = Some(CameraTransition {
+            from: self.pose(),
+            to,
+            elapsed: 0.0,
+            duration,
+        });
+    }
+}
+
+/// Frames per second of the --headless update loop
+const HEADLESS_TICK_RATE: f64 = 60.0;
+
+/// Unfocused windows never update faster than this, as in Bevy's default
+const UNFOCUSED_MAX_FPS: f32 = 60.0;
+
+/// Update pacing for a `display.max_fps` cap. Between frames winit sleeps until
+/// the frame time is up, waking early only for window input so the controls stay
+/// responsive. `None` (no cap) keeps Bevy's continuous updates.
+fn frame_pacing(max_fps: f32) -> Option<WinitSettings> {
+    if max_fps <= 0.0 {
+        return None;
+    }
+    let frame_time = |fps: f32| Duration::from_secs_f32(1.0 / fps);
+    Some(WinitSettings {
+        focused_mode: UpdateMode::reactive_low_power(frame_time(max_fps)),
+        unfocused_mode: UpdateMode::reactive_low_power(frame_time(max_fps.min(UNFOCUSED_MAX_FPS))),
+    })
+}
+
+/// The systems that keep the file model and the agents up to date, shared by
+/// the windowed app and --headless so the two can't drift apart
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+enum Simulation {
+    /// Stars queued at startup, watcher events and expanded aggregates
+    FileModel,
+    /// Incoming agent events (including tool uses retried once their file exists) and agent movement
+    Agents,
+}
+
 fn main() {
+    build_app(Args::parse(), None).run();
+}
+
+/// Everything `main` runs: the watcher, the WebSocket client and the app with
+/// its resources and systems, trimmed down to the simulation under --headless.
+/// `agent_events` stands in for the WebSocket client, so tests run without a server.
+fn build_app(args: Args, agent_events: Option<Receiver<AgentEvent>>) -> App {
     // Get directory to watch from command line args
-    let args: Vec<String> = env::args().collect();
-    let watch_path = if args.len() > 1 {
-        PathBuf::from(&args[1])
-    } else {
-        println!("Usage: {} <directory-to-watch>", args[0]);
+    let watch_path = args.path.clone().unwrap_or_else(|| {
         println!("No directory specified, watching current directory");
         PathBuf::from(".")
-    };
+    });
 
-    // Canonicalize the path
-    let watch_path = watch_path
-        .canonicalize()
-        .expect("Failed to resolve watch path");
+    // Canonicalize the path; a typo here is the most common first-run mistake
+    let watch_path = match watch_path.canonicalize() {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Error: cannot open {}: {}", watch_path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    if !watch_path.is_dir() {
+        eprintln!("Error: {} is not a directory", watch_path.display());
+        std::process::exit(1);
+    }
 
     println!("Watching directory: {}", watch_path.display());
 
     // Build file system model eagerly so the resource is available to all startup systems
     println!("Building file system model...");
-    let model = FileSystemModel::build_initial(watch_path.clone());
-    println!("Found {} files/directories", model.total_nodes());
+    let limits = ModelLimits {
+        max_depth: args.max_depth,
+        max_children: args.max_children,
+    };
+    let mut model = FileSystemModel::build_initial(watch_path.clone(), limits);
+    println!("Found {} files/directories", model.node_count());
+
+    let layout_mode = if args.force_layout {
+        LayoutMode::ForceDirected
+    } else {
+        LayoutMode::Spiral
+    };
+    let layout = galaxy::compute_layout(&model, layout_mode);
+    model.set_layout(layout);
 
     let gitignore_checker = GitignoreChecker::new(&watch_path);
 
@@ -186,13 +787,61 @@ fn main() {
     let handle = watch_directory(handle, watch_path.clone());
 
     // Start WebSocket client
-    let (ws_rx, _ws_handle) = start_ws_client();
-
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
+    let ws_url = if args.compact_events {
+        let separator = if args.ws_url.contains('?') { '&' } else { '?' };
+        format!("{}{}format=compact", args.ws_url, separator)
+    } else {
+        args.ws_url.clone()
+    };
+    let (connection_tx, connection_rx) = crossbeam_channel::unbounded();
+    let ws_rx = match agent_events {
+        Some(receiver) => receiver,
+        None => start_ws_client(ws_url, watch_path.clone(), handle.injector(), connection_tx).0,
+    };
+    let (launch_tx, launch_rx) = crossbeam_channel::unbounded();
+
+    let settings = settings::load_settings();
+    let start_pose = default_camera_pose(&settings.camera);
+    let mut camera_controller = CameraController {
+        mode: CameraMode::Auto,
+        orbit_distance: start_pose.distance,
+        orbit_angle: start_pose.angle,
+        orbit_height: start_pose.height,
+        camera_frozen: false,
+        transition: None,
+        pan_target: Vec3::ZERO,
+        top_down_height: settings.camera.clamp_top_down_height(DEFAULT_TOP_DOWN_HEIGHT),
+        is_dragging: false,
+        last_mouse_pos: None,
+    };
+    if args.fly_in {
+        camera_controller.set_pose(FLY_IN_START_POSE);
+        camera_controller.start_transition(start_pose, FLY_IN_DURATION);
+    }
+    let tips_seen = tips::has_been_shown();
+
+    let mut app = App::new();
+    if args.headless {
+        // Just the asset types the simulation creates handles for; nothing renders them
+        app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+            1.0 / HEADLESS_TICK_RATE,
+        ))))
+        .add_plugins(AssetPlugin::default())
+        .add_plugins(FontMeshPlugin)
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_asset::<PlanetMaterial>()
+        .init_asset::<Scene>();
+    } else {
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Space Agents!".to_string(),
                 resolution: WindowResolution::new(1920, 1080),
+                present_mode: if settings.display.vsync {
+                    PresentMode::AutoVsync
+                } else {
+                    PresentMode::AutoNoVsync
+                },
                 ..default()
             }),
             ..default()
@@ -200,15 +849,15 @@ fn main() {
         .add_plugins(FontMeshPlugin)
         .add_plugins(MaterialPlugin::<PlanetMaterial>::default())
         .add_plugins(MeshPickingPlugin)
-        .insert_resource(ClearColor(Color::srgb(0.05, 0.02, 0.15))) // Deep purple background
-        .insert_resource(CameraController {
-            mode: CameraMode::Auto,
-            orbit_distance: 40.0,
-            orbit_angle: 0.0,
-            orbit_height: 20.0,
-            is_dragging: false,
-            last_mouse_pos: None,
-        })
+        .add_plugins(FrameTimeDiagnosticsPlugin::default());
+        if let Some(winit_settings) = frame_pacing(settings.display.max_fps) {
+            app.insert_resource(winit_settings);
+        }
+    }
+
+    app.insert_resource(ClearColor(args.theme.clear_color()))
+        .insert_resource(args.theme)
+        .insert_resource(camera_controller)
         .insert_resource(FileSystemState {
             model,
             event_receiver: rx,
@@ -218,90 +867,275 @@ fn main() {
             _watcher_handle: handle,
         })
         .insert_resource(WsClientState { receiver: ws_rx })
-        .insert_resource(AgentRegistry::default())
+        .insert_resource(AgentRegistry::with_max_agents(args.max_agents))
+        .insert_resource(ActionBubbleSettings {
+            max_width: args.bubble_max_width,
+        })
         .insert_resource(FileStats::default())
+        .insert_resource(HeatmapExport {
+            exit_path: args.heatmap_on_exit.clone(),
+        })
         .insert_resource(FileEventHistory::default())
+        .insert_resource(PendingToolUses::default())
         .insert_resource(HoveredFile::default())
+        .insert_resource(SelectedAgent::default())
+        .init_resource::<replay::ReplayGhostMesh>()
+        .init_resource::<PulseRingMesh>()
+        .init_resource::<LabelMaterial>()
+        .insert_resource(Spotlight::default())
+        .insert_resource(StarColorMode::default())
+        .insert_resource(StarSizeMode::default())
+        .insert_resource(file_colors::load_file_colors(args.theme))
+        .insert_resource(settings)
+        .insert_resource(VisibleTypes::default())
+        .insert_resource(GroundGrid::default())
+        .insert_resource(StarGlyphs::default())
+        .insert_resource(layout_mode)
+        .insert_resource(LabelLod {
+            file_distance: args.label_distance,
+            directory_distance: args.directory_label_distance,
+        })
+        .insert_resource(LastStarClick::default())
+        .insert_resource(PendingExpansions::default())
+        .insert_resource(LightingCycle {
+            enabled: args.animate_lighting,
+        })
         .insert_resource(PromptInputState::default())
+        .insert_resource(SearchState::default())
         .insert_resource(PendingAgentTask::default())
+        .insert_resource(replay::EventLog::default())
+        .insert_resource(Paused::default())
+        .insert_resource(ActivityGrouping {
+            by_directory: args.group_activity,
+            collapsed: HashSet::new(),
+        })
+        .insert_resource(OrthographicView::default())
+        .insert_resource(LaunchClient {
+            server_address: ws_client::server_address(&args.ws_url),
+            sender: launch_tx,
+            receiver: launch_rx,
+        })
         .insert_resource(TipsState {
-            visible: true, // Show on first load
-            has_been_shown: false,
+            // Show on the very first run only
+            visible: !args.no_tips && !tips_seen,
+            has_been_shown: tips_seen,
+            tips: tips::load_tips(),
         })
         .add_message::<AgentArrivedEvent>()
-        .add_observer(on_file_star_over)
+        .add_message::<AgentFinishedEvent>()
+        .add_message::<DirectoryToggled>();
+    if args.headless {
+        // Nothing shows the connection state, so don't let its updates pile up
+        drop(connection_rx);
+    } else {
+        app.insert_resource(ServerConnection {
+            receiver: connection_rx,
+            status: ConnectionStatus::Connecting,
+        });
+    }
+    if args.stdin_control {
+        println!("[control] Reading focus/follow commands from stdin");
+        app.insert_resource(control::start_stdin_control());
+    }
+
+    app.configure_sets(Update, Simulation::FileModel.before(Simulation::Agents))
+        .add_systems(Startup, setup_galaxy)
+        .add_systems(Last, (save_file_stats, export_heatmap_on_exit))
+        .add_systems(
+            Update,
+            (
+                spawn_queued_stars,
+                expand_aggregates,
+                update_file_system,
+                move_replaced_stars.after(update_file_system).after(expand_aggregates),
+            )
+                .in_set(Simulation::FileModel),
+        )
+        .add_systems(
+            Update,
+            (
+                process_ws_events,
+                // Agents hold still (and hidden) while the timeline is being replayed
+                // and freeze in place while paused
+                agent_state_machine.run_if(replay::is_live).run_if(is_running),
+                agent_transform_system.run_if(replay::is_live).run_if(is_running),
+                agent_despawn_system.run_if(replay::is_live).run_if(is_running),
+            )
+                .chain()
+                .in_set(Simulation::Agents),
+        )
+        .add_systems(
+            Update,
+            handle_control_commands
+                .run_if(resource_exists::<control::ControlChannel>)
+                .after(Simulation::Agents),
+        );
+    if args.headless {
+        return app;
+    }
+
+    app.add_observer(on_file_star_over)
         .add_observer(on_file_star_out)
+        .add_observer(on_file_star_click)
+        .add_observer(on_agent_click)
         .add_systems(
             Startup,
             (
                 setup_camera,
                 setup_lighting,
-                setup_galaxy,
                 setup_ui,
                 setup_vignette,
                 setup_ambient_stars,
                 setup_orbit_circles,
+                load_file_stats,
+                replay::setup_replay_bar,
+                setup_debug_overlay,
+                setup_pause_indicator,
+                setup_post_fx_readout,
+                (minimap::setup_minimap, setup_connection_indicator).chain().after(setup_ui),
+                restore_camera_state,
             ),
         )
+        .add_systems(Last, save_camera_state)
         .add_systems(
             Update,
             (
-                update_file_system,
-                handle_camera_mode_buttons,
-                update_camera,
-                handle_manual_camera_input,
                 billboard_labels,
                 update_agent_nameplates,
                 update_agent_action_bubble_transforms,
-                update_agent_action_bubble_content,
+                update_agent_action_bubble_content.run_if(replay::is_live),
+                (spawn_agent_status_rings, update_agent_status_rings.run_if(replay::is_live)).chain(),
                 cleanup_agent_labels,
+                // Clicks land on last frame's rows, before they're rebuilt
+                handle_activity_panel_clicks.before(update_agent_actions_display),
                 update_agent_actions_display,
+                update_agent_leaderboard,
                 update_file_stats_display,
-                track_file_visits,
                 update_file_hover_panel,
-                animate_ambient_stars,
-                animate_orbit_circles,
+                animate_ambient_stars.run_if(is_running),
+                animate_orbit_circles.run_if(is_running),
+                animate_lighting,
                 hover_glow_system,
             ),
         )
         .add_systems(
             Update,
             (
-                handle_prompt_focus,
-                handle_prompt_unfocus,
-                handle_prompt_input,
-                handle_prompt_submit,
-                apply_pending_agent_tasks,
-                update_prompt_display,
-                animate_cursor,
-                handle_help_button,
-                handle_close_overlay,
-                update_tips_overlay,
-            ),
+                cycle_agent_focus.run_if(not_typing),
+                handle_agent_history_close,
+                handle_agent_spotlight_button,
+                dismiss_agent_history_on_empty_click,
+                update_agent_history_panel,
+            )
+                .chain(),
         )
+        .add_systems(Update, (animate_star_spawns, animate_star_despawns).after(Simulation::FileModel))
+        .add_systems(Update, (spawn_session_toasts, update_toasts))
+        .add_systems(Update, update_connection_indicator)
+        .add_systems(Update, (toggle_debug_overlay, update_debug_overlay).chain())
+        .add_systems(Update, (minimap::handle_minimap_click, minimap::draw_minimap))
         .add_systems(
             Update,
             (
-                process_ws_events,
-                agent_state_machine,
-                agent_transform_system,
-                agent_despawn_system,
-                file_highlight_system,
-                process_spaceship_materials,
+                replay::handle_replay_controls,
+                replay::advance_playback,
+                replay::sync_replay_visibility,
+                replay::update_replay_ghosts,
+                replay::apply_replay_highlights,
+                replay::update_replay_bar,
             )
                 .chain(),
         )
-        .run();
-}
-
-fn setup_camera(mut commands: Commands) {
-    // Spawn 3D camera with bloom
-    commands.spawn((
-        Camera3d::default(),
-        Transform::from_xyz(30.0, 20.0, 30.0).looking_at(Vec3::ZERO, Vec3::Y),
-        Bloom {
-            intensity: 0.2,
-            low_frequency_boost: 0.3,
+        .add_systems(
+            Update,
+            (
+                handle_color_mode_toggle.run_if(not_typing),
+                handle_size_mode_toggle.run_if(not_typing),
+                apply_star_size_mode.after(handle_size_mode_toggle),
+                handle_layout_mode_toggle.run_if(not_typing),
+                apply_star_color_mode,
+                apply_spotlight_to_stars.after(apply_star_color_mode),
+                update_color_legend,
+                handle_legend_toggles,
+                toggle_clicked_directory,
+                apply_type_visibility.after(toggle_clicked_directory),
+                apply_label_lod.after(apply_type_visibility),
+                draw_hierarchy_lines,
+                handle_grid_toggle.run_if(not_typing),
+                draw_ground_grid,
+                (handle_glyph_toggle.run_if(not_typing), sync_star_glyphs)
+                    .chain()
+                    .after(Simulation::FileModel)
+                    .before(apply_label_lod),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_camera_mode_buttons,
+                handle_reset_view_button,
+                handle_camera_freeze_toggle.run_if(not_typing),
+                handle_projection_toggle.run_if(not_typing),
+                handle_screenshot_keys,
+                handle_heatmap_export_key.run_if(not_typing),
+                handle_pause_toggle.run_if(not_typing),
+                handle_hud_toggles.run_if(not_typing),
+                handle_post_fx_keys,
+                advance_camera_transition,
+                update_camera,
+                sync_camera_projection.after(update_camera),
+                handle_manual_camera_input,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_prompt_focus,
+                handle_prompt_unfocus,
+                handle_prompt_input,
+                handle_prompt_submit,
+                apply_pending_agent_tasks,
+                reconcile_launched_sessions,
+                update_prompt_display,
+                handle_search_focus,
+                handle_search_unfocus,
+                handle_search_input,
+                update_search_display,
+                apply_search_highlight,
+                animate_cursor,
+                handle_help_button,
+                handle_close_overlay,
+                update_tips_overlay,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                draw_agent_target_lines.run_if(replay::is_live),
+                spawn_agent_trails.run_if(replay::is_live).run_if(is_running),
+                fade_trail_points.run_if(is_running),
+                file_highlight_system.run_if(is_running),
+                spawn_pulse_rings,
+                animate_pulse_rings.run_if(is_running),
+                process_spaceship_materials,
+                apply_agent_spotlight,
+                // After recoloring, so a swapped ship is recolored once its new scene spawns
+                update_spaceship_scenes,
+            )
+                .chain()
+                .after(Simulation::Agents),
+        );
+    app
+}
+
+fn setup_camera(mut commands: Commands, settings: Res<Settings>) {
+    // Spawn 3D camera with bloom
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(30.0, 20.0, 30.0).looking_at(Vec3::ZERO, Vec3::Y),
+        Bloom {
+            intensity: settings.bloom.intensity,
+            low_frequency_boost: 0.3,
             low_frequency_boost_curvature: 0.95,
             high_pass_frequency: 1.0,
             composite_mode: BloomCompositeMode::Additive,
@@ -319,7 +1153,9 @@ fn setup_camera(mut commands: Commands) {
     ));
 }
 
-fn setup_lighting(mut commands: Commands) {
+fn setup_lighting(mut commands: Commands, theme: Res<Theme>) {
+    let [top_color, bottom_left_color, right_color] = theme.accent_colors();
+
     // Dim directional light to let stars bloom
     commands.spawn((
         DirectionalLight {
@@ -328,51 +1164,97 @@ fn setup_lighting(mut commands: Commands) {
             ..default()
         },
         Transform::from_xyz(10.0, 20.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        KeyLight,
     ));
 
     // Add ambient colored point lights for gradient feel
-    // Pink light from top
+    // Theme accent light from top (pink by default)
     commands.spawn((
         PointLight {
-            color: Color::srgb(1.0, 0.3, 0.7),
+            color: top_color,
             intensity: 100000.0,
             range: 100.0,
             ..default()
         },
         Transform::from_xyz(0.0, 30.0, 0.0),
+        AccentLight {
+            base_color: top_color,
+            phase: 0.0,
+        },
     ));
 
-    // Blue light from bottom left
+    // Bottom left (blue by default)
     commands.spawn((
         PointLight {
-            color: Color::srgb(0.2, 0.5, 1.0),
+            color: bottom_left_color,
             intensity: 80000.0,
             range: 100.0,
             ..default()
         },
         Transform::from_xyz(-30.0, -10.0, -30.0),
+        AccentLight {
+            base_color: bottom_left_color,
+            phase: 2.1,
+        },
     ));
 
-    // Purple light from right
+    // Right (purple by default)
     commands.spawn((
         PointLight {
-            color: Color::srgb(0.6, 0.2, 0.9),
+            color: right_color,
             intensity: 90000.0,
             range: 100.0,
             ..default()
         },
         Transform::from_xyz(30.0, 0.0, 30.0),
+        AccentLight {
+            base_color: right_color,
+            phase: 4.2,
+        },
     ));
 }
 
+fn animate_lighting(
+    time: Res<Time>,
+    cycle: Res<LightingCycle>,
+    mut key_lights: Query<&mut Transform, With<KeyLight>>,
+    mut accent_lights: Query<(&AccentLight, &mut PointLight)>,
+) {
+    if !cycle.enabled {
+        return;
+    }
+
+    let t = time.elapsed_secs() / LIGHT_CYCLE_SECS * std::f32::consts::TAU;
+
+    // Key light circles the galaxy while gently rising and setting,
+    // starting from its static position at (10, 20, 10)
+    for mut transform in key_lights.iter_mut() {
+        let angle = t + std::f32::consts::FRAC_PI_4;
+        let elevation = 20.0 + 10.0 * (t * 2.0).sin();
+        let position = Vec3::new(14.0 * angle.cos(), elevation, 14.0 * angle.sin());
+        *transform = Transform::from_translation(position).looking_at(Vec3::ZERO, Vec3::Y);
+    }
+
+    // Accent lights drift a little around their base hue
+    for (accent, mut light) in accent_lights.iter_mut() {
+        let shift = 25.0 * (t * 3.0 + accent.phase).sin();
+        light.color = accent.base_color.rotate_hue(shift);
+    }
+}
+
 fn setup_ambient_stars(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    theme: Res<Theme>,
+    settings: Res<Settings>,
 ) {
+    let palette = theme.ambient_star_colors();
+
     // Spawn dim colored stars in the background
-    let star_count = 100;
-    let range = 80.0;
+    let star_count = settings.quality.ambient_star_count();
+    let spread = settings.quality.ambient_star_spread();
+    let range = 80.0 * spread;
 
     for i in 0..star_count {
         let t = i as f32 / star_count as f32;
@@ -380,23 +1262,14 @@ fn setup_ambient_stars(
         // Random-ish position using pseudo-random distribution
         let angle1 = t * std::f32::consts::TAU * 7.0;
         let angle2 = t * std::f32::consts::TAU * 13.0;
-        let radius = 50.0 + (t * 30.0);
+        let radius = 50.0 + (t * 30.0 * spread);
 
         let x = radius * angle1.cos() * angle2.sin();
         let y = (t - 0.5) * range * 2.0;
         let z = radius * angle1.sin() * angle2.cos();
 
-        // Color from palette: pinks, purples, yellows, blues
-        let color_choice = (i % 4) as f32 / 4.0;
-        let base_color = if color_choice < 0.25 {
-            Color::srgb(1.0, 0.4, 0.7) // Pink
-        } else if color_choice < 0.5 {
-            Color::srgb(0.6, 0.3, 1.0) // Purple
-        } else if color_choice < 0.75 {
-            Color::srgb(1.0, 0.9, 0.4) // Yellow
-        } else {
-            Color::srgb(0.4, 0.7, 1.0) // Blue
-        };
+        // Color from the theme's palette (pinks, purples, yellows, blues by default)
+        let base_color = palette[i % palette.len()];
 
         let pos = Vec3::new(x, y, z);
 
@@ -421,14 +1294,20 @@ fn setup_ambient_stars(
 
 fn animate_ambient_stars(
     time: Res<Time>,
+    theme: Res<Theme>,
+    settings: Res<Settings>,
+    mut clock: Local<f32>,
     mut query: Query<(&AmbientStar, &mut Transform, &mut MeshMaterial3d<StandardMaterial>)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
+    // Accumulated rather than elapsed time, so unpausing resumes where it froze
+    *clock += time.delta_secs();
+    let cycle_colors = settings.quality.cycles_ambient_colors();
     for (ambient_star, mut transform, material_handle) in query.iter_mut() {
-        let t = time.elapsed_secs() * ambient_star.speed + ambient_star.color_offset;
+        let t = *clock * ambient_star.speed + ambient_star.color_offset;
 
         // Gentle orbital movement around initial position
-        let orbit_t = time.elapsed_secs() * ambient_star.orbit_speed;
+        let orbit_t = *clock * ambient_star.orbit_speed;
         let offset = Vec3::new(
             ambient_star.orbit_radius * orbit_t.cos(),
             ambient_star.orbit_radius * (orbit_t * 0.5).sin() * 0.5,
@@ -437,13 +1316,16 @@ fn animate_ambient_stars(
 
         transform.translation = ambient_star.initial_pos + offset;
 
-        // Cycle through colors smoothly
+        // Cycle smoothly through the theme's palette
+        if !cycle_colors {
+            continue;
+        }
         if let Some(material) = materials.get_mut(&material_handle.0) {
-            let color = Color::srgb(
-                0.5 + 0.5 * (t).sin(),
-                0.5 + 0.5 * (t + 2.0).sin(),
-                0.5 + 0.5 * (t + 4.0).sin(),
-            );
+            let palette = theme.ambient_star_colors();
+            let phase = (t / std::f32::consts::TAU).rem_euclid(1.0) * palette.len() as f32;
+            let from = phase as usize % palette.len();
+            let to = (from + 1) % palette.len();
+            let color = palette[from].mix(&palette[to], phase.fract());
 
             material.base_color = color;
             material.emissive = LinearRgba::from(color) * 0.3;
@@ -453,12 +1335,14 @@ fn animate_ambient_stars(
 
 fn animate_orbit_circles(
     time: Res<Time>,
+    mut clock: Local<f32>,
     query: Query<(&OrbitCircle, &MeshMaterial3d<StandardMaterial>)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
+    *clock += time.delta_secs();
     for (orbit_circle, material_handle) in query.iter() {
         if let Some(material) = materials.get_mut(&material_handle.0) {
-            let t = time.elapsed_secs() * orbit_circle.fade_speed + orbit_circle.phase_offset;
+            let t = *clock * orbit_circle.fade_speed + orbit_circle.phase_offset;
 
             // Very gentle fade between almost invisible and barely visible
             let alpha = 0.005 + 0.01 * (t.sin() * 0.5 + 0.5);
@@ -519,14 +1403,58 @@ fn setup_orbit_circles(
     }
 }
 
-fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
-    // Root UI container in bottom left
-    commands
+fn setup_ui(
+    mut commands: Commands,
+    _fs_state: Res<FileSystemState>,
+    color_mode: Res<StarColorMode>,
+    visible_types: Res<VisibleTypes>,
+    file_colors: Res<FileColors>,
+    tips_state: Res<TipsState>,
+) {
+    // Bottom left row: the camera mode panel with file stats above it, then the
+    // minimap column. Both columns stack upwards in spawn order.
+    let bottom_left = commands
         .spawn((
             Node {
                 position_type: PositionType::Absolute,
                 left: Val::Px(20.0),
                 bottom: Val::Px(20.0),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::End,
+                column_gap: Val::Px(20.0),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .id();
+    let camera_column = commands
+        .spawn((
+            Node {
+                width: Val::Px(320.0),
+                flex_direction: FlexDirection::ColumnReverse,
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            Pickable::IGNORE,
+            ChildOf(bottom_left),
+        ))
+        .id();
+    commands.spawn((
+        Node {
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Start,
+            row_gap: Val::Px(8.0),
+            ..default()
+        },
+        Pickable::IGNORE,
+        MinimapColumn,
+        ChildOf(bottom_left),
+    ));
+
+    // Camera mode panel
+    commands
+        .spawn((
+            Node {
                 width: Val::Px(320.0),
                 flex_direction: FlexDirection::Column,
                 row_gap: Val::Px(10.0),
@@ -537,6 +1465,8 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
             },
             BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.92)),
             BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.3)),
+            HudToggle { key: KeyCode::Digit1 },
+            ChildOf(camera_column),
         ))
         .with_children(|parent| {
             // Title
@@ -628,7 +1558,55 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
                             },
                             TextColor(Color::WHITE),
                         ));
+
+                    // Top-down button
+                    buttons
+                        .spawn((
+                            Button,
+                            Node {
+                                padding: UiRect::all(Val::Px(10.0)),
+                                border: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                            BorderColor::all(Color::srgb(0.5, 0.5, 0.5)),
+                            CameraModeButton {
+                                mode: CameraMode::TopDown,
+                            },
+                        ))
+                        .with_child((
+                            Text::new("Top"),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
                 });
+
+            // Reset view button
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        border_radius: BorderRadius::all(Val::Px(6.0)),
+                        align_self: AlignSelf::Start,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.05, 0.15, 0.8)),
+                    BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.4)),
+                    ResetViewButton,
+                ))
+                .with_child((
+                    Text::new("Reset View"),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
         });
 
     // Prompt interface at the top center
@@ -637,9 +1615,10 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
             Node {
                 position_type: PositionType::Absolute,
                 top: Val::Px(20.0),
-                left: Val::Percent(50.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
                 width: Val::Px(600.0),
-                margin: UiRect::left(Val::Px(-300.0)), // Center by offsetting half width
+                margin: UiRect::horizontal(Val::Auto), // Centered at any window width
                 flex_direction: FlexDirection::Row,
                 align_items: AlignItems::Center,
                 column_gap: Val::Px(12.0),
@@ -697,34 +1676,74 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
             ));
         });
 
-    // Agent actions display at the top left
+    // File search box just below the prompt bar
     commands
         .spawn((
             Node {
                 position_type: PositionType::Absolute,
-                top: Val::Px(20.0),
-                left: Val::Px(20.0),
+                top: Val::Px(110.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
                 width: Val::Px(320.0),
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::Start,
-                row_gap: Val::Px(8.0),
-                padding: UiRect::all(Val::Px(20.0)),
+                margin: UiRect::horizontal(Val::Auto),
+                padding: UiRect::all(Val::Px(6.0)),
                 border: UiRect::all(Val::Px(1.0)),
                 border_radius: BorderRadius::all(Val::Px(10.0)),
                 ..default()
             },
             BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.92)),
             BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.3)),
-            AgentActionsContainer,
+        ))
+        .with_child((
+            Button,
+            Node {
+                flex_grow: 1.0,
+                padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                border_radius: BorderRadius::all(Val::Px(6.0)),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.05, 0.15, 0.8)),
+            SearchInputField,
         ));
 
-    // File stats display above camera mode (bottom left, above the camera controls)
+    // Agent actions display at the top left, with the leaderboard stacked beneath it
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.0),
+            left: Val::Px(20.0),
+            width: Val::Px(320.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(12.0),
+            ..default()
+        })
+        .with_children(|column| {
+            let panel = || {
+                (
+                    Node {
+                        width: Val::Percent(100.0),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Start,
+                        row_gap: Val::Px(8.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        border_radius: BorderRadius::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.92)),
+                    BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.3)),
+                )
+            };
+            column.spawn((panel(), AgentActionsContainer, HudToggle { key: KeyCode::Digit2 }));
+            column.spawn((panel(), LeaderboardContainer, HudToggle { key: KeyCode::Digit6 }));
+        });
+
+    // File stats display above camera mode
     commands
         .spawn((
             Node {
-                position_type: PositionType::Absolute,
-                left: Val::Px(20.0),
-                bottom: Val::Px(180.0), // Position above camera mode
                 width: Val::Px(320.0),
                 flex_direction: FlexDirection::Column,
                 align_items: AlignItems::Start,
@@ -737,6 +1756,8 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
             BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.92)),
             BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.3)),
             FileStatsContainer,
+            HudToggle { key: KeyCode::Digit3 },
+            ChildOf(camera_column),
         ));
 
     // File hover panel at the top right (hidden by default)
@@ -762,15 +1783,50 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
             progress: 0.0,
             last_node: None,
         },
+        HudToggle { key: KeyCode::Digit5 },
     ));
 
-    // Color legend in bottom right
-    commands
+    // Agent history panel on the right, below the hover panel (hidden until an agent is clicked)
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(200.0),
+            right: Val::Px(20.0),
+            width: Val::Px(340.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            row_gap: Val::Px(6.0),
+            padding: UiRect::axes(Val::Px(20.0), Val::Px(14.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            border_radius: BorderRadius::all(Val::Px(10.0)),
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.92)),
+        BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.3)),
+        AgentHistoryPanel,
+    ));
+
+    // Bottom right column, stacked upwards: color legend, help button, toasts
+    let bottom_right = commands
         .spawn((
             Node {
                 position_type: PositionType::Absolute,
                 right: Val::Px(20.0),
                 bottom: Val::Px(20.0),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::End,
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .id();
+
+    // Color legend
+    commands
+        .spawn((
+            Node {
                 flex_direction: FlexDirection::Column,
                 align_items: AlignItems::Start,
                 row_gap: Val::Px(4.0),
@@ -782,72 +1838,15 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
             BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.92)),
             BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.3)),
             ColorLegendContainer,
+            HudToggle { key: KeyCode::Digit4 },
+            ChildOf(bottom_right),
         ))
-        .with_children(|parent| {
-            // Title
-            parent.spawn((
-                Text::new("File Types"),
-                TextFont {
-                    font_size: 22.0,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-            ));
-
-            // File type colors - matching galaxy.rs calculate_star_color
-            let legend_items = [
-                ("Directories", Color::srgb(1.0, 0.95, 0.7)),
-                ("Rust (.rs)", Color::srgb(1.0, 0.75, 0.6)),
-                ("Config", Color::srgb(1.0, 0.95, 0.6)),
-                ("Docs (.md)", Color::srgb(0.9, 0.8, 1.0)),
-                ("JavaScript", Color::srgb(1.0, 0.98, 0.7)),
-                ("Python (.py)", Color::srgb(0.7, 0.85, 1.0)),
-                ("Web (html/css)", Color::srgb(1.0, 0.7, 0.85)),
-                ("Compiled", Color::srgb(0.85, 0.75, 1.0)),
-                ("Go", Color::srgb(0.7, 0.9, 1.0)),
-            ];
-
-            for (label, color) in legend_items {
-                // Create a container for each legend item with mini planet + label
-                parent.spawn(Node {
-                    flex_direction: FlexDirection::Row,
-                    align_items: AlignItems::Center,
-                    column_gap: Val::Px(8.0),
-                    ..default()
-                }).with_children(|item| {
-                    // Mini planet (circular with border)
-                    item.spawn((
-                        Node {
-                            width: Val::Px(14.0),
-                            height: Val::Px(14.0),
-                            border: UiRect::all(Val::Px(1.0)),
-                            border_radius: BorderRadius::all(Val::Px(7.0)), // Make it circular
-                            ..default()
-                        },
-                        BackgroundColor(color),
-                        BorderColor::all(Color::srgba(1.0, 1.0, 1.0, 0.3)), // Subtle white border
-                    ));
-
-                    // Label text
-                    item.spawn((
-                        Text::new(label),
-                        TextFont {
-                            font_size: 14.0,
-                            ..default()
-                        },
-                        TextColor(Color::WHITE),
-                    ));
-                });
-            }
-        });
+        .with_children(|parent| spawn_color_legend_items(parent, *color_mode, &visible_types, &file_colors));
 
-    // Help button in bottom right corner (above color legend)
+    // Help button above the color legend
     commands.spawn((
         Button,
         Node {
-            position_type: PositionType::Absolute,
-            right: Val::Px(20.0),
-            bottom: Val::Px(460.0), // Position above color legend
             width: Val::Px(50.0),
             height: Val::Px(50.0),
             justify_content: JustifyContent::Center,
@@ -859,6 +1858,7 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
         BackgroundColor(Color::srgba(0.6, 0.45, 0.9, 0.9)),
         BorderColor::all(Color::srgba(0.8, 0.6, 1.0, 0.6)),
         HelpButton,
+        ChildOf(bottom_right),
     ))
     .with_child((
         Text::new("?"),
@@ -869,7 +1869,20 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
         TextColor(Color::WHITE),
     ));
 
-    // Tips overlay (initially visible, will be managed by update_tips_overlay)
+    // Toasts stack upwards from just above the help button
+    commands.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::End,
+            row_gap: Val::Px(8.0),
+            ..default()
+        },
+        Pickable::IGNORE,
+        ToastContainer,
+        ChildOf(bottom_right),
+    ));
+
+    // Tips overlay (shown or hidden by update_tips_overlay)
     commands.spawn((
         Node {
             position_type: PositionType::Absolute,
@@ -911,18 +1924,10 @@ fn setup_ui(mut commands: Commands, _fs_state: Res<FileSystemState>) {
                 TextColor(Color::srgb(0.9, 0.8, 1.0)),
             ));
 
-            // Tips
-            let tips = [
-                "Stars represent files & folders in your codebase",
-                "Spaceships are AI agents working on your code",
-                "Hover over a star to see recent activity",
-                "Use the prompt bar at the top to give agents tasks",
-                "Watch the Agent Activity panel to see what they're doing",
-            ];
-
-            for tip in tips {
+            // Tips, from assets/tips.txt
+            for tip in &tips_state.tips {
                 panel.spawn((
-                    Text::new(tip),
+                    Text::new(tip.clone()),
                     TextFont {
                         font_size: 18.0,
                         ..default()
@@ -977,55 +1982,237 @@ fn setup_vignette(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
     ));
 }
 
-fn setup_galaxy(
+/// Initial stars not spawned yet; spawn_queued_stars works through a batch per frame
+#[derive(Resource, Default)]
+struct StarSpawnQueue {
+    remaining: VecDeque<usize>,
+    total: usize,
+}
+
+const STARS_PER_FRAME: usize = 200;
+
+#[derive(Component)]
+struct StarLoadingIndicator;
+
+fn setup_galaxy(mut commands: Commands, fs_state: Res<FileSystemState>) {
+    // Queue the already-built model's nodes instead of spawning them all before the first frame
+    let remaining: VecDeque<usize> = fs_state.model.live_indices().collect();
+    let total = remaining.len();
+    commands.insert_resource(StarSpawnQueue { remaining, total });
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(160.0),
+            left: Val::Px(0.0),
+            right: Val::Px(0.0),
+            width: Val::Px(320.0),
+            margin: UiRect::horizontal(Val::Auto),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        Text::new(format!("Loading stars… 0 / {}", total)),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgba(0.8, 0.7, 1.0, 0.9)),
+        StarLoadingIndicator,
+    ));
+}
+
+fn spawn_queued_stars(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut planet_materials: ResMut<Assets<PlanetMaterial>>,
-    asset_server: Res<AssetServer>,
+    mut assets: StarAssets,
     mut fs_state: ResMut<FileSystemState>,
+    mut queue: ResMut<StarSpawnQueue>,
+    mut indicator: Query<(Entity, &mut Text), With<StarLoadingIndicator>>,
 ) {
-    // Spawn initial galaxy stars from the already-built file system model
-    for node_idx in 0..fs_state.model.total_nodes() {
-        let entity = spawn_star(
-            &mut commands,
-            &mut meshes,
-            &mut materials,
-            &mut planet_materials,
-            &asset_server,
-            &fs_state.model,
-            node_idx,
-        );
+    if queue.remaining.is_empty() {
+        return;
+    }
+
+    let batch = queue.remaining.len().min(STARS_PER_FRAME);
+    for node_idx in queue.remaining.drain(..batch) {
+        // The watcher may have deleted the node, or already spawned it, since startup
+        let still_tracked = fs_state.model.get_node(node_idx).is_some();
+        if !still_tracked || fs_state.entity_map.contains_key(&node_idx) {
+            continue;
+        }
+
+        let entity = spawn_star(&mut commands, &mut assets, &fs_state.model, node_idx);
         fs_state.entity_map.insert(node_idx, entity);
     }
-}
 
-fn is_gitignore_file(path: &PathBuf) -> bool {
-    path.file_name().map(|n| n == ".gitignore").unwrap_or(false)
+    let Ok((entity, mut text)) = indicator.single_mut() else {
+        return;
+    };
+    if queue.remaining.is_empty() {
+        commands.entity(entity).despawn();
+    } else {
+        text.0 = format!(
+            "Loading stars… {} / {}",
+            queue.total - queue.remaining.len(),
+            queue.total
+        );
+    }
 }
 
-fn despawn_star_with_label(
-    commands: &mut Commands,
-    star_entity: Entity,
-    label_query: &Query<(Entity, &FileLabel)>,
+/// Swap clicked "…N more" stars for the files and folders they stood for
+fn expand_aggregates(
+    mut commands: Commands,
+    mut assets: StarAssets,
+    mut fs_state: ResMut<FileSystemState>,
+    mut expansions: ResMut<PendingExpansions>,
+    label_query: Query<(Entity, &FileLabel)>,
 ) {
-    commands.entity(star_entity).despawn();
+    for aggregate_idx in std::mem::take(&mut expansions.nodes) {
+        let new_nodes = fs_state.model.expand_aggregate(aggregate_idx);
+        if let Some(entity) = fs_state.entity_map.remove(&aggregate_idx) {
+            despawn_star_with_label(&mut commands, entity, &label_query);
+        }
+
+        for node_idx in new_nodes {
+            let entity = spawn_star(&mut commands, &mut assets, &fs_state.model, node_idx);
+            fs_state.entity_map.insert(node_idx, entity);
+        }
+    }
+}
+
+fn is_gitignore_file(path: &PathBuf) -> bool {
+    path.file_name().map(|n| n == ".gitignore").unwrap_or(false)
+}
+
+/// Start fading out a star and its labels; animate_star_despawns removes them.
+/// Dropping FileStar and FileLabel keeps them out of every other star and label system.
+fn despawn_star_with_label(
+    commands: &mut Commands,
+    star_entity: Entity,
+    label_query: &Query<(Entity, &FileLabel)>,
+) {
+    commands
+        .entity(star_entity)
+        .remove::<(FileStar, StarSpawnAnim, HoverGlow, agent::FileHighlight)>()
+        .insert(StarDespawning::default());
     for (label_entity, file_label) in label_query.iter() {
         if file_label.star_entity == star_entity {
-            commands.entity(label_entity).despawn();
-            break;
+            commands
+                .entity(label_entity)
+                .remove::<FileLabel>()
+                .insert(StarDespawning::default());
+        }
+    }
+}
+
+/// Drop a path and everything under it from the model, fading out their stars
+fn remove_path_stars(
+    fs_state: &mut FileSystemState,
+    commands: &mut Commands,
+    path: &Path,
+    label_query: &Query<(Entity, &FileLabel)>,
+) {
+    for node_idx in fs_state.model.remove_node(path) {
+        if let Some(entity) = fs_state.entity_map.remove(&node_idx) {
+            despawn_star_with_label(commands, entity, label_query);
+        }
+    }
+}
+
+/// Move stars the model placed again after their directory gained or lost an entry
+fn move_replaced_stars(mut fs_state: ResMut<FileSystemState>, mut stars: Query<&mut Transform, With<FileStar>>) {
+    for node_idx in fs_state.model.take_moved() {
+        if let Some(&entity) = fs_state.entity_map.get(&node_idx)
+            && let Ok(mut transform) = stars.get_mut(entity)
+        {
+            transform.translation = fs_state.model.position(node_idx);
+        }
+    }
+}
+
+const STAR_SPAWN_DURATION: f32 = 0.4;
+const STAR_DESPAWN_DURATION: f32 = 0.4;
+
+/// Grow new stars from zero and ramp their glow up to its usual strength
+fn animate_star_spawns(
+    mut commands: Commands,
+    time: Res<Time>,
+    fs_state: Res<FileSystemState>,
+    emphasis: StarEmphasis,
+    mut stars: Query<(Entity, &FileStar, &mut StarSpawnAnim, &mut Transform, &MeshMaterial3d<PlanetMaterial>)>,
+    mut planet_materials: ResMut<Assets<PlanetMaterial>>,
+) {
+    for (entity, star, mut anim, mut transform, mat_handle) in stars.iter_mut() {
+        anim.progress = (anim.progress + time.delta_secs() / STAR_SPAWN_DURATION).min(1.0);
+        let t = ease_out_cubic(anim.progress);
+        transform.scale = Vec3::splat(t);
+
+        if let Some(node) = fs_state.model.get_node(star.node_index)
+            && let Some(material) = planet_materials.get_mut(&mat_handle.0)
+        {
+            let emissive_strength = galaxy::calculate_star_emissive_strength(node)
+                * emphasis.emissive_scale(node);
+            material.base.emissive = LinearRgba::from(material.base.base_color) * emissive_strength * t;
+        }
+
+        if anim.progress >= 1.0 {
+            commands.entity(entity).remove::<StarSpawnAnim>();
+        }
+    }
+}
+
+type DespawningStarFilter = With<MeshMaterial3d<PlanetMaterial>>;
+type DespawningLabelFilter = Without<MeshMaterial3d<PlanetMaterial>>;
+
+/// Shrink deleted stars and fade their labels, then despawn both
+fn animate_star_despawns(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut stars: Query<(Entity, &mut StarDespawning, &mut Transform), DespawningStarFilter>,
+    mut labels: Query<(Entity, &mut StarDespawning, &mut MeshMaterial3d<StandardMaterial>), DespawningLabelFilter>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    label_material: Res<LabelMaterial>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut despawning, mut transform) in stars.iter_mut() {
+        despawning.timer += dt;
+        if despawning.timer >= STAR_DESPAWN_DURATION {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.scale = Vec3::splat(1.0 - ease_out_cubic(despawning.timer / STAR_DESPAWN_DURATION));
+    }
+
+    for (entity, mut despawning, mut mat_handle) in labels.iter_mut() {
+        despawning.timer += dt;
+        if despawning.timer >= STAR_DESPAWN_DURATION {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // Every label shares one material, so a fading one gets its own copy first
+        if mat_handle.0 == label_material.0
+            && let Some(material) = materials.get(&label_material.0).cloned()
+        {
+            mat_handle.0 = materials.add(material);
+        }
+        if let Some(material) = materials.get_mut(&*mat_handle) {
+            material.alpha_mode = AlphaMode::Blend;
+            material.base_color.set_alpha(1.0 - despawning.timer / STAR_DESPAWN_DURATION);
         }
     }
 }
 
+/// Teal, so on-disk edits read differently from an agent's own-colored arrival glow
+const MODIFIED_HIGHLIGHT_COLOR: LinearRgba = LinearRgba::rgb(0.1, 0.9, 0.8);
+const MODIFIED_HIGHLIGHT_INTENSITY: f32 = 4.0;
+
 fn update_file_system(
     mut fs_state: ResMut<FileSystemState>,
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut planet_materials: ResMut<Assets<PlanetMaterial>>,
-    asset_server: Res<AssetServer>,
+    mut assets: StarAssets,
     label_query: Query<(Entity, &FileLabel)>,
+    mut label_text_query: Query<(&mut TextMesh, Has<StarGlyph>), With<FileLabel>>,
+    mut pending: ResMut<PendingToolUses>,
+    history: Res<FileEventHistory>,
 ) {
     let mut gitignore_changed = false;
 
@@ -1048,16 +2235,10 @@ fn update_file_system(
                     if is_dir { "dir" } else { "file" }
                 );
 
-                if let Some(node_idx) = fs_state.model.add_node(path, is_dir) {
-                    let entity = spawn_star(
-                        &mut commands,
-                        &mut meshes,
-                        &mut materials,
-                        &mut planet_materials,
-                        &asset_server,
-                        &fs_state.model,
-                        node_idx,
-                    );
+                if let Some(node_idx) = fs_state.model.add_node(path.clone(), is_dir) {
+                    pending.file_created(&path);
+                    let entity = spawn_star(&mut commands, &mut assets, &fs_state.model, node_idx);
+                    commands.entity(entity).insert(StarSpawnAnim::default());
                     fs_state.entity_map.insert(node_idx, entity);
                 }
             }
@@ -1067,12 +2248,48 @@ fn update_file_system(
                 }
 
                 println!("Deleted: {}", path.display());
+                fs_state.model.invalidate_line_count(&path);
 
                 // Always process deletions — the file may have been in the model
-                if let Some(node_idx) = fs_state.model.remove_node(&path) {
-                    if let Some(entity) = fs_state.entity_map.remove(&node_idx) {
-                        despawn_star_with_label(&mut commands, entity, &label_query);
+                remove_path_stars(&mut fs_state, &mut commands, &path, &label_query);
+            }
+            FileSystemEvent::Renamed(from, to) => {
+                if is_gitignore_file(&from) || is_gitignore_file(&to) {
+                    gitignore_changed = true;
+                }
+
+                println!("Renamed: {} -> {}", from.display(), to.display());
+
+                if fs_state.gitignore_checker.is_ignored(&to) {
+                    // Renamed into an ignored name: treat as a deletion
+                    remove_path_stars(&mut fs_state, &mut commands, &from, &label_query);
+                    continue;
+                }
+
+                if let Some(node_idx) = fs_state.model.rename_node(&from, to.clone()) {
+                    pending.file_created(&to);
+                    let node = &fs_state.model.nodes[node_idx];
+                    if let Some(&star_entity) = fs_state.entity_map.get(&node_idx) {
+                        // The name label and the glyph (the extension may have changed)
+                        for (label_entity, file_label) in label_query.iter() {
+                            if file_label.star_entity == star_entity
+                                && let Ok((mut text_mesh, is_glyph)) = label_text_query.get_mut(label_entity)
+                            {
+                                text_mesh.text = if is_glyph { galaxy::star_glyph(node) } else { node.name.clone() };
+                            }
+                        }
                     }
+                    continue;
+                }
+
+                // Moved across directories (or unknown source): delete + create
+                remove_path_stars(&mut fs_state, &mut commands, &from, &label_query);
+                let is_dir = to.is_dir();
+                if let Some(node_idx) = fs_state.model.add_node(to.clone(), is_dir) {
+                    pending.file_created(&to);
+                    let entity = spawn_star(&mut commands, &mut assets, &fs_state.model, node_idx);
+                    commands.entity(entity).insert(StarSpawnAnim::default());
+                    fs_state.entity_map.insert(node_idx, entity);
                 }
             }
             FileSystemEvent::Modified(path) => {
@@ -1081,6 +2298,30 @@ fn update_file_system(
                 }
 
                 println!("Modified: {}", path.display());
+                fs_state.model.invalidate_line_count(&path);
+                fs_state.model.mark_modified(&path);
+
+                // Pulse the star so edits made outside any agent still show up; an
+                // agent's own writes already light it up on arrival
+                if history.recently_used(&path) {
+                    continue;
+                }
+                let Some((node_idx, _)) = fs_state.model.get_node_by_path(&path) else {
+                    continue;
+                };
+                if let Some(&star_entity) = fs_state.entity_map.get(&node_idx) {
+                    commands
+                        .entity(star_entity)
+                        .entry::<agent::FileHighlight>()
+                        .and_modify(|mut highlight| {
+                            highlight.intensity = MODIFIED_HIGHLIGHT_INTENSITY;
+                            highlight.tint = Some(MODIFIED_HIGHLIGHT_COLOR);
+                        })
+                        .or_insert(agent::FileHighlight::new(
+                            MODIFIED_HIGHLIGHT_INTENSITY,
+                            Some(MODIFIED_HIGHLIGHT_COLOR),
+                        ));
+                }
             }
         }
     }
@@ -1088,6 +2329,7 @@ fn update_file_system(
     // When .gitignore changes, reconcile: remove now-ignored files, add now-visible files
     if gitignore_changed {
         println!("Gitignore changed, reconciling visualization...");
+        fs_state.gitignore_checker.rebuild();
         let valid_paths = get_valid_paths(&fs_state.root_path);
 
         // Remove stars for paths that are now gitignored
@@ -1101,11 +2343,7 @@ fn update_file_system(
 
         for path in &paths_to_remove {
             println!("Removing now-ignored: {}", path.display());
-            if let Some(node_idx) = fs_state.model.remove_node(path) {
-                if let Some(entity) = fs_state.entity_map.remove(&node_idx) {
-                    despawn_star_with_label(&mut commands, entity, &label_query);
-                }
-            }
+            remove_path_stars(&mut fs_state, &mut commands, path, &label_query);
         }
 
         // Add stars for paths that are now visible (were previously ignored)
@@ -1113,15 +2351,8 @@ fn update_file_system(
             if !fs_state.model.path_to_index.contains_key(path) {
                 let is_dir = path.is_dir();
                 if let Some(node_idx) = fs_state.model.add_node(path.clone(), is_dir) {
-                    let entity = spawn_star(
-                        &mut commands,
-                        &mut meshes,
-                        &mut materials,
-                        &mut planet_materials,
-                        &asset_server,
-                        &fs_state.model,
-                        node_idx,
-                    );
+                    let entity = spawn_star(&mut commands, &mut assets, &fs_state.model, node_idx);
+                    commands.entity(entity).insert(StarSpawnAnim::default());
                     fs_state.entity_map.insert(node_idx, entity);
                 }
             }
@@ -1129,6 +2360,379 @@ fn update_file_system(
     }
 }
 
+fn handle_color_mode_toggle(
+    mut color_mode: ResMut<StarColorMode>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    // C cycles between coloring by file type, by directory and by recency
+    if keyboard.just_pressed(KeyCode::KeyC) {
+        *color_mode = match *color_mode {
+            StarColorMode::FileType => StarColorMode::Directory,
+            StarColorMode::Directory => StarColorMode::Recency,
+            StarColorMode::Recency => StarColorMode::FileType,
+        };
+        println!("Star color mode: {:?}", *color_mode);
+    }
+}
+
+fn handle_size_mode_toggle(
+    mut size_mode: ResMut<StarSizeMode>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    // B switches star sizes between line count and byte size
+    if keyboard.just_pressed(KeyCode::KeyB) {
+        *size_mode = match *size_mode {
+            StarSizeMode::Lines => StarSizeMode::Bytes,
+            StarSizeMode::Bytes => StarSizeMode::Lines,
+        };
+        println!("Star size mode: {:?}", *size_mode);
+    }
+}
+
+/// Resize existing stars (and lift their labels) when the size mode changes
+fn apply_star_size_mode(
+    size_mode: Res<StarSizeMode>,
+    settings: Res<Settings>,
+    fs_state: Res<FileSystemState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut stars: Query<(Entity, &FileStar, &mut Mesh3d)>,
+    mut labels: Query<(&mut FileLabel, Has<StarGlyph>)>,
+) {
+    if !size_mode.is_changed() || size_mode.is_added() {
+        return;
+    }
+
+    let mut sizes = HashMap::new();
+    for (entity, star, mut mesh) in stars.iter_mut() {
+        let size = galaxy::calculate_star_size(&fs_state.model, star.node_index, *size_mode, settings.stars.scale);
+        mesh.0 = meshes.add(Sphere::new(size));
+        sizes.insert(entity, size);
+    }
+    // Labels float just above their star and glyphs hang below it, so both follow the new radius
+    for (mut label, is_glyph) in labels.iter_mut() {
+        if let Some(&size) = sizes.get(&label.star_entity) {
+            label.offset = if is_glyph { galaxy::glyph_offset(size) } else { Vec3::new(0.0, size + 1.5, 0.0) };
+        }
+    }
+}
+
+fn handle_layout_mode_toggle(
+    mut layout_mode: ResMut<LayoutMode>,
+    mut fs_state: ResMut<FileSystemState>,
+    mut star_query: Query<(&FileStar, &mut Transform)>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    // L switches between the spiral and the force-directed layout
+    if keyboard.just_pressed(KeyCode::KeyL) {
+        *layout_mode = match *layout_mode {
+            LayoutMode::Spiral => LayoutMode::ForceDirected,
+            LayoutMode::ForceDirected => LayoutMode::Spiral,
+        };
+        println!("Layout mode: {:?}", *layout_mode);
+
+        let layout = galaxy::compute_layout(&fs_state.model, *layout_mode);
+        fs_state.model.set_layout(layout);
+        for (star, mut transform) in star_query.iter_mut() {
+            transform.translation = fs_state.model.position(star.node_index);
+        }
+    }
+}
+
+fn apply_star_color_mode(
+    color_mode: Res<StarColorMode>,
+    file_colors: Res<FileColors>,
+    fs_state: Res<FileSystemState>,
+    stars: Query<(&FileStar, &MeshMaterial3d<PlanetMaterial>)>,
+    mut planet_materials: ResMut<Assets<PlanetMaterial>>,
+    emphasis: StarEmphasis,
+    time: Res<Time>,
+    mut since_refresh: Local<f32>,
+) {
+    // Recolor existing stars when the mode changes, and periodically while
+    // coloring by recency since those colors cool down over time
+    let mode_changed = color_mode.is_changed() && !color_mode.is_added();
+    *since_refresh += time.delta_secs();
+    let recency_due =
+        *color_mode == StarColorMode::Recency && *since_refresh >= RECENCY_REFRESH_SECS;
+    if !mode_changed && !recency_due {
+        return;
+    }
+    *since_refresh = 0.0;
+
+    for (star, mat_handle) in stars.iter() {
+        let Some(node) = fs_state.model.get_node(star.node_index) else {
+            continue;
+        };
+        let color = galaxy::calculate_star_color_for_mode(&fs_state.model, star.node_index, *color_mode, &file_colors);
+        let emissive_strength = galaxy::calculate_star_emissive_strength(node)
+            * emphasis.emissive_scale(node);
+
+        if let Some(material) = planet_materials.get_mut(&mat_handle.0) {
+            material.base.base_color = color;
+            material.base.emissive = LinearRgba::from(color) * emissive_strength;
+            material.extension.base_color = LinearRgba::from(color);
+        }
+    }
+}
+
+type LegendItem = (&'static str, Color, Option<FileCategory>);
+
+/// Title and swatches for the color legend, matching the active color mode.
+/// File type rows double as toggles for the type filter.
+fn spawn_color_legend_items(
+    parent: &mut ChildSpawnerCommands,
+    color_mode: StarColorMode,
+    visible_types: &VisibleTypes,
+    file_colors: &FileColors,
+) {
+    // (label, swatch color, category the row toggles)
+    let (title, legend_items): (&str, Vec<LegendItem>) = match color_mode {
+        StarColorMode::FileType | StarColorMode::Directory => (
+            "File Types",
+            FileCategory::ALL
+                .iter()
+                .map(|&category| (category.label(), file_colors.category_color(category), Some(category)))
+                .collect(),
+        ),
+        StarColorMode::Recency => {
+            // Sample the same gradient galaxy.rs uses, against a neutral file color
+            let neutral = file_colors.other;
+            let hot = galaxy::RECENCY_HOT_COLOR;
+            (
+                "Last Modified",
+                vec![
+                    ("Under 1 min", hot, None),
+                    ("~15 min", neutral.mix(&hot, 0.75), None),
+                    ("~30 min", neutral.mix(&hot, 0.5), None),
+                    ("~45 min", neutral.mix(&hot, 0.25), None),
+                    ("Over 1 hour (type color)", neutral, None),
+                ],
+            )
+        }
+    };
+
+    parent.spawn((
+        Text::new(title),
+        TextFont {
+            font_size: 22.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+    ));
+
+    for (label, color, category) in legend_items {
+        // Hidden categories are drawn faded
+        let enabled = category.is_none_or(|c| visible_types.is_visible(c));
+        let alpha = if enabled { 1.0 } else { 0.3 };
+
+        // Create a container for each legend item with mini planet + label
+        let mut row = parent.spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            ..default()
+        });
+        if let Some(category) = category {
+            row.insert((Button, LegendToggle { category }));
+        }
+        row.with_children(|item| {
+            // Mini planet (circular with border)
+            item.spawn((
+                Node {
+                    width: Val::Px(14.0),
+                    height: Val::Px(14.0),
+                    border: UiRect::all(Val::Px(1.0)),
+                    border_radius: BorderRadius::all(Val::Px(7.0)), // Make it circular
+                    ..default()
+                },
+                BackgroundColor(color.with_alpha(alpha)),
+                BorderColor::all(Color::srgba(1.0, 1.0, 1.0, 0.3 * alpha)), // Subtle white border
+            ));
+
+            // Label text
+            item.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE.with_alpha(alpha)),
+            ));
+        });
+    }
+}
+
+fn update_color_legend(
+    mut commands: Commands,
+    color_mode: Res<StarColorMode>,
+    visible_types: Res<VisibleTypes>,
+    file_colors: Res<FileColors>,
+    container_query: Query<Entity, With<ColorLegendContainer>>,
+    children_query: Query<&Children>,
+) {
+    let mode_changed = color_mode.is_changed() && !color_mode.is_added();
+    let filter_changed = visible_types.is_changed() && !visible_types.is_added();
+    let colors_changed = file_colors.is_changed() && !file_colors.is_added();
+    if !mode_changed && !filter_changed && !colors_changed {
+        return;
+    }
+
+    let Ok(container) = container_query.single() else {
+        return;
+    };
+
+    if let Ok(children) = children_query.get(container) {
+        for child in children.iter() {
+            commands.entity(child).despawn();
+        }
+    }
+
+    commands
+        .entity(container)
+        .with_children(|parent| spawn_color_legend_items(parent, *color_mode, &visible_types, &file_colors));
+}
+
+fn handle_legend_toggles(
+    mut visible_types: ResMut<VisibleTypes>,
+    interaction_query: Query<(&Interaction, &LegendToggle), Changed<Interaction>>,
+) {
+    for (interaction, toggle) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            visible_types.toggle(toggle.category);
+        }
+    }
+}
+
+/// A single click on a directory star folds or unfolds its subtree. Applied once
+/// the double-click window has passed so double-click (fly to) doesn't also toggle.
+fn toggle_clicked_directory(
+    time: Res<Time>,
+    mut last_click: ResMut<LastStarClick>,
+    mut fs_state: ResMut<FileSystemState>,
+    mut toggled: MessageWriter<DirectoryToggled>,
+) {
+    let Some(node_idx) = last_click.node_index else {
+        return;
+    };
+    if time.elapsed_secs() - last_click.time <= DOUBLE_CLICK_WINDOW {
+        return;
+    }
+    if !fs_state.model.get_node(node_idx).is_some_and(|node| node.is_dir()) {
+        return;
+    }
+    last_click.node_index = None;
+
+    let collapsed = fs_state.model.toggle_collapsed(node_idx);
+    println!(
+        "{} {}",
+        if collapsed { "Collapsed" } else { "Expanded" },
+        fs_state.model.nodes[node_idx].path.display()
+    );
+    toggled.write(DirectoryToggled);
+}
+
+/// Show or hide stars (and their labels) according to the legend filter and collapsed directories
+fn apply_type_visibility(
+    visible_types: Res<VisibleTypes>,
+    fs_state: Res<FileSystemState>,
+    mut toggled: MessageReader<DirectoryToggled>,
+    mut stars: Query<(Ref<FileStar>, &mut Visibility)>,
+) {
+    let stars_added = stars.iter().any(|(star, _)| star.is_added());
+    let dirs_toggled = toggled.read().count() > 0;
+    if !visible_types.is_changed() && !stars_added && !dirs_toggled {
+        return;
+    }
+
+    // Labels follow their star in apply_label_lod
+    for (star, mut visibility) in stars.iter_mut() {
+        let Some(node) = fs_state.model.get_node(star.node_index) else {
+            continue;
+        };
+        let folded_away = fs_state.model.visible_ancestor(star.node_index) != star.node_index;
+        let visible = visible_types.is_visible(FileCategory::of(node)) && !folded_away;
+        visibility.set_if_neq(if visible { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
+
+/// Show a label only while its star is shown and close enough to the camera to read;
+/// directories get a longer range so zoomed-out views keep just the structure labelled.
+/// Glyphs follow the same rule while they're turned on.
+///
+/// Name labels that would land on top of each other on screen are then thinned
+/// out: the one nearest the camera stays and the rest are hidden until the view
+/// pulls them apart.
+fn apply_label_lod(
+    lod: Res<LabelLod>,
+    fs_state: Res<FileSystemState>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    stars: Query<(&FileStar, &Transform, &Visibility)>,
+    mut labels: Query<(Entity, &FileLabel, Has<StarGlyph>, &mut Visibility), Without<FileStar>>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+    let camera_pos = camera_transform.translation();
+
+    // In-range name labels: entity, screen position, squared camera distance
+    let mut candidates: Vec<(Entity, Vec2, f32)> = Vec::new();
+    for (entity, label, is_glyph, mut visibility) in labels.iter_mut() {
+        let in_range = stars.get(label.star_entity).ok().filter(|(star, transform, star_vis)| {
+            let is_dir = fs_state
+                .model
+                .get_node(star.node_index)
+                .is_some_and(|node| node.is_dir());
+            let range = if is_dir { lod.directory_distance } else { lod.file_distance };
+            **star_vis != Visibility::Hidden
+                && transform.translation.distance_squared(camera_pos) <= range * range
+        });
+        let Some((_, transform, _)) = in_range else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        if is_glyph {
+            visibility.set_if_neq(Visibility::Inherited);
+            continue;
+        }
+        let world_pos = transform.translation + label.offset;
+        match camera.world_to_viewport(camera_transform, world_pos) {
+            Ok(screen_pos) => candidates.push((entity, screen_pos, world_pos.distance_squared(camera_pos))),
+            // Behind the camera: nothing to overlap with
+            Err(_) => {
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+        }
+    }
+
+    // Nearest first; a label is kept unless a kept one already covers its spot.
+    // Kept labels are bucketed by declutter box so each check looks at 9 cells.
+    candidates.sort_by(|a, b| a.2.total_cmp(&b.2));
+    let cell_of = |pos: Vec2| {
+        (
+            (pos.x / LABEL_DECLUTTER_WIDTH).floor() as i32,
+            (pos.y / LABEL_DECLUTTER_HEIGHT).floor() as i32,
+        )
+    };
+    let mut kept: HashMap<(i32, i32), Vec<Vec2>> = HashMap::new();
+    for (entity, screen_pos, _) in candidates {
+        let (cx, cy) = cell_of(screen_pos);
+        let covered = (cx - 1..=cx + 1)
+            .flat_map(|x| (cy - 1..=cy + 1).map(move |y| (x, y)))
+            .filter_map(|cell| kept.get(&cell))
+            .flatten()
+            .any(|other| {
+                (other.x - screen_pos.x).abs() < LABEL_DECLUTTER_WIDTH
+                    && (other.y - screen_pos.y).abs() < LABEL_DECLUTTER_HEIGHT
+            });
+        if !covered {
+            kept.entry((cx, cy)).or_default().push(screen_pos);
+        }
+        if let Ok((_, _, _, mut visibility)) = labels.get_mut(entity) {
+            visibility.set_if_neq(if covered { Visibility::Hidden } else { Visibility::Inherited });
+        }
+    }
+}
+
 fn handle_camera_mode_buttons(
     mut controller: ResMut<CameraController>,
     interaction_query: Query<(&Interaction, &CameraModeButton), Changed<Interaction>>,
@@ -1141,42 +2745,622 @@ fn handle_camera_mode_buttons(
         }
     }
 
-    // Update all button colors based on current mode
-    for (button, interaction, mut bg_color) in all_buttons.iter_mut() {
-        match *interaction {
-            Interaction::Hovered => {
-                if controller.mode != button.mode {
-                    *bg_color = BackgroundColor(Color::srgb(0.3, 0.3, 0.3));
-                } else {
-                    *bg_color = BackgroundColor(Color::srgb(0.6, 0.45, 0.7));
-                }
-            }
-            Interaction::Pressed | Interaction::None => {
-                if controller.mode == button.mode {
-                    *bg_color = BackgroundColor(Color::srgb(0.6, 0.45, 0.7));
-                } else {
-                    *bg_color = BackgroundColor(Color::srgb(0.2, 0.2, 0.2));
-                }
-            }
-        }
+    // Update all button colors based on current mode
+    for (button, interaction, mut bg_color) in all_buttons.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                if controller.mode != button.mode {
+                    *bg_color = BackgroundColor(Color::srgb(0.3, 0.3, 0.3));
+                } else {
+                    *bg_color = BackgroundColor(Color::srgb(0.6, 0.45, 0.7));
+                }
+            }
+            Interaction::Pressed | Interaction::None => {
+                if controller.mode == button.mode {
+                    *bg_color = BackgroundColor(Color::srgb(0.6, 0.45, 0.7));
+                } else {
+                    *bg_color = BackgroundColor(Color::srgb(0.2, 0.2, 0.2));
+                }
+            }
+        }
+    }
+}
+
+/// Put the camera back where the last run on this project left it, kept within
+/// the configured limits. A --fly-in lands on the restored view instead of the default.
+fn restore_camera_state(
+    mut controller: ResMut<CameraController>,
+    fs_state: Res<FileSystemState>,
+    settings: Res<Settings>,
+) {
+    let Some(saved) = camera_store::load_camera(&fs_state.root_path) else {
+        return;
+    };
+    if !saved.is_finite() {
+        eprintln!("[camera] Ignoring saved camera with non-finite values");
+        return;
+    }
+
+    let camera = &settings.camera;
+    let pose = CameraPose {
+        distance: camera.clamp_distance(saved.orbit_distance),
+        angle: saved.orbit_angle.rem_euclid(std::f32::consts::TAU),
+        height: camera.clamp_height(saved.orbit_height),
+    };
+    let controller = &mut *controller;
+    // There's no agent to follow yet at startup
+    controller.mode = match saved.mode {
+        CameraMode::Follow => CameraMode::Auto,
+        mode => mode,
+    };
+    controller.top_down_height = camera.clamp_top_down_height(saved.top_down_height);
+    controller.pan_target = Vec3::from_array(saved.pan_target);
+    match &mut controller.transition {
+        Some(transition) => transition.to = pose,
+        None => controller.set_pose(pose),
+    }
+}
+
+/// Remember the camera for the next run on this project
+fn save_camera_state(
+    controller: Res<CameraController>,
+    fs_state: Res<FileSystemState>,
+    mut exit_events: MessageReader<AppExit>,
+) {
+    if exit_events.read().count() == 0 {
+        return;
+    }
+    // Mid-transition, save where the camera was headed
+    let pose = controller.transition.as_ref().map_or(controller.pose(), |transition| transition.to);
+    camera_store::save_camera(
+        &fs_state.root_path,
+        camera_store::SavedCamera {
+            mode: controller.mode,
+            orbit_distance: pose.distance,
+            orbit_angle: pose.angle,
+            orbit_height: pose.height,
+            top_down_height: controller.top_down_height,
+            pan_target: controller.pan_target.to_array(),
+        },
+    );
+}
+
+const RESET_VIEW_DURATION: f32 = 1.0;
+
+fn handle_reset_view_button(
+    mut controller: ResMut<CameraController>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<ResetViewButton>)>,
+    settings: Res<Settings>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        // Ease back to the nearest full turn so we don't spin through accumulated orbits
+        let mut target = default_camera_pose(&settings.camera);
+        target.angle += (controller.orbit_angle / std::f32::consts::TAU).round() * std::f32::consts::TAU;
+
+        controller.mode = CameraMode::Auto;
+        controller.camera_frozen = false;
+        controller.pan_target = Vec3::ZERO;
+        controller.start_transition(target, RESET_VIEW_DURATION);
+    }
+}
+
+fn handle_camera_freeze_toggle(
+    mut controller: ResMut<CameraController>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    // F toggles the freeze
+    if keyboard.just_pressed(KeyCode::KeyF) {
+        controller.camera_frozen = !controller.camera_frozen;
+        println!(
+            "Camera {}",
+            if controller.camera_frozen { "frozen" } else { "unfrozen" }
+        );
+    }
+}
+
+fn handle_projection_toggle(
+    mut orthographic: ResMut<OrthographicView>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyO) {
+        orthographic.0 = !orthographic.0;
+        println!(
+            "Projection: {}",
+            if orthographic.0 { "orthographic" } else { "perspective" }
+        );
+    }
+}
+
+/// Swap the camera's projection to match OrthographicView. The orthographic view
+/// is sized to what the perspective camera sees at its focus point, so the galaxy
+/// stays framed across the swap and while zooming.
+fn sync_camera_projection(
+    orthographic: Res<OrthographicView>,
+    controller: Res<CameraController>,
+    mut camera_query: Query<&mut Projection, With<Camera3d>>,
+) {
+    let Ok(mut projection) = camera_query.single_mut() else {
+        return;
+    };
+
+    if !orthographic.0 {
+        if matches!(*projection, Projection::Orthographic(_)) {
+            *projection = Projection::Perspective(PerspectiveProjection::default());
+        }
+        return;
+    }
+
+    let focus_distance = match controller.mode {
+        CameraMode::TopDown => controller.top_down_height,
+        _ => Vec2::new(controller.orbit_distance, controller.orbit_height).length(),
+    };
+    let fov = PerspectiveProjection::default().fov;
+    let viewport_height = 2.0 * focus_distance * (fov * 0.5).tan();
+
+    // Only write when the size changed, so the frustum isn't rebuilt every frame
+    let scaling_mode = ScalingMode::FixedVertical { viewport_height };
+    if let Projection::Orthographic(ortho) = &*projection
+        && matches!(ortho.scaling_mode, ScalingMode::FixedVertical { viewport_height: h } if h == viewport_height)
+    {
+        return;
+    }
+    *projection = Projection::Orthographic(OrthographicProjection {
+        scaling_mode,
+        // Orthographic depth starts at the camera, so reach well behind the galaxy
+        far: 2000.0,
+        ..OrthographicProjection::default_3d()
+    });
+}
+
+fn setup_debug_overlay(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(76.0), // Just above the timeline
+            left: Val::Px(0.0),
+            right: Val::Px(0.0),
+            width: Val::Px(600.0),
+            margin: UiRect::horizontal(Val::Auto),
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+            border_radius: BorderRadius::all(Val::Px(6.0)),
+            display: Display::None, // Hidden until F3
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.6, 1.0, 0.6)),
+        DebugOverlay,
+    ));
+}
+
+fn setup_pause_indicator(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(200.0),
+            left: Val::Px(0.0),
+            right: Val::Px(0.0),
+            width: Val::Px(240.0),
+            margin: UiRect::horizontal(Val::Auto),
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+            border_radius: BorderRadius::all(Val::Px(6.0)),
+            justify_content: JustifyContent::Center,
+            display: Display::None, // Shown while paused
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Text::new("Paused (Space to resume)"),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.85, 0.4)),
+        PausedIndicator,
+    ));
+}
+
+fn setup_connection_indicator(mut commands: Commands, column: Single<Entity, With<MinimapColumn>>) {
+    let (color, label) = connection_display(&ConnectionStatus::Connecting);
+    commands
+        .spawn((
+            Node {
+                max_width: Val::Px(420.0),
+                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                border_radius: BorderRadius::all(Val::Px(6.0)),
+                column_gap: Val::Px(8.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            ChildOf(*column),
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Node {
+                    width: Val::Px(10.0),
+                    height: Val::Px(10.0),
+                    flex_shrink: 0.0,
+                    border_radius: BorderRadius::all(Val::Px(5.0)),
+                    ..default()
+                },
+                BackgroundColor(color),
+                ConnectionDot,
+            ));
+            row.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.85)),
+                ConnectionLabel,
+            ));
+        });
+}
+
+/// Dot color and label for a connection state: green when connected, amber
+/// while the first attempt is under way, red with the error otherwise
+fn connection_display(status: &ConnectionStatus) -> (Color, String) {
+    match status {
+        ConnectionStatus::Connecting => (Color::srgb(1.0, 0.75, 0.3), "Connecting to server…".to_string()),
+        ConnectionStatus::Connected => (Color::srgb(0.3, 0.9, 0.4), "Connected".to_string()),
+        ConnectionStatus::Disconnected { error } => (Color::srgb(1.0, 0.3, 0.3), format!("Disconnected: {}", error)),
+        ConnectionStatus::Reconnecting { last_error } => {
+            (Color::srgb(1.0, 0.3, 0.3), format!("Reconnecting… ({})", last_error))
+        }
+    }
+}
+
+fn update_connection_indicator(
+    mut connection: ResMut<ServerConnection>,
+    mut dots: Query<&mut BackgroundColor, With<ConnectionDot>>,
+    mut labels: Query<&mut Text, With<ConnectionLabel>>,
+) {
+    let Some(status) = connection.receiver.try_iter().last() else {
+        return;
+    };
+    if status == connection.status {
+        return;
+    }
+    let (color, label) = connection_display(&status);
+    connection.status = status;
+    for mut dot in dots.iter_mut() {
+        dot.0 = color;
+    }
+    for mut text in labels.iter_mut() {
+        text.0 = label.clone();
+    }
+}
+
+fn setup_post_fx_readout(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(240.0),
+            left: Val::Px(0.0),
+            right: Val::Px(0.0),
+            width: Val::Px(320.0),
+            margin: UiRect::horizontal(Val::Auto),
+            padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+            border_radius: BorderRadius::all(Val::Px(6.0)),
+            justify_content: JustifyContent::Center,
+            display: Display::None, // Shown briefly after an adjustment
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.8, 0.7, 1.0)),
+        PostFxReadout::default(),
+    ));
+}
+
+/// [ and ] turn bloom down and up, - and = chromatic aberration, for tuning the
+/// look to a projector or monitor; the new values show for a couple of seconds
+fn handle_post_fx_keys(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    text_focus: TextInputFocus,
+    mut camera: Query<(&mut Bloom, &mut ChromaticAberration)>,
+    mut readout: Query<(&mut Node, &mut Text, &mut PostFxReadout)>,
+) {
+    let Ok((mut node, mut text, mut shown)) = readout.single_mut() else {
+        return;
+    };
+    shown.remaining -= time.delta_secs();
+    if shown.remaining <= 0.0 && node.display != Display::None {
+        node.display = Display::None;
+    }
+
+    if text_focus.is_typing() {
+        return;
+    }
+    let bloom_delta = if keyboard.just_pressed(KeyCode::BracketRight) {
+        BLOOM_STEP
+    } else if keyboard.just_pressed(KeyCode::BracketLeft) {
+        -BLOOM_STEP
+    } else {
+        0.0
+    };
+    let aberration_delta = if keyboard.just_pressed(KeyCode::Equal) {
+        ABERRATION_STEP
+    } else if keyboard.just_pressed(KeyCode::Minus) {
+        -ABERRATION_STEP
+    } else {
+        0.0
+    };
+    if bloom_delta == 0.0 && aberration_delta == 0.0 {
+        return;
+    }
+
+    let Ok((mut bloom, mut aberration)) = camera.single_mut() else {
+        return;
+    };
+    bloom.intensity = (bloom.intensity + bloom_delta).clamp(0.0, BLOOM_MAX);
+    aberration.intensity = (aberration.intensity + aberration_delta).clamp(0.0, ABERRATION_MAX);
+
+    text.0 = format!("Bloom {:.2}  |  Aberration {:.3}", bloom.intensity, aberration.intensity);
+    node.display = Display::Flex;
+    shown.remaining = POST_FX_READOUT_SECS;
+}
+
+/// Space toggles the pause, unless a text field is taking the keystroke
+fn handle_pause_toggle(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut paused: ResMut<Paused>,
+    mut indicator: Query<&mut Node, With<PausedIndicator>>,
+) {
+    if !keyboard.just_pressed(KeyCode::Space) {
+        return;
+    }
+    paused.0 = !paused.0;
+    for mut node in indicator.iter_mut() {
+        node.display = if paused.0 { Display::Flex } else { Display::None };
+    }
+}
+
+/// 1–6 show and hide the camera mode, agent activity, file stats, legend, hover and leaderboard panels.
+/// Visibility rather than display, since the hover panel sets its own display every frame.
+fn handle_hud_toggles(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut panels: Query<(&HudToggle, &mut Visibility)>,
+) {
+    for (toggle, mut visibility) in panels.iter_mut() {
+        if keyboard.just_pressed(toggle.key) {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+fn toggle_debug_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut overlay: Query<&mut Node, With<DebugOverlay>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F3) {
+        return;
+    }
+    for mut node in overlay.iter_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+fn update_debug_overlay(
+    diagnostics: Res<DiagnosticsStore>,
+    stars: Query<(), With<FileStar>>,
+    agents: Query<(), With<agent::Agent>>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut overlay: Query<(&Node, &mut Text), With<DebugOverlay>>,
+) {
+    let Ok((node, mut text)) = overlay.single_mut() else {
+        return;
+    };
+    if node.display == Display::None {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .map_or("--".to_string(), |fps| format!("{:.0}", fps));
+    text.0 = format!(
+        "FPS {}  |  stars {}  |  agents {}  |  materials {}",
+        fps,
+        stars.iter().count(),
+        agents.iter().count(),
+        materials.len(),
+    );
+}
+
+/// Top-level UI nodes; hiding these hides the whole HUD
+type UiRootFilter = (With<Node>, Without<ChildOf>);
+
+/// F12 saves the current frame to a PNG in the working directory;
+/// Shift+F12 hides the HUD for that frame to get a clean shot of the galaxy.
+fn handle_screenshot_keys(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut ui_roots: Query<(Entity, &mut Visibility), UiRootFilter>,
+) {
+    if !keyboard.just_pressed(KeyCode::F12) {
+        return;
+    }
+    let clean = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let suffix = if clean { "-clean" } else { "" };
+    let path = PathBuf::from(format!("space-agents-{}{}.png", stamp, suffix));
+
+    // Hide every top-level UI node for the captured frame, remembering what was already hidden
+    let mut hidden = Vec::new();
+    if clean {
+        for (entity, mut visibility) in ui_roots.iter_mut() {
+            if *visibility != Visibility::Hidden {
+                hidden.push((entity, *visibility));
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+
+    let saved_path = path.clone();
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(path))
+        .observe(
+            move |_: On<ScreenshotCaptured>, mut visibilities: Query<&mut Visibility>| {
+                for (entity, previous) in hidden.drain(..) {
+                    if let Ok(mut visibility) = visibilities.get_mut(entity) {
+                        *visibility = previous;
+                    }
+                }
+                println!("Screenshot saved to {}", saved_path.display());
+            },
+        );
+}
+
+const DOUBLE_CLICK_WINDOW: f32 = 0.35;
+const FOCUS_DISTANCE: f32 = 12.0;
+
+fn on_file_star_click(
+    event: On<Pointer<Click>>,
+    time: Res<Time>,
+    stars: Query<&FileStar>,
+    fs_state: Res<FileSystemState>,
+    mut last_click: ResMut<LastStarClick>,
+    mut controller: ResMut<CameraController>,
+    mut expansions: ResMut<PendingExpansions>,
+) {
+    if event.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(star) = stars.get(event.entity) else {
+        return;
+    };
+
+    // A single click unfolds a "…N more" star
+    if fs_state.model.is_aggregate(star.node_index) {
+        expansions.nodes.push(star.node_index);
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    let is_double_click = last_click.node_index == Some(star.node_index)
+        && now - last_click.time <= DOUBLE_CLICK_WINDOW;
+    last_click.node_index = Some(star.node_index);
+    last_click.time = now;
+
+    if !is_double_click {
+        return;
+    }
+    last_click.node_index = None;
+
+    // The star may have been deleted between the two clicks
+    let Some(node) = fs_state.model.get_node(star.node_index) else {
+        return;
+    };
+    if !fs_state.model.path_to_index.contains_key(&node.path) {
+        return;
+    }
+
+    let target = fs_state.model.position(star.node_index);
+    let pose = focus_pose(&controller, target);
+
+    controller.mode = CameraMode::Manual;
+    controller.transition = None;
+    controller.set_pose(pose);
+}
+
+/// Camera pose that puts `target` in the center of the view
+fn focus_pose(controller: &CameraController, target: Vec3) -> CameraPose {
+    // The camera always looks at the origin, so place it on the ray from the
+    // origin through the star to get the star centered in view
+    let direction = if target.length_squared() > 0.001 {
+        target.normalize()
+    } else {
+        Vec3::new(controller.orbit_angle.cos(), 0.5, controller.orbit_angle.sin()).normalize()
+    };
+    let camera_pos = target + direction * FOCUS_DISTANCE;
+
+    CameraPose {
+        distance: Vec2::new(camera_pos.x, camera_pos.z).length().max(1.0),
+        angle: camera_pos.z.atan2(camera_pos.x),
+        height: camera_pos.y,
+    }
+}
+
+fn advance_camera_transition(mut controller: ResMut<CameraController>, time: Res<Time>) {
+    if controller.camera_frozen {
+        return;
+    }
+    let Some(transition) = controller.transition.as_mut() else {
+        return;
+    };
+
+    transition.elapsed += time.delta_secs();
+    let t = ease_out_cubic((transition.elapsed / transition.duration).clamp(0.0, 1.0));
+    let (from, to) = (transition.from, transition.to);
+    let finished = transition.elapsed >= transition.duration;
+
+    controller.set_pose(CameraPose {
+        distance: from.distance.lerp(to.distance, t),
+        angle: from.angle.lerp(to.angle, t),
+        height: from.height.lerp(to.height, t),
+    });
+
+    // Hand control back to the normal camera modes
+    if finished {
+        controller.transition = None;
     }
 }
 
 fn update_camera(
     _time: Res<Time>,
     controller: Res<CameraController>,
+    selected: Res<SelectedAgent>,
+    agents: Query<&Transform, (With<agent::Agent>, Without<Camera3d>)>,
     mut camera_query: Query<&mut Transform, With<Camera3d>>,
 ) {
+    // Frozen camera keeps its last transform
+    if controller.camera_frozen {
+        return;
+    }
+
     match controller.mode {
         CameraMode::Auto | CameraMode::Follow => {
-            // Auto orbit (Follow will do the same for now)
+            // Auto orbit; Follow orbits the selected agent instead of the origin
+            let center = match controller.mode {
+                CameraMode::Follow => selected
+                    .0
+                    .and_then(|entity| agents.get(entity).ok())
+                    .map_or(Vec3::ZERO, |transform| transform.translation),
+                _ => Vec3::ZERO,
+            };
             let angle = controller.orbit_angle;
             let x = controller.orbit_distance * angle.cos();
             let z = controller.orbit_distance * angle.sin();
             let y = controller.orbit_height;
 
             if let Ok(mut transform) = camera_query.single_mut() {
-                *transform = Transform::from_xyz(x, y, z).looking_at(Vec3::ZERO, Vec3::Y);
+                *transform = Transform::from_translation(center + Vec3::new(x, y, z)).looking_at(center, Vec3::Y);
             }
         }
         CameraMode::Manual => {
@@ -1189,6 +3373,15 @@ fn update_camera(
                 *transform = Transform::from_xyz(x, y, z).looking_at(Vec3::ZERO, Vec3::Y);
             }
         }
+        CameraMode::TopDown => {
+            // Straight down at the pan target; -Z is "up" on screen
+            let target = controller.pan_target;
+            let eye = target + Vec3::Y * controller.top_down_height;
+
+            if let Ok(mut transform) = camera_query.single_mut() {
+                *transform = Transform::from_translation(eye).looking_at(target, Vec3::NEG_Z);
+            }
+        }
     }
 }
 
@@ -1196,30 +3389,72 @@ fn handle_manual_camera_input(
     mut controller: ResMut<CameraController>,
     time: Res<Time>,
     keyboard: Res<ButtonInput<KeyCode>>,
+    text_focus: TextInputFocus,
+    paused: Res<Paused>,
+    settings: Res<Settings>,
 ) {
-    // Auto mode updates angle automatically
+    let camera = &settings.camera;
+
+    // Frozen camera ignores both auto-orbit and manual input, and transitions own the camera
+    if controller.camera_frozen || controller.transition.is_some() {
+        return;
+    }
+
+    // Auto mode updates angle automatically (but holds still while paused)
     if controller.mode == CameraMode::Auto || controller.mode == CameraMode::Follow {
-        controller.orbit_angle += time.delta_secs() * 0.1;
+        if !paused.0 {
+            controller.orbit_angle += time.delta_secs() * camera.orbit_speed;
+        }
         return;
     }
 
-    // Manual mode controls
-    if controller.mode != CameraMode::Manual {
+    // Arrow keys and W/S belong to the text field while one is focused
+    if text_focus.is_typing() {
         return;
     }
 
     // Arrow keys for navigation
-    let move_speed = 20.0 * time.delta_secs();
-    let rotate_speed = 2.0 * time.delta_secs();
+    let move_speed = camera.move_speed * time.delta_secs();
+    let rotate_speed = camera.rotate_speed * time.delta_secs();
+
+    // Top-down mode: arrows pan across the XZ plane, W/S zoom
+    if controller.mode == CameraMode::TopDown {
+        let pan_speed = move_speed * controller.top_down_height / 40.0;
+        if keyboard.pressed(KeyCode::ArrowUp) {
+            controller.pan_target.z -= pan_speed;
+        }
+        if keyboard.pressed(KeyCode::ArrowDown) {
+            controller.pan_target.z += pan_speed;
+        }
+        if keyboard.pressed(KeyCode::ArrowLeft) {
+            controller.pan_target.x -= pan_speed;
+        }
+        if keyboard.pressed(KeyCode::ArrowRight) {
+            controller.pan_target.x += pan_speed;
+        }
+        if keyboard.pressed(KeyCode::KeyW) {
+            controller.top_down_height -= move_speed * 2.0;
+        }
+        if keyboard.pressed(KeyCode::KeyS) {
+            controller.top_down_height += move_speed * 2.0;
+        }
+        controller.top_down_height = camera.clamp_top_down_height(controller.top_down_height);
+        return;
+    }
+
+    // Manual mode controls
+    if controller.mode != CameraMode::Manual {
+        return;
+    }
 
     // Up/Down arrows: zoom in/out
     if keyboard.pressed(KeyCode::ArrowUp) {
         controller.orbit_distance -= move_speed;
-        controller.orbit_distance = controller.orbit_distance.clamp(10.0, 100.0);
+        controller.orbit_distance = camera.clamp_distance(controller.orbit_distance);
     }
     if keyboard.pressed(KeyCode::ArrowDown) {
         controller.orbit_distance += move_speed;
-        controller.orbit_distance = controller.orbit_distance.clamp(10.0, 100.0);
+        controller.orbit_distance = camera.clamp_distance(controller.orbit_distance);
     }
 
     // Left/Right arrows: rotate around
@@ -1233,11 +3468,134 @@ fn handle_manual_camera_input(
     // W/S keys: adjust height
     if keyboard.pressed(KeyCode::KeyW) {
         controller.orbit_height += move_speed * 0.5;
-        controller.orbit_height = controller.orbit_height.clamp(5.0, 50.0);
+        controller.orbit_height = camera.clamp_height(controller.orbit_height);
     }
     if keyboard.pressed(KeyCode::KeyS) {
         controller.orbit_height -= move_speed * 0.5;
-        controller.orbit_height = controller.orbit_height.clamp(5.0, 50.0);
+        controller.orbit_height = camera.clamp_height(controller.orbit_height);
+    }
+}
+
+/// Faint lines from each star to its parent so the directory tree is legible.
+/// Drawn with gizmos every frame, so they follow nodes added or removed by the watcher.
+fn draw_hierarchy_lines(
+    mut gizmos: Gizmos,
+    fs_state: Res<FileSystemState>,
+    star_query: Query<(&Transform, &Visibility), With<FileStar>>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let camera_pos = camera_query
+        .single()
+        .map(|t| t.translation())
+        .unwrap_or(Vec3::ZERO);
+
+    for (&node_idx, &entity) in fs_state.entity_map.iter() {
+        let Some(parent_idx) = fs_state.model.nodes[node_idx].parent else {
+            continue;
+        };
+        let Some(&parent_entity) = fs_state.entity_map.get(&parent_idx) else {
+            continue;
+        };
+        let (Ok((child, child_vis)), Ok((parent, parent_vis))) =
+            (star_query.get(entity), star_query.get(parent_entity))
+        else {
+            continue;
+        };
+        // Filtered-out stars don't get lines either
+        if *child_vis == Visibility::Hidden || *parent_vis == Visibility::Hidden {
+            continue;
+        }
+
+        // Fade out lines that are far from the camera
+        let midpoint = (child.translation + parent.translation) * 0.5;
+        let distance = midpoint.distance(camera_pos);
+        let alpha = HIERARCHY_LINE_ALPHA * (1.0 - (distance / HIERARCHY_LINE_FADE_DISTANCE)).clamp(0.0, 1.0);
+        if alpha <= 0.0 {
+            continue;
+        }
+
+        gizmos.line(
+            parent.translation,
+            child.translation,
+            Color::srgba(0.6, 0.7, 1.0, alpha),
+        );
+    }
+}
+
+fn handle_grid_toggle(
+    mut grid: ResMut<GroundGrid>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyG) {
+        grid.visible = !grid.visible;
+    }
+}
+
+fn handle_glyph_toggle(
+    mut glyphs: ResMut<StarGlyphs>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyI) {
+        glyphs.visible = !glyphs.visible;
+    }
+}
+
+/// Spawn a glyph under every star when glyphs are turned on (and under stars
+/// added while they're on), and despawn them all when they're turned off
+fn sync_star_glyphs(
+    mut commands: Commands,
+    glyphs: Res<StarGlyphs>,
+    assets: StarAssets,
+    fs_state: Res<FileSystemState>,
+    stars: Query<(Entity, Ref<FileStar>)>,
+    glyph_query: Query<Entity, With<StarGlyph>>,
+) {
+    if !glyphs.visible {
+        if glyphs.is_changed() {
+            for entity in glyph_query.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+        return;
+    }
+    for (entity, star) in stars.iter() {
+        if glyphs.is_changed() || star.is_added() {
+            spawn_star_glyph(&mut commands, &assets, &fs_state.model, star.node_index, entity);
+        }
+    }
+}
+
+/// Ground grid drawn one cell edge at a time so each segment fades with its own
+/// distance from the camera
+fn draw_ground_grid(
+    mut gizmos: Gizmos,
+    grid: Res<GroundGrid>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    if !grid.visible {
+        return;
+    }
+    let camera_pos = camera_query
+        .single()
+        .map(|t| t.translation())
+        .unwrap_or(Vec3::ZERO);
+
+    let cells = (GRID_HALF_EXTENT / GRID_SPACING) as i32;
+    let coord = |i: i32| i as f32 * GRID_SPACING;
+    for line in -cells..=cells {
+        for step in -cells..cells {
+            // One segment along X and one along Z per step
+            for (start, end) in [
+                (Vec3::new(coord(step), 0.0, coord(line)), Vec3::new(coord(step + 1), 0.0, coord(line))),
+                (Vec3::new(coord(line), 0.0, coord(step)), Vec3::new(coord(line), 0.0, coord(step + 1))),
+            ] {
+                let distance = ((start + end) * 0.5).distance(camera_pos);
+                let alpha = GRID_ALPHA * (1.0 - (distance / GRID_FADE_DISTANCE)).clamp(0.0, 1.0);
+                if alpha > 0.0 {
+                    gizmos.line(start, end, Color::srgba(0.6, 0.7, 1.0, alpha));
+                }
+            }
+        }
     }
 }
 
@@ -1262,6 +3620,21 @@ fn billboard_labels(
     }
 }
 
+fn handle_activity_panel_clicks(
+    mut grouping: ResMut<ActivityGrouping>,
+    toggle_query: Query<&Interaction, (Changed<Interaction>, With<ActivityGroupToggle>)>,
+    header_query: Query<(&Interaction, &ActivityGroupHeader), Changed<Interaction>>,
+) {
+    if toggle_query.iter().any(|i| *i == Interaction::Pressed) {
+        grouping.by_directory = !grouping.by_directory;
+    }
+    for (interaction, header) in header_query.iter() {
+        if *interaction == Interaction::Pressed && !grouping.collapsed.remove(&header.0) {
+            grouping.collapsed.insert(header.0.clone());
+        }
+    }
+}
+
 fn update_agent_actions_display(
     mut commands: Commands,
     agents: Query<&agent::Agent>,
@@ -1269,6 +3642,7 @@ fn update_agent_actions_display(
     children_query: Query<&Children>,
     windows: Query<&Window>,
     asset_server: Res<AssetServer>,
+    mut panel: ActivityPanel,
 ) {
     // Get the container entity
     let Ok(container) = container_query.single() else {
@@ -1283,23 +3657,48 @@ fn update_agent_actions_display(
     let _title_font_size = base_font_size * 1.5;
     let action_font_size = base_font_size * 0.75;
 
-    // Despawn all existing child text entities
-    if let Ok(children) = children_query.get(container) {
-        for child in children.iter() {
-            commands.entity(child).despawn();
-        }
-    }
-
-    // Collect all active agents with their actions and colors
-    let mut agent_actions: Vec<(String, String, Color)> = agents
+    // Collect all active agents with their actions, colors and top-level directory
+    let mut agent_actions: Vec<ActivityRow> = agents
         .iter()
         .filter_map(|agent| {
             agent.current_action.as_ref().map(|action| {
-                (agent.session_id.clone(), action.clone(), agent.color)
+                (
+                    agent.session_id.clone(),
+                    action.clone(),
+                    agent.color,
+                    agent.greek_symbol.clone(),
+                    panel.directory_of(agent),
+                )
             })
         })
         .collect();
 
+    // Sort by session_id for consistent ordering
+    agent_actions.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Only rebuild when something on the panel would change
+    let by_directory = panel.grouping.by_directory;
+    let mut collapsed: Vec<String> = panel.grouping.collapsed.iter().cloned().collect();
+    collapsed.sort();
+    let key = ActivityPanelKey {
+        rows: agent_actions,
+        by_directory,
+        collapsed,
+        font_size: action_font_size,
+    };
+    if panel.shown.as_ref() == Some(&key) {
+        return;
+    }
+    let key = panel.shown.insert(key);
+    let (agent_actions, collapsed) = (&key.rows, &key.collapsed);
+
+    // Despawn all existing child text entities
+    if let Ok(children) = children_query.get(container) {
+        for child in children.iter() {
+            commands.entity(child).despawn();
+        }
+    }
+
     // If there are no active actions, show a placeholder
     if agent_actions.is_empty() {
         commands.entity(container).with_children(|parent| {
@@ -1315,13 +3714,159 @@ fn update_agent_actions_display(
         return;
     }
 
-    // Sort by session_id for consistent ordering
-    agent_actions.sort_by(|a, b| a.0.cmp(&b.0));
-
     // Add a text entity for each active action
+    commands.entity(container).with_children(|parent| {
+        parent
+            .spawn(Node {
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .with_children(|title_row| {
+                title_row.spawn((
+                    Text::new("Agent Activity"),
+                    TextFont {
+                        font_size: 22.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+                title_row
+                    .spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                            border_radius: BorderRadius::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgba(0.3, 0.2, 0.5, 0.6)),
+                        ActivityGroupToggle,
+                    ))
+                    .with_child((
+                        Text::new(if by_directory { "By dir" } else { "Flat" }),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+            });
+
+        // Load a font that supports Greek characters
+        let greek_font = asset_server.load("fonts/FiraMono-Medium.ttf");
+        let action_row = |symbol: &str, action: &str, color: Color, indent: f32| {
+            (
+                Text::new(format!("{} {}", symbol, action)),
+                TextFont {
+                    font: greek_font.clone(),
+                    font_size: action_font_size,
+                    ..default()
+                },
+                TextColor(color),
+                Node {
+                    margin: UiRect::left(Val::Px(indent)),
+                    ..default()
+                },
+            )
+        };
+
+        if !by_directory {
+            // Action list - each agent uses their unique color and Greek symbol
+            for (_session_id, action, color, symbol, _directory) in agent_actions.iter() {
+                parent.spawn(action_row(symbol, action, *color, 0.0));
+            }
+            return;
+        }
+
+        // One foldable header per top-level directory, agents beneath it
+        let mut by_dir: BTreeMap<&str, Vec<_>> = BTreeMap::new();
+        for entry in agent_actions {
+            by_dir.entry(entry.4.as_str()).or_default().push(entry);
+        }
+        for (directory, entries) in by_dir {
+            let collapsed = collapsed.iter().any(|folded| folded == directory);
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(4.0), Val::Px(1.0)),
+                        ..default()
+                    },
+                    ActivityGroupHeader(directory.to_string()),
+                ))
+                .with_child((
+                    Text::new(format!(
+                        "{} {} ({})",
+                        if collapsed { "+" } else { "-" },
+                        directory,
+                        entries.len()
+                    )),
+                    TextFont {
+                        font: greek_font.clone(),
+                        font_size: action_font_size,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.75, 0.7, 0.9)),
+                ));
+            if collapsed {
+                continue;
+            }
+            for (_session_id, action, color, symbol, _directory) in entries {
+                parent.spawn(action_row(symbol, action, *color, 14.0));
+            }
+        }
+    });
+}
+
+fn update_agent_leaderboard(
+    mut commands: Commands,
+    registry: Res<agent::AgentRegistry>,
+    agents: Query<&agent::Agent>,
+    container_query: Query<Entity, With<LeaderboardContainer>>,
+    children_query: Query<&Children>,
+    asset_server: Res<AssetServer>,
+    mut shown: Local<Option<Vec<LeaderboardRow>>>,
+) {
+    let Ok(container) = container_query.single() else {
+        return;
+    };
+
+    // Walk sessions in start order so the stable sort breaks ties by who started first
+    let mut rows: Vec<LeaderboardRow> = registry
+        .session_id_order
+        .iter()
+        .filter_map(|session_id| registry.map.get(session_id))
+        .filter_map(|&entity| agents.get(entity).ok())
+        .filter(|agent| !matches!(agent.state, agent::AgentState::Despawning { .. }))
+        .map(|agent| {
+            (
+                agent.greek_symbol.clone(),
+                agent.color,
+                agent.touched_files.len(),
+                agent.action_count,
+            )
+        })
+        .collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.2));
+
+    // Only rebuild when the ranking or a count changed
+    if shown.as_ref() == Some(&rows) {
+        return;
+    }
+    let rows = shown.insert(rows);
+
+    if let Ok(children) = children_query.get(container) {
+        for child in children.iter() {
+            commands.entity(child).despawn();
+        }
+    }
+
+    let greek_font = asset_server.load("fonts/FiraMono-Medium.ttf");
     commands.entity(container).with_children(|parent| {
         parent.spawn((
-            Text::new("Agent Activity"),
+            Text::new("Leaderboard"),
             TextFont {
                 font_size: 22.0,
                 ..default()
@@ -1329,25 +3874,52 @@ fn update_agent_actions_display(
             TextColor(Color::WHITE),
         ));
 
-        // Greek alphabet symbols
-        let greek_symbols = ["α", "β", "γ", "δ", "ε", "ζ", "η", "θ", "ι", "κ", "λ", "μ",
-                             "ν", "ξ", "ο", "π", "ρ", "σ", "τ", "υ", "φ", "χ", "ψ", "ω"];
-
-        // Load a font that supports Greek characters
-        let greek_font = asset_server.load("fonts/FiraMono-Medium.ttf");
-
-        // Action list - each agent uses their unique color and Greek symbol
-        for (i, (_session_id, action, color)) in agent_actions.iter().enumerate() {
-            let symbol = greek_symbols[i % greek_symbols.len()];
+        if rows.is_empty() {
             parent.spawn((
-                Text::new(format!("{} {}", symbol, action)),
+                Text::new("No active agents"),
                 TextFont {
-                    font: greek_font.clone(),
-                    font_size: action_font_size,
+                    font_size: 14.0,
                     ..default()
                 },
-                TextColor(*color),
+                TextColor(Color::srgb(0.5, 0.5, 0.5)),
             ));
+            return;
+        }
+
+        for (rank, (symbol, color, files, uses)) in rows.iter().enumerate() {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::SpaceBetween,
+                    width: Val::Percent(100.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        Text::new(format!("{}. Agent {}", rank + 1, symbol)),
+                        TextFont {
+                            font: greek_font.clone(),
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(*color),
+                    ));
+                    row.spawn((
+                        Text::new(format!(
+                            "{} file{} · {} use{}",
+                            files,
+                            if *files == 1 { "" } else { "s" },
+                            uses,
+                            if *uses == 1 { "" } else { "s" }
+                        )),
+                        TextFont {
+                            font: greek_font.clone(),
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                    ));
+                });
         }
     });
 }
@@ -1370,43 +3942,110 @@ fn generate_agent_color(session_id: &str) -> Color {
     hsl_to_rgb(hue, saturation, lightness)
 }
 
-fn track_file_visits(
-    mut file_stats: ResMut<FileStats>,
-    mut arrived_events: MessageReader<AgentArrivedEvent>,
+/// Load visit counts from earlier runs on this project
+fn load_file_stats(mut file_stats: ResMut<FileStats>, fs_state: Res<FileSystemState>) {
+    file_stats.saved = visit_store::load_visits(&fs_state.root_path);
+    if !file_stats.saved.is_empty() {
+        println!("[visits] Loaded visit counts for {} files", file_stats.saved.len());
+    }
+}
+
+/// Write visit counts to disk periodically while they change, and once more on exit
+fn save_file_stats(
+    file_stats: Res<FileStats>,
+    history: Res<FileEventHistory>,
+    fs_state: Res<FileSystemState>,
+    time: Res<Time>,
+    mut exit_events: MessageReader<AppExit>,
+    mut since_save: Local<f32>,
+    mut dirty: Local<bool>,
+) {
+    *dirty |= history.is_changed();
+    *since_save += time.delta_secs();
+
+    let exiting = exit_events.read().count() > 0;
+    if *dirty && (exiting || *since_save >= VISIT_SAVE_INTERVAL) {
+        visit_store::save_visits(&fs_state.root_path, &file_stats.counts(&history));
+        *dirty = false;
+        *since_save = 0.0;
+    }
+}
+
+fn export_heatmap(path: &Path, history: &FileEventHistory, file_stats: &FileStats, root: &Path) {
+    match heatmap::write_report(path, root, &history.totals, &file_stats.counts(history)) {
+        Ok(()) => println!("[heatmap] Wrote {} files to {}", history.totals.len(), path.display()),
+        Err(e) => eprintln!("[heatmap] Failed to write {}: {}", path.display(), e),
+    }
+}
+
+/// H saves a heatmap report of this run next to the screenshots
+fn handle_heatmap_export_key(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    history: Res<FileEventHistory>,
+    file_stats: Res<FileStats>,
+    fs_state: Res<FileSystemState>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+    export_heatmap(&heatmap::default_report_path(), &history, &file_stats, &fs_state.root_path);
+}
+
+fn export_heatmap_on_exit(
+    export: Res<HeatmapExport>,
+    mut exit_events: MessageReader<AppExit>,
+    history: Res<FileEventHistory>,
+    file_stats: Res<FileStats>,
     fs_state: Res<FileSystemState>,
 ) {
-    for event in arrived_events.read() {
-        // Get the file path for this node
-        if let Some(node) = fs_state.model.nodes.get(event.node_index) {
-            let path = node.path.clone();
-            *file_stats.visits.entry(path).or_insert(0) += 1;
-        }
+    if exit_events.read().count() == 0 {
+        return;
+    }
+    if let Some(path) = &export.exit_path {
+        export_heatmap(path, &history, &file_stats, &fs_state.root_path);
     }
 }
 
 fn update_file_stats_display(
     mut commands: Commands,
     file_stats: Res<FileStats>,
+    history: Res<FileEventHistory>,
     fs_state: Res<FileSystemState>,
-    container_query: Query<Entity, With<FileStatsContainer>>,
-    children_query: Query<&Children>,
+    file_colors: Res<FileColors>,
+    container_query: Query<(Entity, Option<&Children>), With<FileStatsContainer>>,
+    // Rows the panel was last built with: path, visits, edits
+    mut shown: Local<Option<Vec<(PathBuf, usize, usize)>>>,
 ) {
-    let Ok(container) = container_query.single() else {
+    let Ok((container, children)) = container_query.single() else {
         return;
     };
 
+    if !file_stats.is_changed() && !history.is_changed() && !file_colors.is_changed() {
+        return;
+    }
+
+    // Get top 6 most visited files
+    let mut sorted_visits: Vec<_> = file_stats.counts(&history).into_iter().collect();
+    sorted_visits.sort_by(|a, b| b.1.visits.cmp(&a.1.visits).then_with(|| a.0.cmp(&b.0)));
+    let top_6: Vec<(PathBuf, usize, usize)> = sorted_visits
+        .into_iter()
+        .take(6)
+        .map(|(path, counts)| (path, counts.visits, counts.edits))
+        .collect();
+
+    // Only rebuild when the ranking or a count changed
+    if shown.as_ref() == Some(&top_6) && !file_colors.is_changed() {
+        return;
+    }
+    let top_6 = shown.insert(top_6);
+
     // Despawn all existing children
-    if let Ok(children) = children_query.get(container) {
+    if let Some(children) = children {
         for child in children.iter() {
             commands.entity(child).despawn();
         }
     }
 
-    // Get top 6 most visited files
-    let mut sorted_visits: Vec<_> = file_stats.visits.iter().collect();
-    sorted_visits.sort_by(|a, b| b.1.cmp(a.1));
-    let top_6: Vec<_> = sorted_visits.into_iter().take(6).collect();
-
     commands.entity(container).with_children(|parent| {
         if top_6.is_empty() {
             parent.spawn((
@@ -1418,7 +4057,7 @@ fn update_file_stats_display(
                 TextColor(Color::srgb(0.5, 0.5, 0.5)),
             ));
         } else {
-            for (path, count) in top_6 {
+            for (path, count, edits) in top_6.iter() {
                 let filename = path.file_name()
                     .and_then(|n| n.to_str())
                     .unwrap_or("unknown");
@@ -1426,7 +4065,7 @@ fn update_file_stats_display(
                 // Get node color from galaxy
                 let color = if let Some((node_idx, _)) = fs_state.model.get_node_by_path(path) {
                     let node = &fs_state.model.nodes[node_idx];
-                    galaxy::calculate_star_color(node)
+                    galaxy::calculate_star_color(node, &file_colors)
                 } else {
                     Color::srgb(0.7, 0.7, 0.7)
                 };
@@ -1448,9 +4087,9 @@ fn update_file_stats_display(
                         TextColor(color),
                     ));
 
-                    // Edit count (right-aligned, white)
+                    // Edit and read counts (right-aligned, white)
                     row.spawn((
-                        Text::new(format!("{} edits", count)),
+                        Text::new(visit_label(*edits, count - edits)),
                         TextFont {
                             font_size: 14.0,
                             ..default()
@@ -1463,6 +4102,16 @@ fn update_file_stats_display(
     });
 }
 
+/// "2 edits, 1 read", dropping whichever side is zero
+fn visit_label(edits: usize, reads: usize) -> String {
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    match (edits, reads) {
+        (_, 0) => plural(edits, "edit"),
+        (0, _) => plural(reads, "read"),
+        _ => format!("{}, {}", plural(edits, "edit"), plural(reads, "read")),
+    }
+}
+
 // Convert HSL to RGB color
 fn hsl_to_rgb(h: f32, s: f32, l: f32) -> Color {
     let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
@@ -1540,10 +4189,18 @@ fn tool_color(tool_name: &str) -> Color {
         "Read" => Color::srgb(0.4, 0.9, 0.9),   // Cyan
         "Write" => Color::srgb(1.0, 0.65, 0.3),  // Orange
         "Edit" => Color::srgb(0.4, 0.9, 0.4),    // Green
+        "Grep" => Color::srgb(0.95, 0.5, 0.95),  // Magenta
+        "Glob" => Color::srgb(0.6, 0.6, 1.0),    // Periwinkle
+        "Bash" => Color::srgb(1.0, 0.9, 0.35),   // Yellow
+        "Task" => Color::srgb(1.0, 0.45, 0.45),  // Coral
+        "WebFetch" => Color::srgb(0.35, 0.7, 1.0), // Sky blue
         _ => Color::srgb(0.7, 0.7, 0.7),          // Gray
     }
 }
 
+/// (file, events on it, newest event's time and repeat, font size) the hover panel was last built for
+type HoverPanelKey = (usize, usize, Option<(Option<String>, u32)>, f32);
+
 fn update_file_hover_panel(
     time: Res<Time>,
     mut commands: Commands,
@@ -1555,7 +4212,9 @@ fn update_file_hover_panel(
         With<FileHoverPanel>,
     >,
     children_query: Query<&Children>,
+    mut child_colors: Query<(Option<&mut TextColor>, Option<&mut BackgroundColor>), Without<FileHoverPanel>>,
     windows: Query<&Window>,
+    mut shown: Local<Option<HoverPanelKey>>,
 ) {
     let Ok((panel_entity, mut panel_node, mut bg_color, mut border_color, mut anim)) =
         panel_query.single_mut()
@@ -1581,13 +4240,6 @@ fn update_file_hover_panel(
 
     let t = ease_out_cubic(anim.progress);
 
-    // Despawn old children
-    if let Ok(children) = children_query.get(panel_entity) {
-        for child in children.iter() {
-            commands.entity(child).despawn();
-        }
-    }
-
     // Fully hidden
     if anim.progress <= 0.001 {
         panel_node.display = Display::None;
@@ -1606,89 +4258,487 @@ fn update_file_hover_panel(
     // Content
     let Some(node_idx) = anim.last_node else {
         return;
-    };
+    };
+
+    // Get font sizes
+    let base_font_size = if let Ok(window) = windows.single() {
+        (window.width() / 80.0).clamp(14.0, 24.0)
+    } else {
+        16.0
+    };
+    let title_font_size = base_font_size * 1.2;
+    let event_font_size = base_font_size * 0.75;
+
+    let alpha = t;
+
+    // Same file and events as last frame: just fade the existing rows
+    let events = event_history.map.get(&node_idx);
+    let key = (
+        node_idx,
+        events.map_or(0, |events| events.len()),
+        events.and_then(|events| events.last()).map(|event| (event.timestamp.clone(), event.repeat)),
+        base_font_size,
+    );
+    if shown.as_ref() == Some(&key) {
+        if let Ok(children) = children_query.get(panel_entity) {
+            for child in children.iter() {
+                let Ok((text_color, background)) = child_colors.get_mut(child) else {
+                    continue;
+                };
+                // Text fades with the panel; the separator is the one row with a background
+                if let Some(mut text_color) = text_color
+                    && text_color.0.alpha() != alpha
+                {
+                    text_color.0.set_alpha(alpha);
+                }
+                if let Some(mut background) = background
+                    && background.0.alpha() != 0.5 * alpha
+                {
+                    background.0.set_alpha(0.5 * alpha);
+                }
+            }
+        }
+        return;
+    }
+    *shown = Some(key);
+
+    // Despawn old children
+    if let Ok(children) = children_query.get(panel_entity) {
+        for child in children.iter() {
+            commands.entity(child).despawn();
+        }
+    }
+
+    // Get file name
+    let file_name = fs_state
+        .model
+        .get_node(node_idx)
+        .map_or_else(|| "Unknown".to_string(), |node| node.name.clone());
+
+    commands.entity(panel_entity).with_children(|parent| {
+        // Title: file name
+        parent.spawn((
+            Text::new(file_name),
+            TextFont {
+                font_size: title_font_size,
+                ..default()
+            },
+            TextColor(Color::srgba(1.0, 1.0, 1.0, alpha)),
+        ));
+
+        // Thin accent separator
+        parent.spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(1.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.5, 0.3, 0.8, 0.5 * alpha)),
+        ));
+
+        // Get events for this file
+        if let Some(events) = events {
+            // Show last 3 events, most recent first
+            let recent: Vec<_> = events.iter().rev().take(3).collect();
+            for event in recent {
+                let time_str = event
+                    .timestamp
+                    .as_deref()
+                    .map(extract_time_from_rfc3339)
+                    .unwrap_or("--:--:--");
+
+                let base_color = tool_color(&event.tool_name);
+                let srgba = base_color.to_srgba();
+                let color = Color::srgba(srgba.red, srgba.green, srgba.blue, alpha);
+
+                // Use explanation if available, otherwise the tool's verb (or its name)
+                let mut label = if let Some(reason) = &event.reason {
+                    reason.clone()
+                } else {
+                    agent::action_verb(&event.tool_name).unwrap_or(&event.tool_name).to_string()
+                };
+                if event.repeat > 1 {
+                    label = format!("{} ×{}", label, event.repeat);
+                }
+                let label = format!("{} [{}]", label, time_str);
+
+                parent.spawn((
+                    Text::new(label),
+                    TextFont {
+                        font_size: event_font_size,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
+        } else {
+            parent.spawn((
+                Text::new("No recent events"),
+                TextFont {
+                    font_size: event_font_size,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.5, 0.5, 0.5, alpha)),
+            ));
+        }
+    });
+}
+
+fn update_agent_history_panel(
+    mut commands: Commands,
+    mut selected: ResMut<SelectedAgent>,
+    spotlight: Res<Spotlight>,
+    agents: Query<&agent::Agent>,
+    mut panel_query: Query<(Entity, &mut Node), With<AgentHistoryPanel>>,
+    children_query: Query<&Children>,
+    // (agent, history length, newest timestamp) the panel was last built for
+    mut shown: Local<Option<(Entity, usize, Option<String>)>>,
+) {
+    let Ok((panel_entity, mut panel_node)) = panel_query.single_mut() else {
+        return;
+    };
+
+    // Close the panel if nothing is selected or the agent despawned
+    let Some((entity, agent)) = selected
+        .0
+        .and_then(|entity| agents.get(entity).ok().map(|agent| (entity, agent)))
+    else {
+        if selected.0.is_some() {
+            selected.0 = None;
+        }
+        panel_node.display = Display::None;
+        *shown = None;
+        return;
+    };
+
+    let key = (
+        entity,
+        agent.history.len(),
+        agent.history.back().and_then(|e| e.timestamp.clone()),
+    );
+    if shown.as_ref() == Some(&key) && !spotlight.is_changed() {
+        return;
+    }
+    *shown = Some(key);
+
+    panel_node.display = Display::Flex;
+
+    if let Ok(children) = children_query.get(panel_entity) {
+        for child in children.iter() {
+            commands.entity(child).despawn();
+        }
+    }
+
+    commands.entity(panel_entity).with_children(|parent| {
+        // Header: agent name + close button
+        parent
+            .spawn(Node {
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                width: Val::Percent(100.0),
+                ..default()
+            })
+            .with_children(|header| {
+                header.spawn((
+                    Text::new(format!("Agent {}", agent.greek_symbol)),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(agent.color),
+                ));
+
+                let spotlit = spotlight.0.as_ref() == Some(&agent.session_id);
+                header
+                    .spawn((
+                        Button,
+                        Node {
+                            margin: UiRect::left(Val::Auto),
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                            border_radius: BorderRadius::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(if spotlit {
+                            agent.color.with_alpha(0.6)
+                        } else {
+                            Color::srgba(0.3, 0.2, 0.5, 0.6)
+                        }),
+                        AgentSpotlightButton,
+                    ))
+                    .with_child((
+                        Text::new(if spotlit { "Clear spotlight" } else { "Spotlight" }),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+
+                header
+                    .spawn((
+                        Button,
+                        Node {
+                            margin: UiRect::left(Val::Px(6.0)),
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                            border_radius: BorderRadius::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgba(0.3, 0.2, 0.5, 0.6)),
+                        AgentHistoryCloseButton,
+                    ))
+                    .with_child((
+                        Text::new("x"),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+            });
+
+        // Thin accent separator
+        parent.spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(1.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.5, 0.3, 0.8, 0.5)),
+        ));
+
+        if agent.history.is_empty() {
+            parent.spawn((
+                Text::new("No tool uses yet"),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.5, 0.5, 0.5)),
+            ));
+            return;
+        }
+
+        // Most recent first
+        for event in agent.history.iter().rev().take(AGENT_HISTORY_PANEL_ROWS) {
+            let time_str = event
+                .timestamp
+                .as_deref()
+                .map(extract_time_from_rfc3339)
+                .unwrap_or("--:--:--");
+            let file_name = event
+                .file_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("unknown");
+
+            parent.spawn((
+                Text::new(format!("[{}] {} {}", time_str, event.tool_name, file_name)),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(tool_color(&event.tool_name)),
+            ));
+        }
+    });
+}
+
+fn handle_agent_history_close(
+    mut selected: ResMut<SelectedAgent>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<AgentHistoryCloseButton>)>,
+) {
+    if button_query.iter().any(|i| *i == Interaction::Pressed) {
+        selected.0 = None;
+    }
+}
+
+/// Spotlight the open agent's session, or clear the spotlight if it's already on it
+fn handle_agent_spotlight_button(
+    selected: Res<SelectedAgent>,
+    mut spotlight: ResMut<Spotlight>,
+    agents: Query<&agent::Agent>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<AgentSpotlightButton>)>,
+) {
+    if !button_query.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+    let Some(agent) = selected.0.and_then(|entity| agents.get(entity).ok()) else {
+        return;
+    };
+    spotlight.0 = if spotlight.0.as_ref() == Some(&agent.session_id) {
+        None
+    } else {
+        Some(agent.session_id.clone())
+    };
+}
+
+/// Tab / Shift-Tab step the selected agent through live sessions in arrival order,
+/// switching the camera to Follow and carrying an active spotlight along
+fn cycle_agent_focus(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<AgentRegistry>,
+    agents: Query<&agent::Agent>,
+    mut selected: ResMut<SelectedAgent>,
+    mut spotlight: ResMut<Spotlight>,
+    mut controller: ResMut<CameraController>,
+    // Slot of the last focused agent, to carry on from if it has since despawned
+    mut index: Local<Option<usize>>,
+) {
+    if !keyboard.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    let backward = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    let live: Vec<(Entity, &agent::Agent)> = registry
+        .session_id_order
+        .iter()
+        .filter_map(|session_id| registry.map.get(session_id))
+        .filter_map(|&entity| agents.get(entity).ok().map(|agent| (entity, agent)))
+        .filter(|(_, agent)| !matches!(agent.state, agent::AgentState::Despawning { .. }))
+        .collect();
+    if live.is_empty() {
+        return;
+    }
+
+    let current = selected
+        .0
+        .and_then(|entity| live.iter().position(|(e, _)| *e == entity));
+    let next = match (current, *index) {
+        (Some(i), _) if backward => (i + live.len() - 1) % live.len(),
+        (Some(i), _) => (i + 1) % live.len(),
+        // The focused agent left; the slot it held now has its successor in it
+        (None, Some(i)) if backward => (i.min(live.len()) + live.len() - 1) % live.len(),
+        (None, Some(i)) => i % live.len(),
+        (None, None) if backward => live.len() - 1,
+        (None, None) => 0,
+    };
+    *index = Some(next);
+
+    let (entity, agent) = live[next];
+    selected.0 = Some(entity);
+    if spotlight.0.is_some() {
+        spotlight.0 = Some(agent.session_id.clone());
+    }
+    controller.mode = CameraMode::Follow;
+}
+
+/// Apply commands from the --stdin-control channel: `focus` frames a file like a
+/// single search match, `follow` selects a session's agent like Tab does
+fn handle_control_commands(
+    channel: Res<control::ControlChannel>,
+    fs_state: Res<FileSystemState>,
+    registry: Res<AgentRegistry>,
+    mut selected: ResMut<SelectedAgent>,
+    mut spotlight: ResMut<Spotlight>,
+    mut controller: ResMut<CameraController>,
+) {
+    while let Ok(command) = channel.receiver.try_recv() {
+        match command {
+            control::ControlCommand::Focus(path) => {
+                let resolved = control::resolve_focus_path(&path, &fs_state.root_path);
+                let Some((node_idx, _)) = fs_state.model.get_node_by_path(&resolved) else {
+                    eprintln!("[control] No star for {}", path.display());
+                    continue;
+                };
+                // Files inside a collapsed directory frame that directory's star
+                let target = fs_state.model.position(fs_state.model.visible_ancestor(node_idx));
+                let pose = focus_pose(&controller, target);
+                controller.mode = CameraMode::Manual;
+                controller.start_transition(pose, RESET_VIEW_DURATION);
+            }
+            control::ControlCommand::Follow(session_id) => {
+                let Some(&entity) = registry.map.get(&session_id) else {
+                    eprintln!("[control] No agent for session {}", session_id);
+                    continue;
+                };
+                selected.0 = Some(entity);
+                if spotlight.0.is_some() {
+                    spotlight.0 = Some(session_id);
+                }
+                controller.transition = None;
+                controller.mode = CameraMode::Follow;
+            }
+        }
+    }
+}
+
+fn dismiss_agent_history_on_empty_click(
+    mut selected: ResMut<SelectedAgent>,
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    hover_map: Res<HoverMap>,
+) {
+    if selected.0.is_none() || !mouse_button.just_pressed(MouseButton::Left) {
+        return;
+    }
 
-    // Get font sizes
-    let base_font_size = if let Ok(window) = windows.single() {
-        (window.width() / 80.0).clamp(14.0, 24.0)
-    } else {
-        16.0
-    };
-    let title_font_size = base_font_size * 1.2;
-    let event_font_size = base_font_size * 0.75;
+    // Nothing under the pointer (no UI, star or agent) means empty space
+    let over_something = hover_map
+        .get(&PointerId::Mouse)
+        .is_some_and(|hits| !hits.is_empty());
+    if !over_something {
+        selected.0 = None;
+    }
+}
 
-    // Get file name
-    let file_name = if node_idx < fs_state.model.nodes.len() {
-        fs_state.model.nodes[node_idx].name.clone()
-    } else {
-        "Unknown".to_string()
+fn spawn_session_toasts(
+    mut commands: Commands,
+    mut finished_events: MessageReader<AgentFinishedEvent>,
+    container_query: Query<Entity, With<ToastContainer>>,
+) {
+    let Ok(container) = container_query.single() else {
+        finished_events.clear();
+        return;
     };
 
-    let alpha = t;
+    for event in finished_events.read() {
+        let message = format!(
+            "{} finished after {} tool use{}",
+            event.greek_symbol,
+            event.action_count,
+            if event.action_count == 1 { "" } else { "s" }
+        );
 
-    commands.entity(panel_entity).with_children(|parent| {
-        // Title: file name
-        parent.spawn((
-            Text::new(file_name),
+        commands.entity(container).with_child((
+            Text::new(message),
             TextFont {
-                font_size: title_font_size,
+                font_size: 15.0,
                 ..default()
             },
-            TextColor(Color::srgba(1.0, 1.0, 1.0, alpha)),
-        ));
-
-        // Thin accent separator
-        parent.spawn((
+            TextColor(event.color.with_alpha(0.0)),
             Node {
-                width: Val::Percent(100.0),
-                height: Val::Px(1.0),
+                padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                border: UiRect::all(Val::Px(1.0)),
+                border_radius: BorderRadius::all(Val::Px(8.0)),
                 ..default()
             },
-            BackgroundColor(Color::srgba(0.5, 0.3, 0.8, 0.5 * alpha)),
+            BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.0)),
+            BorderColor::all(event.color.with_alpha(0.0)),
+            Toast { age: 0.0 },
         ));
+    }
+}
 
-        // Get events for this file
-        if let Some(events) = event_history.map.get(&node_idx) {
-            // Show last 3 events, most recent first
-            let recent: Vec<_> = events.iter().rev().take(3).collect();
-            for event in recent {
-                let time_str = event
-                    .timestamp
-                    .as_deref()
-                    .map(extract_time_from_rfc3339)
-                    .unwrap_or("--:--:--");
-
-                let base_color = tool_color(&event.tool_name);
-                let srgba = base_color.to_srgba();
-                let color = Color::srgba(srgba.red, srgba.green, srgba.blue, alpha);
+fn update_toasts(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut toasts: Query<(Entity, &mut Toast, &mut TextColor, &mut BackgroundColor, &mut BorderColor)>,
+) {
+    for (entity, mut toast, mut text_color, mut background, mut border) in toasts.iter_mut() {
+        toast.age += time.delta_secs();
+        if toast.age >= TOAST_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
 
-                // Use explanation if available, otherwise show tool name
-                let label = if let Some(reason) = &event.reason {
-                    format!("{} [{}]", reason, time_str)
-                } else {
-                    format!("{} [{}]", event.tool_name, time_str)
-                };
+        let fade_in = ease_out_cubic((toast.age / TOAST_FADE_IN).min(1.0));
+        let fade_out = ease_out_cubic(((TOAST_LIFETIME - toast.age) / TOAST_FADE_OUT).min(1.0));
+        let alpha = fade_in.min(fade_out);
 
-                parent.spawn((
-                    Text::new(label),
-                    TextFont {
-                        font_size: event_font_size,
-                        ..default()
-                    },
-                    TextColor(color),
-                ));
-            }
-        } else {
-            parent.spawn((
-                Text::new("No recent events"),
-                TextFont {
-                    font_size: event_font_size,
-                    ..default()
-                },
-                TextColor(Color::srgba(0.5, 0.5, 0.5, alpha)),
-            ));
-        }
-    });
+        text_color.0.set_alpha(alpha);
+        background.0.set_alpha(0.92 * alpha);
+        *border = BorderColor::all(text_color.0.with_alpha(0.5 * alpha));
+    }
 }
 
 fn hover_glow_system(
@@ -1772,69 +4822,123 @@ fn handle_prompt_unfocus(
     }
 }
 
+/// Character typed by a key for the search box (lowercase letters, digits, common punctuation)
+fn key_to_char(key: &KeyCode) -> Option<char> {
+    match key {
+        KeyCode::Space => Some(' '),
+        KeyCode::KeyA => Some('a'),
+        KeyCode::KeyB => Some('b'),
+        KeyCode::KeyC => Some('c'),
+        KeyCode::KeyD => Some('d'),
+        KeyCode::KeyE => Some('e'),
+        KeyCode::KeyF => Some('f'),
+        KeyCode::KeyG => Some('g'),
+        KeyCode::KeyH => Some('h'),
+        KeyCode::KeyI => Some('i'),
+        KeyCode::KeyJ => Some('j'),
+        KeyCode::KeyK => Some('k'),
+        KeyCode::KeyL => Some('l'),
+        KeyCode::KeyM => Some('m'),
+        KeyCode::KeyN => Some('n'),
+        KeyCode::KeyO => Some('o'),
+        KeyCode::KeyP => Some('p'),
+        KeyCode::KeyQ => Some('q'),
+        KeyCode::KeyR => Some('r'),
+        KeyCode::KeyS => Some('s'),
+        KeyCode::KeyT => Some('t'),
+        KeyCode::KeyU => Some('u'),
+        KeyCode::KeyV => Some('v'),
+        KeyCode::KeyW => Some('w'),
+        KeyCode::KeyX => Some('x'),
+        KeyCode::KeyY => Some('y'),
+        KeyCode::KeyZ => Some('z'),
+        KeyCode::Digit0 => Some('0'),
+        KeyCode::Digit1 => Some('1'),
+        KeyCode::Digit2 => Some('2'),
+        KeyCode::Digit3 => Some('3'),
+        KeyCode::Digit4 => Some('4'),
+        KeyCode::Digit5 => Some('5'),
+        KeyCode::Digit6 => Some('6'),
+        KeyCode::Digit7 => Some('7'),
+        KeyCode::Digit8 => Some('8'),
+        KeyCode::Digit9 => Some('9'),
+        KeyCode::Period => Some('.'),
+        KeyCode::Comma => Some(','),
+        KeyCode::Minus => Some('-'),
+        KeyCode::Slash => Some('/'),
+        _ => None,
+    }
+}
+
 fn handle_prompt_input(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    mut keyboard_events: MessageReader<KeyboardInput>,
     mut prompt_state: ResMut<PromptInputState>,
 ) {
-    // Only handle input when focused
+    // Only handle input when focused (and don't let keys typed elsewhere pile up)
     if !prompt_state.is_focused {
+        keyboard_events.clear();
         return;
     }
 
-    // Handle backspace
-    if keyboard.just_pressed(KeyCode::Backspace) {
-        prompt_state.text.pop();
-    }
+    for event in keyboard_events.read() {
+        // Presses and OS key repeats; releases carry no text
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
 
-    // Handle character input - basic alphanumeric and common punctuation
-    for key in keyboard.get_just_pressed() {
-        let char_to_add = match key {
-            KeyCode::Space => Some(' '),
-            KeyCode::KeyA => Some('a'),
-            KeyCode::KeyB => Some('b'),
-            KeyCode::KeyC => Some('c'),
-            KeyCode::KeyD => Some('d'),
-            KeyCode::KeyE => Some('e'),
-            KeyCode::KeyF => Some('f'),
-            KeyCode::KeyG => Some('g'),
-            KeyCode::KeyH => Some('h'),
-            KeyCode::KeyI => Some('i'),
-            KeyCode::KeyJ => Some('j'),
-            KeyCode::KeyK => Some('k'),
-            KeyCode::KeyL => Some('l'),
-            KeyCode::KeyM => Some('m'),
-            KeyCode::KeyN => Some('n'),
-            KeyCode::KeyO => Some('o'),
-            KeyCode::KeyP => Some('p'),
-            KeyCode::KeyQ => Some('q'),
-            KeyCode::KeyR => Some('r'),
-            KeyCode::KeyS => Some('s'),
-            KeyCode::KeyT => Some('t'),
-            KeyCode::KeyU => Some('u'),
-            KeyCode::KeyV => Some('v'),
-            KeyCode::KeyW => Some('w'),
-            KeyCode::KeyX => Some('x'),
-            KeyCode::KeyY => Some('y'),
-            KeyCode::KeyZ => Some('z'),
-            KeyCode::Digit0 => Some('0'),
-            KeyCode::Digit1 => Some('1'),
-            KeyCode::Digit2 => Some('2'),
-            KeyCode::Digit3 => Some('3'),
-            KeyCode::Digit4 => Some('4'),
-            KeyCode::Digit5 => Some('5'),
-            KeyCode::Digit6 => Some('6'),
-            KeyCode::Digit7 => Some('7'),
-            KeyCode::Digit8 => Some('8'),
-            KeyCode::Digit9 => Some('9'),
-            KeyCode::Period => Some('.'),
-            KeyCode::Comma => Some(','),
-            KeyCode::Minus => Some('-'),
-            KeyCode::Slash => Some('/'),
-            _ => None,
-        };
+        // Escape hands the keyboard back to the camera and hotkeys; the text stays
+        if event.logical_key == Key::Escape {
+            prompt_state.is_focused = false;
+            prompt_state.history_cursor = None;
+            break;
+        }
+
+        // Up/Down step through previously submitted prompts
+        if event.logical_key == Key::ArrowUp {
+            let next = match prompt_state.history_cursor {
+                Some(i) => Some(i.saturating_sub(1)),
+                None if !prompt_state.history.is_empty() => {
+                    prompt_state.draft = prompt_state.text.clone();
+                    Some(prompt_state.history.len() - 1)
+                }
+                None => None,
+            };
+            if let Some(i) = next {
+                prompt_state.text = prompt_state.history[i].clone();
+                prompt_state.history_cursor = Some(i);
+            }
+            continue;
+        }
+        if event.logical_key == Key::ArrowDown {
+            if let Some(i) = prompt_state.history_cursor {
+                if i + 1 < prompt_state.history.len() {
+                    prompt_state.text = prompt_state.history[i + 1].clone();
+                    prompt_state.history_cursor = Some(i + 1);
+                } else {
+                    // Past the newest entry: back to what was being typed
+                    prompt_state.text = std::mem::take(&mut prompt_state.draft);
+                    prompt_state.history_cursor = None;
+                }
+            }
+            continue;
+        }
 
-        if let Some(c) = char_to_add {
-            prompt_state.text.push(c);
+        // Handle backspace
+        if event.logical_key == Key::Backspace {
+            prompt_state.text.pop();
+            prompt_state.history_cursor = None;
+            continue;
+        }
+
+        // Use the layout-aware text so Shift, capitals and symbols all work
+        if let Some(text) = &event.text {
+            prompt_state
+                .text
+                .extend(text.chars().filter(|c| !c.is_control()));
+            // Editing a recalled entry turns it into the new draft
+            if !text.is_empty() {
+                prompt_state.history_cursor = None;
+            }
         }
     }
 }
@@ -1849,7 +4953,9 @@ fn handle_prompt_submit(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut registry: ResMut<agent::AgentRegistry>,
+    agents: Query<&agent::Agent>,
     fs_state: Res<FileSystemState>,
+    launch: Res<LaunchClient>,
 ) {
     let should_submit = (keyboard.just_pressed(KeyCode::Enter) && prompt_state.is_focused)
         || button_query.iter().any(|i| *i == Interaction::Pressed);
@@ -1868,20 +4974,18 @@ fn handle_prompt_submit(
         let mut action_queue = std::collections::VecDeque::new();
 
         // Pick a few random files to visit
-        if fs_state.model.total_nodes() > 0 {
-            let num_files_to_visit = 5.min(fs_state.model.total_nodes());
+        let live: Vec<usize> = fs_state.model.live_indices().collect();
+        if !live.is_empty() {
+            let num_files_to_visit = 5.min(live.len());
             for i in 0..num_files_to_visit {
-                let target_idx = (i * fs_state.model.total_nodes() / num_files_to_visit).min(fs_state.model.total_nodes() - 1);
-                let position = galaxy::calculate_galaxy_position(&fs_state.model, target_idx);
-                action_queue.push_back(agent::AgentAction::MoveTo {
-                    position,
-                    node_index: target_idx,
-                });
+                let target_idx = live[i * live.len() / num_files_to_visit];
+                action_queue.push_back(agent::AgentAction::MoveTo { node_index: target_idx });
             }
         }
 
         // Spawn the agent using the same system as WebSocket agents
-        let greek_symbol = agent::GREEK_SYMBOLS[registry.session_id_order.len() % agent::GREEK_SYMBOLS.len()].to_string();
+        let greek_symbol =
+            agent::greek_symbol_for(&session_id, agents.iter().map(|agent| agent.greek_symbol.as_str()));
 
         let _entity = agent::spawn_agent_entity(
             &mut commands,
@@ -1891,11 +4995,29 @@ fn handle_prompt_submit(
             session_id.clone(),
             action_queue,
             greek_symbol,
+            None, // The server picks the model; its session_start swaps the ship in
         );
 
         registry.session_id_order.push(session_id.clone());
         registry.map.insert(session_id.clone(), _entity);
 
+        // The local agent is a placeholder until the server answers with the real session id
+        ws_client::launch_session(
+            launch.server_address.clone(),
+            prompt_state.text.clone(),
+            fs_state.root_path.to_string_lossy().to_string(),
+            session_id,
+            launch.sender.clone(),
+        );
+
+        // Remember the prompt, skipping immediate repeats
+        if prompt_state.history.last() != Some(&prompt_state.text) {
+            let submitted = prompt_state.text.clone();
+            prompt_state.history.push(submitted);
+        }
+        prompt_state.history_cursor = None;
+        prompt_state.draft.clear();
+
         // Clear the text and unfocus
         prompt_state.text.clear();
         prompt_state.is_focused = false;
@@ -1919,6 +5041,52 @@ fn apply_pending_agent_tasks(
     }
 }
 
+/// Swap each placeholder "user-agent" over to the session id the server assigned,
+/// so the launched session's WebSocket events drive the same spaceship.
+fn reconcile_launched_sessions(
+    launch: Res<LaunchClient>,
+    mut registry: ResMut<agent::AgentRegistry>,
+    mut pending_task: ResMut<PendingAgentTask>,
+    mut agents: Query<&mut agent::Agent>,
+) {
+    for result in launch.receiver.try_iter() {
+        // Server unreachable: the placeholder keeps its local tour
+        let Some(session_id) = result.session_id else {
+            continue;
+        };
+        let Some(entity) = registry.map.remove(&result.local_session_id) else {
+            continue;
+        };
+
+        if registry.map.contains_key(&session_id) {
+            // The session_start beat the HTTP reply and already has a ship; drop ours
+            if let Ok(mut agent) = agents.get_mut(entity) {
+                agent.state = agent::AgentState::Despawning { timer: 0.0 };
+                agent.event_queue.clear();
+            }
+            continue;
+        }
+
+        println!("[agent] {} is now session {}", result.local_session_id, session_id);
+        if let Ok(mut agent) = agents.get_mut(entity) {
+            agent.session_id = session_id.clone();
+            // Moves come from the server from here on
+            agent.event_queue.clear();
+        }
+        if let Some(slot) = registry
+            .session_id_order
+            .iter_mut()
+            .find(|id| **id == result.local_session_id)
+        {
+            *slot = session_id.clone();
+        }
+        if pending_task.session_id.as_ref() == Some(&result.local_session_id) {
+            pending_task.session_id = Some(session_id.clone());
+        }
+        registry.map.insert(session_id, entity);
+    }
+}
+
 fn update_prompt_display(
     mut commands: Commands,
     prompt_state: Res<PromptInputState>,
@@ -2005,6 +5173,203 @@ fn animate_cursor(
     }
 }
 
+fn handle_search_focus(
+    mut search_state: ResMut<SearchState>,
+    input_query: Query<&Interaction, (Changed<Interaction>, With<SearchInputField>)>,
+) {
+    for interaction in input_query.iter() {
+        if *interaction == Interaction::Pressed {
+            search_state.is_focused = true;
+        }
+    }
+}
+
+fn handle_search_unfocus(
+    mut search_state: ResMut<SearchState>,
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    input_query: Query<&Interaction, With<SearchInputField>>,
+) {
+    // Clicking anywhere outside the search box unfocuses it
+    if mouse_button.just_pressed(MouseButton::Left)
+        && let Ok(interaction) = input_query.single()
+        && *interaction == Interaction::None
+        && search_state.is_focused
+    {
+        search_state.is_focused = false;
+    }
+}
+
+fn handle_search_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut search_state: ResMut<SearchState>,
+) {
+    if !search_state.is_focused {
+        return;
+    }
+
+    // Escape clears the search and restores all stars
+    if keyboard.just_pressed(KeyCode::Escape) {
+        search_state.query.clear();
+        search_state.is_focused = false;
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::Enter) {
+        search_state.is_focused = false;
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::Backspace) {
+        search_state.query.pop();
+    }
+
+    for key in keyboard.get_just_pressed() {
+        if let Some(c) = key_to_char(key) {
+            search_state.query.push(c);
+        }
+    }
+}
+
+fn update_search_display(
+    mut commands: Commands,
+    search_state: Res<SearchState>,
+    input_query: Query<Entity, With<SearchInputField>>,
+    children_query: Query<&Children>,
+) {
+    if !search_state.is_changed() {
+        return;
+    }
+    let Ok(input_entity) = input_query.single() else {
+        return;
+    };
+
+    if let Ok(children) = children_query.get(input_entity) {
+        for child in children.iter() {
+            commands.entity(child).despawn();
+        }
+    }
+
+    commands.entity(input_entity).with_children(|parent| {
+        if !search_state.is_focused && search_state.query.is_empty() {
+            parent.spawn((
+                Text::new("Search files..."),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.7, 0.7, 0.7, 0.6)),
+            ));
+            return;
+        }
+
+        parent.spawn((
+            Text::new(&search_state.query),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+
+        if search_state.is_focused {
+            parent.spawn((
+                Text::new("|"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                BlinkingCursor {
+                    timer: 0.0,
+                    visible: true,
+                },
+            ));
+        }
+    });
+}
+
+/// Relight the stars when the spotlight moves, and while it's on, as its session
+/// touches more files
+fn apply_spotlight_to_stars(
+    emphasis: StarEmphasis,
+    fs_state: Res<FileSystemState>,
+    stars: Query<(&FileStar, &MeshMaterial3d<PlanetMaterial>)>,
+    mut planet_materials: ResMut<Assets<PlanetMaterial>>,
+) {
+    let spotlight_moved = emphasis.spotlight.is_changed() && !emphasis.spotlight.is_added();
+    let touched_more = emphasis.spotlight.0.is_some() && emphasis.history.is_changed();
+    if !spotlight_moved && !touched_more {
+        return;
+    }
+
+    for (star, mat_handle) in stars.iter() {
+        let Some(node) = fs_state.model.get_node(star.node_index) else {
+            continue;
+        };
+        // base_color already reflects the active color mode
+        if let Some(material) = planet_materials.get_mut(&mat_handle.0) {
+            let emissive_strength = galaxy::calculate_star_emissive_strength(node)
+                * emphasis.emissive_scale(node);
+            material.base.emissive = LinearRgba::from(material.base.base_color) * emissive_strength;
+        }
+    }
+}
+
+fn apply_search_highlight(
+    mut commands: Commands,
+    emphasis: StarEmphasis,
+    fs_state: Res<FileSystemState>,
+    stars: Query<(Entity, Ref<FileStar>, &MeshMaterial3d<PlanetMaterial>)>,
+    mut planet_materials: ResMut<Assets<PlanetMaterial>>,
+    mut controller: ResMut<CameraController>,
+    mut framed: Local<Option<usize>>,
+) {
+    let search_state = &emphasis.search;
+    let stars_added =
+        !search_state.query.is_empty() && stars.iter().any(|(_, star, _)| star.is_added());
+    if !search_state.is_changed() && !stars_added {
+        return;
+    }
+
+    let mut matched = Vec::new();
+    for (entity, star, mat_handle) in stars.iter() {
+        let Some(node) = fs_state.model.get_node(star.node_index) else {
+            continue;
+        };
+        let is_match = search_state.matches(&node.name);
+        if is_match {
+            matched.push(star.node_index);
+        }
+
+        if is_match {
+            commands.entity(entity).insert(SearchMatch);
+        } else {
+            commands.entity(entity).remove::<SearchMatch>();
+        }
+
+        // base_color already reflects the active color mode
+        if let Some(material) = planet_materials.get_mut(&mat_handle.0) {
+            let color = material.base.base_color;
+            let emissive_strength = galaxy::calculate_star_emissive_strength(node)
+                * emphasis.emissive_scale(node);
+            material.base.emissive = LinearRgba::from(color) * emissive_strength;
+        }
+    }
+
+    // Frame a single match once, without fighting the user if they move away
+    if let [node_idx] = matched[..] {
+        if *framed != Some(node_idx) {
+            *framed = Some(node_idx);
+            let target = fs_state.model.position(node_idx);
+            let pose = focus_pose(&controller, target);
+            controller.mode = CameraMode::Manual;
+            controller.start_transition(pose, RESET_VIEW_DURATION);
+        }
+    } else {
+        *framed = None;
+    }
+}
+
 fn handle_help_button(
     mut tips_state: ResMut<TipsState>,
     button_query: Query<&Interaction, (Changed<Interaction>, With<HelpButton>)>,
@@ -2018,11 +5383,14 @@ fn handle_help_button(
 
 fn handle_close_overlay(
     mut tips_state: ResMut<TipsState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
     button_query: Query<&Interaction, (Changed<Interaction>, With<CloseOverlayButton>)>,
 ) {
-    for interaction in button_query.iter() {
-        if *interaction == Interaction::Pressed {
-            tips_state.visible = false;
+    let escape = tips_state.visible && keyboard.just_pressed(KeyCode::Escape);
+    if escape || button_query.iter().any(|i| *i == Interaction::Pressed) {
+        tips_state.visible = false;
+        if !tips_state.has_been_shown {
+            tips::mark_shown();
             tips_state.has_been_shown = true;
         }
     }
@@ -2040,3 +5408,86 @@ fn update_tips_overlay(
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+
+    fn agent_count(app: &mut App) -> usize {
+        let world = app.world_mut();
+        world.query::<&agent::Agent>().iter(world).count()
+    }
+
+    /// A --headless app on a temp dir holding a.rs, fed agent events through the
+    /// returned sender instead of a server
+    fn headless_app(root: &Path) -> (App, crossbeam_channel::Sender<AgentEvent>) {
+        std::fs::write(root.join("a.rs"), "fn main() {}\n").unwrap();
+        let args = Args::parse_from(["TreeHacks".as_ref(), root.as_os_str(), "--headless".as_ref()]);
+        let (events, receiver) = crossbeam_channel::unbounded();
+        let mut app = build_app(args, Some(receiver));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)));
+        app.finish();
+        app.cleanup();
+        (app, events)
+    }
+
+    fn read_a_rs(root: &Path) -> AgentEvent {
+        AgentEvent::ToolUse {
+            session_id: "s1".to_string(),
+            tool_name: "Read".to_string(),
+            file_path: root.canonicalize().unwrap().join("a.rs").display().to_string(),
+            reason: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn headless_app_spawns_and_despawns_an_agent() {
+        let root = tempfile::tempdir().unwrap();
+        let (mut app, events) = headless_app(root.path());
+
+        events.send(read_a_rs(root.path())).unwrap();
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(agent_count(&mut app), 1);
+
+        events
+            .send(AgentEvent::SessionEnd {
+                session_id: "s1".to_string(),
+            })
+            .unwrap();
+        for _ in 0..100 {
+            app.update();
+        }
+        assert_eq!(agent_count(&mut app), 0);
+    }
+
+    #[test]
+    fn a_status_report_does_not_bring_back_a_departing_agent() {
+        let root = tempfile::tempdir().unwrap();
+        let (mut app, events) = headless_app(root.path());
+
+        events.send(read_a_rs(root.path())).unwrap();
+        for _ in 0..10 {
+            app.update();
+        }
+        events
+            .send(AgentEvent::SessionEnd {
+                session_id: "s1".to_string(),
+            })
+            .unwrap();
+        events
+            .send(AgentEvent::Status {
+                session_id: "s1".to_string(),
+                status: "Thinking".to_string(),
+            })
+            .unwrap();
+        // Well past the despawn, well short of an idle timeout
+        for _ in 0..20 {
+            app.update();
+        }
+        assert_eq!(agent_count(&mut app), 0);
+    }
+}
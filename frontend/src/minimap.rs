@@ -0,0 +1,191 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::ui::RelativeCursorPosition;
+
+use crate::file_colors::FileColors;
+use crate::galaxy::FileStar;
+use crate::{
+    CameraController, CameraMode, CameraPose, FileSystemState, MinimapColumn, FOCUS_DISTANCE, RESET_VIEW_DURATION,
+};
+
+// --- Resources & components ---
+
+/// Top-down overview texture and the world half-width it currently covers
+#[derive(Resource)]
+pub struct Minimap {
+    image: Handle<Image>,
+    half_extent: f32,
+    // Camera translation and forward at the last redraw
+    drawn_camera: Option<(Vec3, Vec3)>,
+}
+
+#[derive(Component)]
+pub struct MinimapView;
+
+// --- Constants ---
+
+const MINIMAP_SIZE: u32 = 180; // pixels per side
+const MINIMAP_MARGIN: f32 = 1.1; // padding around the outermost star
+const MINIMAP_MIN_EXTENT: f32 = 10.0;
+const CAMERA_MARKER_COLOR: [u8; 4] = [255, 255, 255, 255];
+const CAMERA_HEADING_LENGTH: f32 = 14.0; // pixels
+
+pub fn setup_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    column: Single<Entity, With<MinimapColumn>>,
+) {
+    let image = images.add(Image::new_fill(
+        Extent3d {
+            width: MINIMAP_SIZE,
+            height: MINIMAP_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    ));
+
+    // Bottom of the minimap column, just right of the camera mode panel
+    commands.spawn((
+        Button,
+        Node {
+            width: Val::Px(MINIMAP_SIZE as f32),
+            height: Val::Px(MINIMAP_SIZE as f32),
+            border: UiRect::all(Val::Px(1.0)),
+            border_radius: BorderRadius::all(Val::Px(10.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.92)),
+        BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.3)),
+        ImageNode::new(image.clone()),
+        RelativeCursorPosition::default(),
+        MinimapView,
+        ChildOf(*column),
+    ));
+
+    commands.insert_resource(Minimap {
+        image,
+        half_extent: MINIMAP_MIN_EXTENT,
+        drawn_camera: None,
+    });
+}
+
+/// World XZ -> pixel coordinates in the minimap texture
+fn to_pixel(point: Vec2, half_extent: f32) -> Vec2 {
+    (point / half_extent * 0.5 + Vec2::splat(0.5)) * MINIMAP_SIZE as f32
+}
+
+fn plot(data: &mut [u8], x: i32, y: i32, rgba: [u8; 4]) {
+    let size = MINIMAP_SIZE as i32;
+    if x < 0 || y < 0 || x >= size || y >= size {
+        return;
+    }
+    let offset = ((y * size + x) * 4) as usize;
+    data[offset..offset + 4].copy_from_slice(&rgba);
+}
+
+/// Redraw every visible star as a dot, plus the camera position and heading.
+/// Skipped on frames where neither the stars nor the camera have moved.
+pub fn draw_minimap(
+    mut minimap: ResMut<Minimap>,
+    mut images: ResMut<Assets<Image>>,
+    fs_state: Res<FileSystemState>,
+    file_colors: Res<FileColors>,
+    stars: Query<(&FileStar, Ref<Transform>, Ref<Visibility>)>,
+    mut removed_stars: RemovedComponents<FileStar>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let camera = camera_query.single().ok();
+    let camera_pose = camera.map(|camera| (camera.translation(), camera.forward().as_vec3()));
+    let stars_changed = removed_stars.read().count() > 0
+        || file_colors.is_changed()
+        || stars.iter().any(|(_, transform, visibility)| transform.is_changed() || visibility.is_changed());
+    if !stars_changed && camera_pose == minimap.drawn_camera {
+        return;
+    }
+    minimap.drawn_camera = camera_pose;
+
+    // Fit the whole galaxy, growing and shrinking as nodes come and go
+    let farthest = stars
+        .iter()
+        .map(|(_, transform, _)| transform.translation.xz().abs().max_element())
+        .fold(0.0_f32, f32::max);
+    let half_extent = (farthest * MINIMAP_MARGIN).max(MINIMAP_MIN_EXTENT);
+    minimap.half_extent = half_extent;
+
+    let Some(image) = images.get_mut(&minimap.image) else {
+        return;
+    };
+    let Some(data) = image.data.as_mut() else {
+        return;
+    };
+    data.fill(0);
+
+    for (star, transform, visibility) in stars.iter() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let Some(node) = fs_state.model.get_node(star.node_index) else {
+            continue;
+        };
+        let rgba = file_colors.color_of(node).to_srgba().to_u8_array();
+        let pixel = to_pixel(transform.translation.xz(), half_extent);
+        let (x, y) = (pixel.x as i32, pixel.y as i32);
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            plot(data, x + dx, y + dy, rgba);
+        }
+    }
+
+    let Some(camera) = camera else {
+        return;
+    };
+    let position = to_pixel(camera.translation().xz(), half_extent);
+    for dx in -2..=2 {
+        for dy in -2..=2 {
+            plot(data, position.x as i32 + dx, position.y as i32 + dy, CAMERA_MARKER_COLOR);
+        }
+    }
+    // Heading: the camera's forward direction flattened onto the XZ plane
+    let heading = camera.forward().xz().normalize_or_zero();
+    for step in 0..CAMERA_HEADING_LENGTH as i32 {
+        let point = position + heading * step as f32;
+        plot(data, point.x as i32, point.y as i32, CAMERA_MARKER_COLOR);
+    }
+}
+
+type MinimapPressFilter = (Changed<Interaction>, With<MinimapView>);
+
+/// Clicking the minimap swings the camera over to that part of the galaxy
+pub fn handle_minimap_click(
+    minimap: Res<Minimap>,
+    views: Query<(&Interaction, &RelativeCursorPosition), MinimapPressFilter>,
+    mut controller: ResMut<CameraController>,
+) {
+    for (interaction, cursor) in views.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(normalized) = cursor.normalized else {
+            continue;
+        };
+        // normalized is centered on the node, -0.5..0.5 on each axis
+        let target = normalized * 2.0 * minimap.half_extent;
+
+        if controller.mode == CameraMode::TopDown {
+            controller.pan_target = Vec3::new(target.x, 0.0, target.y);
+            continue;
+        }
+
+        // The camera orbits the origin, so look across the galaxy from just beyond the spot
+        let pose = CameraPose {
+            distance: target.length() + FOCUS_DISTANCE,
+            angle: target.y.atan2(target.x),
+            height: controller.orbit_height,
+        };
+        controller.mode = CameraMode::Manual;
+        controller.start_transition(pose, RESET_VIEW_DURATION);
+    }
+}
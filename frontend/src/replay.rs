@@ -0,0 +1,572 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::agent::{generate_agent_color, Agent, AgentActionBubble, AgentNameplate, AgentStatusRing};
+use crate::galaxy::{self, FileStar};
+use crate::planet_material::PlanetMaterial;
+use crate::ws_client::AgentEvent;
+use crate::{FileSystemState, SearchState};
+
+// --- Resources ---
+
+/// An agent event as received, stamped with its arrival time
+pub struct LoggedEvent {
+    pub at: f32, // seconds since startup
+    pub event: AgentEvent,
+    path: OnceLock<Option<PathBuf>>, // canonical target of a tool use, resolved on first replay
+}
+
+impl LoggedEvent {
+    fn path(&self) -> Option<&PathBuf> {
+        self.path
+            .get_or_init(|| match &self.event {
+                AgentEvent::ToolUse { file_path, .. } => {
+                    let path = PathBuf::from(file_path);
+                    Some(path.canonicalize().unwrap_or(path))
+                }
+                _ => None,
+            })
+            .as_ref()
+    }
+}
+
+/// Most events kept for replay; past this the oldest are dropped so a
+/// long-running display doesn't grow without bound
+pub const MAX_LOGGED_EVENTS: usize = 20_000;
+
+/// The latest agent events seen this run, plus the replay cursor when scrubbing
+#[derive(Resource, Default)]
+pub struct EventLog {
+    pub entries: VecDeque<LoggedEvent>, // oldest first, at most MAX_LOGGED_EVENTS
+    dropped: u64, // entries dropped off the front so far, i.e. the first entry's sequence number
+    /// Events that arrived while replaying (at most MAX_LOGGED_EVENTS); applied once back to live
+    pub held: VecDeque<AgentEvent>,
+    /// Some while scrubbing through history; live event processing is suspended
+    pub playback: Option<Playback>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Playback {
+    pub time: f32,
+    pub playing: bool,
+}
+
+impl EventLog {
+    pub fn record(&mut self, at: f32, event: &AgentEvent) {
+        if self.entries.len() == MAX_LOGGED_EVENTS {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(LoggedEvent {
+            at,
+            event: event.clone(),
+            path: OnceLock::new(),
+        });
+    }
+
+    /// Park an event that arrived mid-replay, dropping the oldest past the cap
+    pub fn hold(&mut self, event: AgentEvent) {
+        if self.held.len() == MAX_LOGGED_EVENTS {
+            self.held.pop_front();
+        }
+        self.held.push_back(event);
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Arrival time of the first logged event
+    fn start(&self) -> Option<f32> {
+        self.entries.front().map(|entry| entry.at)
+    }
+
+    /// Number of entries that had arrived by `time`
+    fn count_at(&self, time: f32) -> usize {
+        self.entries.partition_point(|entry| entry.at <= time)
+    }
+
+    /// Identifies the events replayed up to `time`: the first one's sequence
+    /// number and how many there are
+    fn snapshot_key(&self, time: f32) -> (u64, usize) {
+        (self.dropped, self.count_at(time))
+    }
+
+    /// Replay the log up to `time`
+    fn snapshot_at(&self, time: f32) -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        for entry in self.entries.iter().take(self.count_at(time)) {
+            match &entry.event {
+                AgentEvent::SessionStart { session_id, .. } => {
+                    snapshot.agents.entry(session_id.clone()).or_insert(None);
+                }
+                AgentEvent::ToolUse { session_id, .. } => {
+                    if let Some(path) = entry.path() {
+                        *snapshot.touched.entry(path.clone()).or_insert(0) += 1;
+                        snapshot.agents.insert(session_id.clone(), Some(path.clone()));
+                    }
+                }
+                AgentEvent::SessionEnd { session_id } => {
+                    snapshot.agents.remove(session_id);
+                }
+                AgentEvent::Status { .. } => {}
+            }
+        }
+        snapshot
+    }
+}
+
+/// Scene state reconstructed from the log at some point in time
+#[derive(Default)]
+struct Snapshot {
+    touched: HashMap<PathBuf, usize>,         // file -> touches so far
+    agents: HashMap<String, Option<PathBuf>>, // open session -> file it last touched
+}
+
+/// The event log with the snapshot at the replay time, rebuilt only when the
+/// scrubbed range gains or loses events
+#[derive(SystemParam)]
+pub struct ReplaySnapshot<'w, 's> {
+    log: Res<'w, EventLog>,
+    cached: Local<'s, Option<((u64, usize), Snapshot)>>,
+}
+
+impl ReplaySnapshot<'_, '_> {
+    /// Scene state at the replay time and whether it was just rebuilt, or None when live
+    fn current(&mut self) -> Option<(&Snapshot, bool)> {
+        let Some(playback) = self.log.playback else {
+            *self.cached = None;
+            return None;
+        };
+        let key = self.log.snapshot_key(playback.time);
+        let stale = self.cached.as_ref().is_none_or(|(cached_key, _)| *cached_key != key);
+        if stale {
+            *self.cached = Some((key, self.log.snapshot_at(playback.time)));
+        }
+        self.cached.as_ref().map(|(_, snapshot)| (snapshot, stale))
+    }
+}
+
+/// Run condition: live events are being applied (not scrubbing)
+pub fn is_live(log: Res<EventLog>) -> bool {
+    !log.is_replaying()
+}
+
+/// Sphere shared by every replay ghost
+#[derive(Resource)]
+pub struct ReplayGhostMesh(Handle<Mesh>);
+
+impl FromWorld for ReplayGhostMesh {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        ReplayGhostMesh(meshes.add(Sphere::new(GHOST_RADIUS)))
+    }
+}
+
+// --- Components ---
+
+/// Stand-in spaceship for a session while replaying
+#[derive(Component)]
+pub struct ReplayGhost {
+    pub session_id: String,
+}
+
+#[derive(Component)]
+pub struct ReplayPlayButton;
+
+#[derive(Component)]
+pub struct ReplayPlayLabel;
+
+#[derive(Component)]
+pub struct ReplayLiveButton;
+
+/// Clickable slider track; the cursor position along it picks the replay time
+#[derive(Component)]
+pub struct ReplayTrack;
+
+#[derive(Component)]
+pub struct ReplayTrackFill;
+
+#[derive(Component)]
+pub struct ReplayTimeLabel;
+
+// --- Constants ---
+
+const REPLAY_SPEED: f32 = 4.0; // playback catches up with live at this rate
+const GHOST_RADIUS: f32 = 0.6;
+const GHOST_HOVER: f32 = 1.5; // height above the star a ghost parks at
+const GHOST_FOLLOW_RATE: f32 = 6.0;
+const REPLAY_TOUCHED_BOOST: f32 = 2.5;
+const REPLAY_UNTOUCHED_DIM: f32 = 0.2;
+
+// --- UI ---
+
+pub fn setup_replay_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(20.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                width: Val::Px(600.0),
+                margin: UiRect::horizontal(Val::Auto), // Centered at any window width
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                border: UiRect::all(Val::Px(1.0)),
+                border_radius: BorderRadius::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.03, 0.01, 0.08, 0.92)),
+            BorderColor::all(Color::srgba(0.4, 0.3, 0.7, 0.3)),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(64.0),
+                        padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                        justify_content: JustifyContent::Center,
+                        border_radius: BorderRadius::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.6, 0.45, 0.9)),
+                    ReplayPlayButton,
+                ))
+                .with_child((
+                    Text::new("Play"),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    ReplayPlayLabel,
+                ));
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        flex_grow: 1.0,
+                        height: Val::Px(10.0),
+                        border_radius: BorderRadius::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.05, 0.15, 0.8)),
+                    RelativeCursorPosition::default(),
+                    ReplayTrack,
+                ))
+                .with_child((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        border_radius: BorderRadius::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.6, 0.45, 0.9, 0.8)),
+                    ReplayTrackFill,
+                ));
+
+            parent.spawn((
+                Text::new("LIVE"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                ReplayTimeLabel,
+            ));
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                        border_radius: BorderRadius::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                    ReplayLiveButton,
+                ))
+                .with_child((
+                    Text::new("Live"),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+        });
+}
+
+pub fn handle_replay_controls(
+    mut log: ResMut<EventLog>,
+    time: Res<Time>,
+    play_buttons: Query<&Interaction, (Changed<Interaction>, With<ReplayPlayButton>)>,
+    live_buttons: Query<&Interaction, (Changed<Interaction>, With<ReplayLiveButton>)>,
+    tracks: Query<(&Interaction, &RelativeCursorPosition), With<ReplayTrack>>,
+) {
+    let Some(start) = log.start() else {
+        return; // Nothing recorded yet
+    };
+    let now = time.elapsed_secs();
+
+    if play_buttons.iter().any(|i| *i == Interaction::Pressed) {
+        log.playback = match log.playback {
+            // From live, play the whole session back from the beginning
+            None => Some(Playback {
+                time: start,
+                playing: true,
+            }),
+            Some(playback) => Some(Playback {
+                playing: !playback.playing,
+                ..playback
+            }),
+        };
+    }
+
+    if live_buttons.iter().any(|i| *i == Interaction::Pressed) {
+        log.playback = None;
+    }
+
+    // Held down on the track: scrub to the cursor, pausing playback
+    for (interaction, cursor) in tracks.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(normalized) = cursor.normalized else {
+            continue;
+        };
+        let fraction = (normalized.x + 0.5).clamp(0.0, 1.0);
+        log.playback = Some(Playback {
+            time: start + fraction * (now - start),
+            playing: false,
+        });
+    }
+}
+
+pub fn advance_playback(mut log: ResMut<EventLog>, time: Res<Time>) {
+    let Some(playback) = log.playback.as_mut() else {
+        return;
+    };
+    if !playback.playing {
+        return;
+    }
+    playback.time += time.delta_secs() * REPLAY_SPEED;
+    // Caught up with the present: resume live processing
+    if playback.time >= time.elapsed_secs() {
+        log.playback = None;
+    }
+}
+
+pub fn update_replay_bar(
+    log: Res<EventLog>,
+    time: Res<Time>,
+    mut fills: Query<&mut Node, With<ReplayTrackFill>>,
+    mut time_labels: Query<&mut Text, (With<ReplayTimeLabel>, Without<ReplayPlayLabel>)>,
+    mut play_labels: Query<&mut Text, (With<ReplayPlayLabel>, Without<ReplayTimeLabel>)>,
+) {
+    let now = time.elapsed_secs();
+    let start = log.start().unwrap_or(now);
+    let span = (now - start).max(f32::EPSILON);
+
+    let (fraction, time_text, play_text) = match log.playback {
+        None => (1.0, "LIVE".to_string(), "Play"),
+        Some(playback) => (
+            ((playback.time - start) / span).clamp(0.0, 1.0),
+            format!("-{}", format_clock(now - playback.time)),
+            if playback.playing { "Pause" } else { "Play" },
+        ),
+    };
+
+    for mut node in fills.iter_mut() {
+        node.width = Val::Percent(fraction * 100.0);
+    }
+    for mut text in time_labels.iter_mut() {
+        if text.0 != time_text {
+            text.0 = time_text.clone();
+        }
+    }
+    for mut text in play_labels.iter_mut() {
+        if text.0 != play_text {
+            text.0 = play_text.to_string();
+        }
+    }
+}
+
+fn format_clock(secs: f32) -> String {
+    let secs = secs.max(0.0) as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// --- Scene reconstruction ---
+
+/// Everything drawn for a live agent that has its own visibility
+type LiveAgentParts = Or<(With<Agent>, With<AgentNameplate>, With<AgentActionBubble>, With<AgentStatusRing>)>;
+
+/// Hide the live spaceships while replaying and bring them back afterwards
+pub fn sync_replay_visibility(
+    log: Res<EventLog>,
+    mut live_agents: Query<&mut Visibility, LiveAgentParts>,
+    mut search_state: ResMut<SearchState>,
+    mut was_replaying: Local<bool>,
+) {
+    let replaying = log.is_replaying();
+    if replaying == *was_replaying {
+        return;
+    }
+    *was_replaying = replaying;
+
+    let visibility = if replaying {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for mut vis in live_agents.iter_mut() {
+        vis.set_if_neq(visibility);
+    }
+
+    // Replay dimmed the stars; let the search highlight restore their normal glow
+    if !replaying {
+        search_state.set_changed();
+    }
+}
+
+/// Keep one ghost ship per session open at the replay time, parked at the file it last touched
+pub fn update_replay_ghosts(
+    mut commands: Commands,
+    mut replay: ReplaySnapshot,
+    time: Res<Time>,
+    fs_state: Res<FileSystemState>,
+    ghost_mesh: Res<ReplayGhostMesh>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ghosts: Query<(Entity, &ReplayGhost, &mut Transform)>,
+) {
+    let Some((snapshot, _)) = replay.current() else {
+        for (entity, _, _) in ghosts.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+    let target_of = |path: &Option<PathBuf>| {
+        let (node_idx, _) = fs_state.model.get_node_by_path(path.as_ref()?)?;
+        Some(fs_state.model.position(node_idx) + Vec3::Y * GHOST_HOVER)
+    };
+
+    let follow = (time.delta_secs() * GHOST_FOLLOW_RATE).min(1.0);
+    let mut present = Vec::new();
+    for (entity, ghost, mut transform) in ghosts.iter_mut() {
+        match snapshot.agents.get(&ghost.session_id) {
+            Some(path) => {
+                if let Some(target) = target_of(path) {
+                    transform.translation = transform.translation.lerp(target, follow);
+                }
+                present.push(ghost.session_id.as_str());
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (session_id, path) in &snapshot.agents {
+        if present.contains(&session_id.as_str()) {
+            continue;
+        }
+        let color = generate_agent_color(session_id);
+        let position = target_of(path).unwrap_or(Vec3::new(0.0, 15.0, 0.0));
+        commands.spawn((
+            Mesh3d(ghost_mesh.0.clone()),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: color,
+                emissive: LinearRgba::from(color) * 4.0,
+                unlit: true,
+                ..default()
+            })),
+            Transform::from_translation(position),
+            ReplayGhost {
+                session_id: session_id.clone(),
+            },
+        ));
+    }
+}
+
+/// Light up the files touched by the replay time and dim everything else
+pub fn apply_replay_highlights(
+    mut replay: ReplaySnapshot,
+    fs_state: Res<FileSystemState>,
+    stars: Query<(&FileStar, &MeshMaterial3d<PlanetMaterial>)>,
+    mut planet_materials: ResMut<Assets<PlanetMaterial>>,
+) {
+    // Only redo the materials when the scrubbed range gains or loses events
+    let Some((snapshot, true)) = replay.current() else {
+        return;
+    };
+    for (star, mat_handle) in stars.iter() {
+        let Some(node) = fs_state.model.get_node(star.node_index) else {
+            continue;
+        };
+        let scale = match snapshot.touched.get(&node.path) {
+            Some(&touches) => REPLAY_TOUCHED_BOOST + (touches as f32).ln(),
+            None => REPLAY_UNTOUCHED_DIM,
+        };
+        if let Some(material) = planet_materials.get_mut(&mat_handle.0) {
+            let color = material.base.base_color;
+            material.base.emissive =
+                LinearRgba::from(color) * galaxy::calculate_star_emissive_strength(node) * scale;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_drops_oldest_events_past_the_cap() {
+        let mut log = EventLog::default();
+        let event = AgentEvent::SessionEnd {
+            session_id: "s1".to_string(),
+        };
+        for i in 0..MAX_LOGGED_EVENTS + 5 {
+            log.record(i as f32, &event);
+        }
+        assert_eq!(log.entries.len(), MAX_LOGGED_EVENTS);
+        assert_eq!(log.start(), Some(5.0));
+    }
+
+    #[test]
+    fn snapshot_key_changes_when_the_oldest_events_roll_off() {
+        let mut log = EventLog::default();
+        let event = AgentEvent::SessionEnd {
+            session_id: "s1".to_string(),
+        };
+        for i in 0..MAX_LOGGED_EVENTS {
+            log.record(i as f32, &event);
+        }
+        let time = MAX_LOGGED_EVENTS as f32;
+        let before = log.snapshot_key(time);
+
+        // Same number of events up to `time`, but a different window of them
+        log.record(time - 0.5, &event);
+        assert_eq!(log.snapshot_key(time).1, before.1);
+        assert_ne!(log.snapshot_key(time), before);
+    }
+
+    #[test]
+    fn held_events_are_capped() {
+        let mut log = EventLog::default();
+        for i in 0..MAX_LOGGED_EVENTS + 3 {
+            log.hold(AgentEvent::SessionEnd {
+                session_id: i.to_string(),
+            });
+        }
+        assert_eq!(log.held.len(), MAX_LOGGED_EVENTS);
+        assert_eq!(log.held.front().map(|e| e.session_id()), Some("3"));
+    }
+}
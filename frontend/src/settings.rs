@@ -0,0 +1,365 @@
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+/// Tunables loaded from `assets/settings.ron` at startup
+#[derive(Resource, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub quality: Quality,
+    pub camera: CameraSettings,
+    pub agents: AgentSettings,
+    pub stars: StarSettings,
+    pub bloom: BloomSettings,
+    pub display: DisplaySettings,
+}
+
+/// Detail level for background decoration: how many ambient stars there are,
+/// how far out they spread and whether their colors cycle every frame
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quality {
+    /// Fewer, static-colored ambient stars for low-end machines
+    Low,
+    #[default]
+    Medium,
+    /// More ambient stars spread wider, for big displays
+    High,
+}
+
+impl Quality {
+    pub fn ambient_star_count(self) -> usize {
+        match self {
+            Quality::Low => 40,
+            Quality::Medium => 100,
+            Quality::High => 250,
+        }
+    }
+
+    /// Multiplier on how far the ambient stars spread from the galaxy
+    pub fn ambient_star_spread(self) -> f32 {
+        match self {
+            Quality::Low | Quality::Medium => 1.0,
+            Quality::High => 1.5,
+        }
+    }
+
+    pub fn cycles_ambient_colors(self) -> bool {
+        self != Quality::Low
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Zoom and height speed in Manual and Top mode, world units per second
+    pub move_speed: f32,
+    /// Manual mode rotation, radians per second
+    pub rotate_speed: f32,
+    /// Auto mode orbit, radians per second
+    pub orbit_speed: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub min_height: f32,
+    pub max_height: f32,
+    pub min_top_down_height: f32,
+    pub max_top_down_height: f32,
+}
+
+impl CameraSettings {
+    pub fn clamp_distance(&self, distance: f32) -> f32 {
+        distance.clamp(self.min_distance, self.max_distance)
+    }
+
+    pub fn clamp_height(&self, height: f32) -> f32 {
+        height.clamp(self.min_height, self.max_height)
+    }
+
+    pub fn clamp_top_down_height(&self, height: f32) -> f32 {
+        height.clamp(self.min_top_down_height, self.max_top_down_height)
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            move_speed: 20.0,
+            rotate_speed: 2.0,
+            orbit_speed: 0.1,
+            min_distance: 10.0,
+            max_distance: 100.0,
+            min_height: 5.0,
+            max_height: 50.0,
+            min_top_down_height: 20.0,
+            max_top_down_height: 200.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AgentSettings {
+    /// Seconds an agent idles with nothing queued before it despawns
+    pub idle_timeout: f32,
+    /// Extra idle seconds per tool use in the last minute, so busy sessions
+    /// survive long thinking pauses
+    pub idle_timeout_per_action: f32,
+    /// Bounds on the extended idle timeout; idle_timeout itself must lie within them
+    pub min_idle_timeout: f32,
+    pub max_idle_timeout: f32,
+    /// Seconds per hop with an empty queue; backlogged agents fly faster
+    pub move_duration: f32,
+    /// Spaceship model scale
+    pub scale: f32,
+}
+
+impl Default for AgentSettings {
+    fn default() -> Self {
+        AgentSettings {
+            idle_timeout: 5.0,
+            idle_timeout_per_action: 0.5,
+            min_idle_timeout: 5.0,
+            max_idle_timeout: 30.0,
+            move_duration: 1.2,
+            scale: 100.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StarSettings {
+    /// Multiplier on every star's radius
+    pub scale: f32,
+}
+
+impl Default for StarSettings {
+    fn default() -> Self {
+        StarSettings { scale: 1.0 }
+    }
+}
+
+/// Brightest bloom [ and ] can reach, and the cap on the configured intensity
+pub const BLOOM_MAX: f32 = 0.6;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BloomSettings {
+    /// Starting intensity; [ and ] still adjust it at runtime
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        BloomSettings { intensity: 0.2 }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Sync frames to the monitor's refresh rate
+    pub vsync: bool,
+    /// Frames per second to stay under, e.g. 30 on a wall display; 0 for no cap
+    pub max_fps: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            vsync: true,
+            max_fps: 0.0,
+        }
+    }
+}
+
+fn settings_path() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets").join("settings.ron")
+}
+
+/// Defaults overlaid with whatever settings.ron sets; a missing or broken file
+/// leaves the defaults in place
+pub fn load_settings() -> Settings {
+    let path = settings_path();
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Settings::default();
+    };
+    match ron::from_str::<Settings>(&contents) {
+        Ok(mut settings) => {
+            settings.validate();
+            settings
+        }
+        Err(e) => {
+            eprintln!("[settings] Ignoring unreadable {}: {}", path.display(), e);
+            Settings::default()
+        }
+    }
+}
+
+/// Put `value` back to `default` if it falls outside `range`
+fn check(name: &str, value: &mut f32, default: f32, range: RangeInclusive<f32>) {
+    if !range.contains(value) {
+        eprintln!(
+            "[settings] {} = {} is outside {:?}, using {}",
+            name, value, range, default
+        );
+        *value = default;
+    }
+}
+
+/// Pull `value` into `min..=max` if a min/max pair leaves it outside
+fn check_within(name: &str, value: &mut f32, (min, max): (f32, f32)) {
+    if !(min..=max).contains(value) {
+        let clamped = value.clamp(min, max);
+        eprintln!(
+            "[settings] {} = {} is outside {}..{}, using {}",
+            name, value, min, max, clamped
+        );
+        *value = clamped;
+    }
+}
+
+/// Put a min/max pair back to defaults if it's inverted
+fn check_bounds(name: &str, (min, max): (&mut f32, &mut f32), (default_min, default_max): (f32, f32)) {
+    if *min >= *max {
+        eprintln!(
+            "[settings] {} range {}..{} is empty, using {}..{}",
+            name, min, max, default_min, default_max
+        );
+        *min = default_min;
+        *max = default_max;
+    }
+}
+
+impl Settings {
+    fn validate(&mut self) {
+        let camera = CameraSettings::default();
+        let c = &mut self.camera;
+        check("camera.move_speed", &mut c.move_speed, camera.move_speed, 0.1..=500.0);
+        check("camera.rotate_speed", &mut c.rotate_speed, camera.rotate_speed, 0.01..=20.0);
+        check("camera.orbit_speed", &mut c.orbit_speed, camera.orbit_speed, 0.0..=5.0);
+        check("camera.min_distance", &mut c.min_distance, camera.min_distance, 1.0..=1000.0);
+        check("camera.max_distance", &mut c.max_distance, camera.max_distance, 1.0..=1000.0);
+        check("camera.min_height", &mut c.min_height, camera.min_height, -500.0..=500.0);
+        check("camera.max_height", &mut c.max_height, camera.max_height, -500.0..=500.0);
+        check("camera.min_top_down_height", &mut c.min_top_down_height, camera.min_top_down_height, 1.0..=2000.0);
+        check("camera.max_top_down_height", &mut c.max_top_down_height, camera.max_top_down_height, 1.0..=2000.0);
+        check_bounds(
+            "camera distance",
+            (&mut c.min_distance, &mut c.max_distance),
+            (camera.min_distance, camera.max_distance),
+        );
+        check_bounds(
+            "camera height",
+            (&mut c.min_height, &mut c.max_height),
+            (camera.min_height, camera.max_height),
+        );
+        check_bounds(
+            "camera top-down height",
+            (&mut c.min_top_down_height, &mut c.max_top_down_height),
+            (camera.min_top_down_height, camera.max_top_down_height),
+        );
+
+        let agents = AgentSettings::default();
+        let a = &mut self.agents;
+        check("agents.idle_timeout", &mut a.idle_timeout, agents.idle_timeout, 0.0..=600.0);
+        check("agents.idle_timeout_per_action", &mut a.idle_timeout_per_action, agents.idle_timeout_per_action, 0.0..=60.0);
+        check("agents.min_idle_timeout", &mut a.min_idle_timeout, agents.min_idle_timeout, 0.0..=600.0);
+        check("agents.max_idle_timeout", &mut a.max_idle_timeout, agents.max_idle_timeout, 0.0..=3600.0);
+        check_bounds(
+            "agents idle timeout",
+            (&mut a.min_idle_timeout, &mut a.max_idle_timeout),
+            (agents.min_idle_timeout, agents.max_idle_timeout),
+        );
+        check_within("agents.idle_timeout", &mut a.idle_timeout, (a.min_idle_timeout, a.max_idle_timeout));
+        check("agents.move_duration", &mut a.move_duration, agents.move_duration, 0.05..=30.0);
+        check("agents.scale", &mut a.scale, agents.scale, 1.0..=1000.0);
+
+        let stars = StarSettings::default();
+        check("stars.scale", &mut self.stars.scale, stars.scale, 0.1..=10.0);
+
+        let bloom = BloomSettings::default();
+        check("bloom.intensity", &mut self.bloom.intensity, bloom.intensity, 0.0..=BLOOM_MAX);
+
+        let display = DisplaySettings::default();
+        check("display.max_fps", &mut self.display.max_fps, display.max_fps, 0.0..=1000.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_in_range_are_kept() {
+        let mut settings = Settings::default();
+        settings.camera.move_speed = 42.0;
+        settings.bloom.intensity = BLOOM_MAX;
+        settings.validate();
+        assert_eq!(settings.camera.move_speed, 42.0);
+        assert_eq!(settings.bloom.intensity, BLOOM_MAX);
+    }
+
+    #[test]
+    fn out_of_range_values_fall_back_to_their_defaults() {
+        let defaults = Settings::default();
+        let mut settings = Settings::default();
+        settings.camera.rotate_speed = -1.0;
+        settings.stars.scale = 50.0;
+        settings.bloom.intensity = BLOOM_MAX + 0.1;
+        settings.display.max_fps = f32::NAN;
+        settings.validate();
+        assert_eq!(settings.camera.rotate_speed, defaults.camera.rotate_speed);
+        assert_eq!(settings.stars.scale, defaults.stars.scale);
+        assert_eq!(settings.bloom.intensity, defaults.bloom.intensity);
+        assert_eq!(settings.display.max_fps, defaults.display.max_fps);
+    }
+
+    #[test]
+    fn inverted_bounds_fall_back_to_the_default_pair() {
+        let defaults = Settings::default();
+        let mut settings = Settings::default();
+        settings.camera.min_distance = 80.0;
+        settings.camera.max_distance = 20.0;
+        settings.validate();
+        assert_eq!(settings.camera.min_distance, defaults.camera.min_distance);
+        assert_eq!(settings.camera.max_distance, defaults.camera.max_distance);
+    }
+
+    #[test]
+    fn idle_timeout_is_pulled_within_its_bounds() {
+        let mut settings = Settings::default();
+        settings.agents.min_idle_timeout = 10.0;
+        settings.agents.idle_timeout = 4.0;
+        settings.validate();
+        assert_eq!(settings.agents.idle_timeout, 10.0);
+
+        settings.agents.idle_timeout = 45.0;
+        settings.validate();
+        assert_eq!(settings.agents.idle_timeout, settings.agents.max_idle_timeout);
+    }
+
+    /// settings.ron lists every key commented out; uncommented, they must
+    /// still be the built-in defaults
+    #[test]
+    fn shipped_settings_file_shows_the_defaults() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/settings.ron");
+        let contents = fs::read_to_string(path).unwrap();
+        let uncommented: String = contents
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                match trimmed.strip_prefix("// ") {
+                    Some(entry) if entry.split_once(':').is_some_and(|(key, _)| !key.contains(' ')) => entry,
+                    _ => line,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let shipped: Settings = ron::from_str(&uncommented).unwrap();
+        assert_eq!(format!("{:?}", shipped), format!("{:?}", Settings::default()));
+    }
+}
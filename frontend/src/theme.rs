@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+/// Color scheme picked at launch with --theme: background, accent lights,
+/// background stars and the file palette all change together
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Theme {
+    /// Deep purple space with pink and blue accent lights
+    #[default]
+    Nebula,
+    /// Near-black background with cooler, dimmer lights and richer file colors
+    Dark,
+    /// Grayscale, for neutral slides and print
+    Mono,
+}
+
+impl Theme {
+    pub fn clear_color(self) -> Color {
+        match self {
+            Theme::Nebula => Color::srgb(0.05, 0.02, 0.15), // Deep purple
+            Theme::Dark => Color::srgb(0.01, 0.01, 0.02),
+            Theme::Mono => Color::srgb(0.04, 0.04, 0.04),
+        }
+    }
+
+    /// Accent point lights: top, bottom left, right
+    pub fn accent_colors(self) -> [Color; 3] {
+        match self {
+            Theme::Nebula => [
+                Color::srgb(1.0, 0.3, 0.7), // Pink
+                Color::srgb(0.2, 0.5, 1.0), // Blue
+                Color::srgb(0.6, 0.2, 0.9), // Purple
+            ],
+            Theme::Dark => [
+                Color::srgb(0.3, 0.4, 0.8),
+                Color::srgb(0.2, 0.6, 0.7),
+                Color::srgb(0.4, 0.3, 0.7),
+            ],
+            Theme::Mono => [
+                Color::srgb(0.8, 0.8, 0.8),
+                Color::srgb(0.6, 0.6, 0.6),
+                Color::srgb(0.7, 0.7, 0.7),
+            ],
+        }
+    }
+
+    /// Colors the background stars drift between
+    pub fn ambient_star_colors(self) -> [Color; 4] {
+        match self {
+            Theme::Nebula => [
+                Color::srgb(1.0, 0.4, 0.7), // Pink
+                Color::srgb(0.6, 0.3, 1.0), // Purple
+                Color::srgb(1.0, 0.9, 0.4), // Yellow
+                Color::srgb(0.4, 0.7, 1.0), // Blue
+            ],
+            Theme::Dark => [
+                Color::srgb(0.5, 0.6, 0.9),
+                Color::srgb(0.4, 0.8, 0.8),
+                Color::srgb(0.7, 0.7, 0.8),
+                Color::srgb(0.5, 0.5, 0.9),
+            ],
+            Theme::Mono => [
+                Color::srgb(0.9, 0.9, 0.9),
+                Color::srgb(0.6, 0.6, 0.6),
+                Color::srgb(0.75, 0.75, 0.75),
+                Color::srgb(0.5, 0.5, 0.5),
+            ],
+        }
+    }
+
+    /// Bring a file palette color into this theme
+    pub fn tint(self, color: Color) -> Color {
+        let hsla = Hsla::from(color);
+        match self {
+            Theme::Nebula => color,
+            // Less washed out against the black background
+            Theme::Dark => hsla
+                .with_saturation((hsla.saturation * 1.3).min(1.0))
+                .with_lightness(hsla.lightness * 0.9)
+                .into(),
+            Theme::Mono => hsla.with_saturation(0.0).into(),
+        }
+    }
+}
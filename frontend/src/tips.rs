@@ -0,0 +1,55 @@
+use bevy::asset::io::file::FileAssetReader;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::visit_store::data_dir;
+
+/// Shown when `assets/tips.txt` is missing or has no tips in it
+const DEFAULT_TIPS: &[&str] = &[
+    "Stars represent files & folders in your codebase",
+    "Spaceships are AI agents working on your code",
+    "Hover over a star to see recent activity",
+    "Use the prompt bar at the top to give agents tasks",
+    "Watch the Agent Activity panel to see what they're doing",
+];
+
+fn tips_path() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets").join("tips.txt")
+}
+
+/// Marker file written once the welcome overlay has been dismissed
+fn seen_path() -> PathBuf {
+    data_dir().join("tips_seen")
+}
+
+/// One tip per line of tips.txt; blank lines and `#` comments are skipped
+pub fn load_tips() -> Vec<String> {
+    let tips: Vec<String> = fs::read_to_string(tips_path())
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    if tips.is_empty() {
+        return DEFAULT_TIPS.iter().map(|tip| tip.to_string()).collect();
+    }
+    tips
+}
+
+/// Whether an earlier run already dismissed the welcome overlay
+pub fn has_been_shown() -> bool {
+    seen_path().exists()
+}
+
+/// Remember that the welcome overlay was dismissed so later runs skip it
+pub fn mark_shown() {
+    let path = seen_path();
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, ""));
+    if let Err(e) = result {
+        eprintln!("[tips] Failed to save {}: {}", path.display(), e);
+    }
+}
@@ -0,0 +1,115 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How often agents touched a file: every tool use, and the Write/Edit subset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "SavedCounts")]
+pub struct FileCounts {
+    pub visits: usize,
+    pub edits: usize,
+}
+
+/// A file's entry as stored; older stores held just the visit count
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedCounts {
+    Counts { visits: usize, edits: usize },
+    Visits(usize),
+}
+
+impl From<SavedCounts> for FileCounts {
+    fn from(saved: SavedCounts) -> Self {
+        match saved {
+            SavedCounts::Counts { visits, edits } => FileCounts { visits, edits },
+            SavedCounts::Visits(visits) => FileCounts { visits, edits: 0 },
+        }
+    }
+}
+
+/// Per-file counts for one watched project, keyed by file path
+pub type Visits = HashMap<PathBuf, FileCounts>;
+
+/// The user's home directory, from HOME (or USERPROFILE on Windows)
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// `~/.agent-vis`, or `./.agent-vis` without a home directory
+pub fn data_dir() -> PathBuf {
+    home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".agent-vis")
+}
+
+const VISITS_FILE: &str = "visits.json";
+
+fn store_path(name: &str) -> PathBuf {
+    data_dir().join(name)
+}
+
+pub fn canonical_root(root_path: &Path) -> PathBuf {
+    root_path
+        .canonicalize()
+        .unwrap_or_else(|_| root_path.to_path_buf())
+}
+
+/// Read a whole store (canonical root path -> that project's entry); a missing
+/// or corrupt file reads as empty
+fn read_store<T: DeserializeOwned>(path: &Path) -> HashMap<PathBuf, T> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        eprintln!("[store] Ignoring unreadable {}: {}", path.display(), e);
+        HashMap::new()
+    })
+}
+
+/// What an earlier run saved for this project in the data dir store `name`
+pub fn load_project<T: DeserializeOwned>(name: &str, root_path: &Path) -> Option<T> {
+    read_store(&store_path(name)).remove(&canonical_root(root_path))
+}
+
+/// Replace this project's entry in the store `name`, leaving other projects untouched
+pub fn save_project<T: Serialize + DeserializeOwned>(name: &str, root_path: &Path, value: T) {
+    let path = store_path(name);
+    let mut store: HashMap<PathBuf, T> = read_store(&path);
+    store.insert(canonical_root(root_path), value);
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            let json = serde_json::to_string_pretty(&store).map_err(std::io::Error::other)?;
+            fs::write(&path, json)
+        });
+    if let Err(e) = result {
+        eprintln!("[store] Failed to save {}: {}", path.display(), e);
+    }
+}
+
+/// Visit and edit counts saved by earlier runs on this project
+pub fn load_visits(root_path: &Path) -> Visits {
+    load_project(VISITS_FILE, root_path).unwrap_or_default()
+}
+
+pub fn save_visits(root_path: &Path, visits: &Visits) {
+    save_project(VISITS_FILE, root_path, visits.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_from_before_edit_counts_still_load() {
+        let store: HashMap<PathBuf, Visits> =
+            serde_json::from_str(r#"{"/old": {"/old/a.rs": 3}, "/new": {"/new/b.rs": {"visits": 4, "edits": 1}}}"#)
+                .unwrap();
+        assert_eq!(store[Path::new("/old")][Path::new("/old/a.rs")], FileCounts { visits: 3, edits: 0 });
+        assert_eq!(store[Path::new("/new")][Path::new("/new/b.rs")], FileCounts { visits: 4, edits: 1 });
+    }
+}
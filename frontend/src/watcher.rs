@@ -1,21 +1,56 @@
 // hello world
-use crossbeam_channel::{unbounded, Receiver};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use notify::event::{ModifyKind, RenameMode};
 use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Events for the same path arriving within this window are collapsed into one
+pub const DEBOUNCE_WINDOW: Duration = Duration::from_millis(200);
+/// A path that never goes quiet (e.g. a log being appended to) is still
+/// forwarded this long after its first event
+pub const DEBOUNCE_MAX_WAIT: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone)]
 pub enum FileSystemEvent {
     Created(PathBuf, bool),  // path, is_dir
     Deleted(PathBuf),
     Modified(PathBuf),
+    Renamed(PathBuf, PathBuf), // from, to
 }
 
 pub struct FileWatcherHandle {
-    _watcher: notify::RecommendedWatcher,
+    _watcher: Option<notify::RecommendedWatcher>, // None for a detached handle
+    injector: Sender<FileSystemEvent>,
+}
+
+impl FileWatcherHandle {
+    /// Feeds events into the same debounced stream as the real watcher,
+    /// for file changes reported from elsewhere (the mock server's churn)
+    pub fn injector(&self) -> Sender<FileSystemEvent> {
+        self.injector.clone()
+    }
+
+    /// A handle with no OS watcher behind it; only injected events come out
+    #[cfg(test)]
+    pub fn detached() -> (Receiver<FileSystemEvent>, FileWatcherHandle) {
+        let (injector, rx) = unbounded::<FileSystemEvent>();
+        let handle = FileWatcherHandle {
+            _watcher: None,
+            injector,
+        };
+        (rx, handle)
+    }
 }
 
 pub fn start_file_watcher(_watch_path: PathBuf) -> (Receiver<FileSystemEvent>, FileWatcherHandle) {
-    let (tx, rx) = unbounded::<FileSystemEvent>();
+    let (tx, raw_rx) = unbounded::<FileSystemEvent>();
+    let (debounced_tx, rx) = unbounded::<FileSystemEvent>();
+
+    thread::spawn(move || run_debouncer(raw_rx, debounced_tx));
+    let injector = tx.clone();
 
     let watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
         match res {
@@ -32,6 +67,9 @@ pub fn start_file_watcher(_watch_path: PathBuf) -> (Receiver<FileSystemEvent>, F
                             let _ = tx.send(FileSystemEvent::Deleted(path));
                         }
                     }
+                    EventKind::Modify(ModifyKind::Name(mode)) => {
+                        send_rename(&tx, mode, event.paths);
+                    }
                     EventKind::Modify(_) => {
                         for path in event.paths {
                             let _ = tx.send(FileSystemEvent::Modified(path));
@@ -45,7 +83,10 @@ pub fn start_file_watcher(_watch_path: PathBuf) -> (Receiver<FileSystemEvent>, F
     })
     .expect("Failed to create file watcher");
 
-    let handle = FileWatcherHandle { _watcher: watcher };
+    let handle = FileWatcherHandle {
+        _watcher: Some(watcher),
+        injector,
+    };
 
     (rx, handle)
 }
@@ -54,9 +95,165 @@ pub fn watch_directory(
     mut watcher: FileWatcherHandle,
     watch_path: PathBuf,
 ) -> FileWatcherHandle {
+    if let Some(inner) = watcher._watcher.as_mut() {
+        inner
+            .watch(&watch_path, RecursiveMode::Recursive)
+            .expect("Failed to watch directory");
+    }
     watcher
-        ._watcher
-        .watch(&watch_path, RecursiveMode::Recursive)
-        .expect("Failed to watch directory");
-    watcher
+}
+
+/// Turn a notify rename into Renamed when both ends are known, otherwise
+/// into the equivalent Deleted/Created so the model still stays in sync.
+fn send_rename(tx: &Sender<FileSystemEvent>, mode: RenameMode, paths: Vec<PathBuf>) {
+    match (mode, paths.as_slice()) {
+        (RenameMode::Both, [from, to]) => {
+            let _ = tx.send(FileSystemEvent::Renamed(from.clone(), to.clone()));
+        }
+        (RenameMode::From, _) => {
+            for path in paths {
+                let _ = tx.send(FileSystemEvent::Deleted(path));
+            }
+        }
+        (RenameMode::To, _) => {
+            for path in paths {
+                let is_dir = path.is_dir();
+                let _ = tx.send(FileSystemEvent::Created(path, is_dir));
+            }
+        }
+        _ => {
+            // Unknown half of a rename: whatever still exists was the destination
+            for path in paths {
+                if path.exists() {
+                    let is_dir = path.is_dir();
+                    let _ = tx.send(FileSystemEvent::Created(path, is_dir));
+                } else {
+                    let _ = tx.send(FileSystemEvent::Deleted(path));
+                }
+            }
+        }
+    }
+}
+
+/// Merge a new event for a path into whatever is already pending for it.
+/// Returns None when the two cancel out (created then deleted).
+fn coalesce(pending: FileSystemEvent, next: FileSystemEvent) -> Option<FileSystemEvent> {
+    use FileSystemEvent::*;
+    match (pending, next) {
+        // Save-by-replace (delete + create) is really just an edit
+        (Deleted(path), Created(..)) => Some(Modified(path)),
+        // Still new as far as the model is concerned
+        (Created(path, is_dir), Modified(_)) => Some(Created(path, is_dir)),
+        (Created(..), Deleted(_)) => None,
+        (_, next) => Some(next),
+    }
+}
+
+/// Whether a path pending since `first` and last touched at `last` should go out:
+/// quiet for DEBOUNCE_WINDOW, or held for DEBOUNCE_MAX_WAIT already
+fn debounce_due(first: Instant, last: Instant, now: Instant) -> bool {
+    now.duration_since(last) >= DEBOUNCE_WINDOW || now.duration_since(first) >= DEBOUNCE_MAX_WAIT
+}
+
+/// Sits between notify and the app: buffers events per path and only forwards
+/// them once the path has been quiet for DEBOUNCE_WINDOW (or busy for DEBOUNCE_MAX_WAIT).
+fn run_debouncer(raw_rx: Receiver<FileSystemEvent>, tx: Sender<FileSystemEvent>) {
+    // path -> (event, first seen, last seen); first seen keeps output ordered
+    let mut pending: HashMap<PathBuf, (FileSystemEvent, Instant, Instant)> = HashMap::new();
+
+    let flush = |pending: &mut HashMap<PathBuf, (FileSystemEvent, Instant, Instant)>,
+                 only_due: bool| {
+        let now = Instant::now();
+        let mut ready: Vec<(PathBuf, Instant)> = pending
+            .iter()
+            .filter(|(_, (_, first, last))| !only_due || debounce_due(*first, *last, now))
+            .map(|(path, (_, first, _))| (path.clone(), *first))
+            .collect();
+        ready.sort_by_key(|(_, first)| *first);
+        for (path, _) in ready {
+            if let Some((event, _, _)) = pending.remove(&path) {
+                let _ = tx.send(event);
+            }
+        }
+    };
+
+    loop {
+        let (path, event) = match raw_rx.recv_timeout(DEBOUNCE_WINDOW / 4) {
+            Ok(FileSystemEvent::Renamed(from, to)) => {
+                // Renames span two paths; flush first so nothing overtakes them
+                flush(&mut pending, false);
+                let _ = tx.send(FileSystemEvent::Renamed(from, to));
+                continue;
+            }
+            Ok(FileSystemEvent::Created(path, is_dir)) => (path.clone(), FileSystemEvent::Created(path, is_dir)),
+            Ok(FileSystemEvent::Deleted(path)) => (path.clone(), FileSystemEvent::Deleted(path)),
+            Ok(FileSystemEvent::Modified(path)) => (path.clone(), FileSystemEvent::Modified(path)),
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
+                flush(&mut pending, true);
+                continue;
+            }
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
+                flush(&mut pending, false);
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        match pending.remove(&path) {
+            Some((previous, first, _)) => {
+                if let Some(merged) = coalesce(previous, event) {
+                    pending.insert(path, (merged, first, now));
+                }
+            }
+            None => {
+                pending.insert(path, (event, now, now));
+            }
+        }
+        flush(&mut pending, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path() -> PathBuf {
+        PathBuf::from("/repo/a.rs")
+    }
+
+    #[test]
+    fn delete_then_create_is_an_edit() {
+        let merged = coalesce(FileSystemEvent::Deleted(path()), FileSystemEvent::Created(path(), false));
+        assert!(matches!(merged, Some(FileSystemEvent::Modified(p)) if p == path()));
+    }
+
+    #[test]
+    fn created_stays_created_through_edits() {
+        let merged = coalesce(FileSystemEvent::Created(path(), false), FileSystemEvent::Modified(path()));
+        assert!(matches!(merged, Some(FileSystemEvent::Created(p, false)) if p == path()));
+    }
+
+    #[test]
+    fn create_then_delete_cancels_out() {
+        assert!(coalesce(FileSystemEvent::Created(path(), false), FileSystemEvent::Deleted(path())).is_none());
+    }
+
+    #[test]
+    fn otherwise_the_latest_event_wins() {
+        let merged = coalesce(FileSystemEvent::Modified(path()), FileSystemEvent::Deleted(path()));
+        assert!(matches!(merged, Some(FileSystemEvent::Deleted(p)) if p == path()));
+    }
+
+    #[test]
+    fn a_path_that_never_goes_quiet_is_flushed_after_the_max_wait() {
+        let first = Instant::now();
+        let busy = |elapsed: Duration| {
+            let now = first + elapsed;
+            // Touched again just before every check
+            debounce_due(first, now - DEBOUNCE_WINDOW / 2, now)
+        };
+        assert!(!busy(DEBOUNCE_MAX_WAIT / 2));
+        assert!(busy(DEBOUNCE_MAX_WAIT));
+        assert!(debounce_due(first, first, first + DEBOUNCE_WINDOW));
+    }
 }
@@ -1,9 +1,23 @@
 // hello world
-use crossbeam_channel::{unbounded, Receiver};
+use crossbeam_channel::{unbounded, Receiver, Sender};
 use serde::Deserialize;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
 use std::thread;
-use std::time::Duration;
-use tungstenite::connect;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{connect, Message};
+
+use crate::watcher::FileSystemEvent;
+
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often an otherwise quiet connection is pinged
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// Silence (not even a pong) for this long means the connection is dead
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(25);
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type")]
@@ -24,28 +38,197 @@ pub enum AgentEvent {
         #[serde(default)]
         timestamp: Option<String>,
     },
+    /// A phase between tool uses ("thinking", "waiting for user"); the agent
+    /// shows it without flying anywhere
+    #[serde(rename = "status")]
+    Status { session_id: String, status: String },
+    #[serde(rename = "session_end")]
+    SessionEnd { session_id: String },
+}
+
+impl AgentEvent {
+    pub fn session_id(&self) -> &str {
+        match self {
+            AgentEvent::SessionStart { session_id, .. }
+            | AgentEvent::ToolUse { session_id, .. }
+            | AgentEvent::Status { session_id, .. }
+            | AgentEvent::SessionEnd { session_id } => session_id,
+        }
+    }
+}
+
+/// Positional array form sent when connecting with `?format=compact`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CompactEvent {
+    ToolUse(String, String, String, String, Option<String>, Option<String>),
+    SessionStart(String, String, String, String),
+    Status(String, String, String),
+    SessionEnd(String, String),
+}
+
+/// File changes announced by the server (the mock's `--mock-churn`) rather than seen on disk
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum RemoteFileEvent {
+    #[serde(rename = "file_created")]
+    Created {
+        path: String,
+        #[serde(default)]
+        is_dir: bool,
+    },
+    #[serde(rename = "file_deleted")]
+    Deleted { path: String },
+}
+
+/// Decode a server-announced file change; these are always sent in the verbose format
+fn decode_file_event(text: &str) -> Option<FileSystemEvent> {
+    match serde_json::from_str::<RemoteFileEvent>(text).ok()? {
+        RemoteFileEvent::Created { path, is_dir } => Some(FileSystemEvent::Created(path.into(), is_dir)),
+        RemoteFileEvent::Deleted { path } => Some(FileSystemEvent::Deleted(path.into())),
+    }
+}
+
+/// The event with its path canonicalized, or None when it falls outside the
+/// watched `root` (e.g. a server running in another directory)
+fn confine_to_root(event: FileSystemEvent, root: &Path) -> Option<FileSystemEvent> {
+    match event {
+        FileSystemEvent::Created(path, is_dir) => Some(FileSystemEvent::Created(path_under_root(path, root)?, is_dir)),
+        FileSystemEvent::Deleted(path) => Some(FileSystemEvent::Deleted(path_under_root(path, root)?)),
+        other => Some(other),
+    }
+}
+
+/// Canonical form of `path` if it lies under `root`. A deleted or not yet
+/// created path can't be canonicalized itself, so its parent is used instead.
+fn path_under_root(path: PathBuf, root: &Path) -> Option<PathBuf> {
+    let path = if path.is_relative() { root.join(path) } else { path };
+    let canonical = path
+        .canonicalize()
+        .ok()
+        .or_else(|| Some(path.parent()?.canonicalize().ok()?.join(path.file_name()?)))
+        .unwrap_or(path);
+    canonical.starts_with(root).then_some(canonical)
+}
+
+/// Decode a server message in either the verbose or the compact format
+pub fn decode_event(text: &str) -> Result<AgentEvent, serde_json::Error> {
+    if !text.trim_start().starts_with('[') {
+        return serde_json::from_str::<AgentEvent>(text);
+    }
+
+    match serde_json::from_str::<CompactEvent>(text)? {
+        CompactEvent::ToolUse(tag, session_id, tool_name, file_path, reason, timestamp) if tag == "t" => {
+            Ok(AgentEvent::ToolUse {
+                session_id,
+                tool_name,
+                file_path,
+                reason,
+                timestamp,
+            })
+        }
+        CompactEvent::SessionStart(tag, session_id, cwd, model) if tag == "s" => {
+            Ok(AgentEvent::SessionStart {
+                session_id,
+                cwd,
+                model,
+            })
+        }
+        CompactEvent::Status(tag, session_id, status) if tag == "p" => {
+            Ok(AgentEvent::Status { session_id, status })
+        }
+        CompactEvent::SessionEnd(tag, session_id) if tag == "e" => {
+            Ok(AgentEvent::SessionEnd { session_id })
+        }
+        _ => Err(serde::de::Error::custom("unknown compact event tag")),
+    }
+}
+
+/// Up to 10% extra delay so many clients don't reconnect in lockstep
+fn with_jitter(delay: Duration) -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let fraction = (nanos % 1000) as f64 / 10_000.0; // 0.0-0.1
+    delay + delay.mul_f64(fraction)
+}
+
+/// Connection state reported by the WebSocket thread for the on-screen indicator
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionStatus {
+    /// First attempt, nothing has gone wrong yet
+    Connecting,
+    Connected,
+    /// The attempt failed or the connection dropped; a retry follows after a backoff
+    Disconnected { error: String },
+    /// Retrying, still carrying why the last connection failed
+    Reconnecting { last_error: String },
 }
 
 pub struct WsClientHandle {
     pub _thread: thread::JoinHandle<()>,
 }
 
-pub fn start_ws_client() -> (Receiver<AgentEvent>, WsClientHandle) {
+pub const DEFAULT_WS_URL: &str = "ws://127.0.0.1:8080/ws";
+
+/// `fs_events` receives file changes the server announces under `root`, alongside the file
+/// watcher's own; `status` hears every connect, disconnect and retry
+pub fn start_ws_client(
+    url: String,
+    root: PathBuf,
+    fs_events: Sender<FileSystemEvent>,
+    status: Sender<ConnectionStatus>,
+) -> (Receiver<AgentEvent>, WsClientHandle) {
     let (tx, rx) = unbounded::<AgentEvent>();
 
     let handle = thread::spawn(move || {
-        let url = "ws://127.0.0.1:8080/ws";
+        let mut reconnect_delay = RECONNECT_BASE_DELAY;
+        let mut last_error: Option<String> = None;
         loop {
             println!("[ws_client] Connecting to {}...", url);
-            match connect(url) {
+            let _ = status.send(match last_error.take() {
+                Some(last_error) => ConnectionStatus::Reconnecting { last_error },
+                None => ConnectionStatus::Connecting,
+            });
+            let error = match connect(url.as_str()) {
                 Ok((mut socket, _response)) => {
                     println!("[ws_client] Connected!");
+                    let _ = status.send(ConnectionStatus::Connected);
+                    reconnect_delay = RECONNECT_BASE_DELAY;
+                    // Wake up from reads periodically to send heartbeats
+                    if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+                        let _ = stream.set_read_timeout(Some(HEARTBEAT_INTERVAL));
+                    }
+                    let mut last_heard = Instant::now();
+                    let mut last_ping = Instant::now();
                     loop {
+                        if last_heard.elapsed() >= HEARTBEAT_TIMEOUT {
+                            eprintln!("[ws_client] No reply from server in {}s, reconnecting", HEARTBEAT_TIMEOUT.as_secs());
+                            break format!("no reply from server in {}s", HEARTBEAT_TIMEOUT.as_secs());
+                        }
+                        if last_ping.elapsed() >= HEARTBEAT_INTERVAL {
+                            last_ping = Instant::now();
+                            if let Err(e) = socket.send(Message::Ping(Default::default())) {
+                                eprintln!("[ws_client] Ping failed: {}", e);
+                                break format!("ping failed: {}", e);
+                            }
+                        }
                         match socket.read() {
                             Ok(msg) => {
+                                last_heard = Instant::now();
                                 if msg.is_text() {
                                     let text = msg.into_text().unwrap_or_default();
-                                    match serde_json::from_str::<AgentEvent>(&text) {
+                                    if let Some(fs_event) = decode_file_event(&text) {
+                                        match confine_to_root(fs_event, &root) {
+                                            Some(fs_event) => {
+                                                let _ = fs_events.send(fs_event);
+                                            }
+                                            None => eprintln!("[ws_client] Ignoring file event outside the watched root"),
+                                        }
+                                        continue;
+                                    }
+                                    match decode_event(&text) {
                                         Ok(event) => {
                                             let _ = tx.send(event);
                                         }
@@ -55,22 +238,161 @@ pub fn start_ws_client() -> (Receiver<AgentEvent>, WsClientHandle) {
                                     }
                                 }
                             }
+                            // Read timed out: loop around to check the heartbeat
+                            Err(tungstenite::Error::Io(e))
+                                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
                             Err(e) => {
                                 eprintln!("[ws_client] Read error: {}", e);
-                                break;
+                                break e.to_string();
                             }
                         }
                     }
                 }
                 Err(e) => {
                     eprintln!("[ws_client] Connection failed: {}", e);
+                    e.to_string()
                 }
-            }
-            println!("[ws_client] Reconnecting in 2s...");
-            thread::sleep(Duration::from_secs(2));
+            };
+            let _ = status.send(ConnectionStatus::Disconnected { error: error.clone() });
+            last_error = Some(error);
+            let delay = with_jitter(reconnect_delay);
+            println!("[ws_client] Reconnecting in {:.1}s...", delay.as_secs_f32());
+            thread::sleep(delay);
+            reconnect_delay = (reconnect_delay * 2).min(RECONNECT_MAX_DELAY);
         }
     });
 
     let ws_handle = WsClientHandle { _thread: handle };
     (rx, ws_handle)
 }
+
+/// Reply to a /launch request, matched back to the optimistically spawned agent
+#[derive(Debug, Clone)]
+pub struct LaunchResult {
+    pub local_session_id: String,
+    pub session_id: Option<String>, // None when the server couldn't be reached
+}
+
+#[derive(Deserialize)]
+struct LaunchResponse {
+    session_id: String,
+}
+
+/// `host:port` of the server behind a WebSocket URL like `ws://host:port/ws?format=compact`
+pub fn server_address(ws_url: &str) -> String {
+    let without_scheme = ws_url.split_once("://").map_or(ws_url, |(_, rest)| rest);
+    let authority = without_scheme.split(['/', '?']).next().unwrap_or(without_scheme);
+    if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    }
+}
+
+/// Ask the server to start a session for `task` on a background thread; the
+/// outcome is sent to `tx` tagged with the local placeholder id.
+pub fn launch_session(
+    address: String,
+    task: String,
+    cwd: String,
+    local_session_id: String,
+    tx: Sender<LaunchResult>,
+) {
+    thread::spawn(move || {
+        let body = serde_json::json!({ "task": task, "cwd": cwd }).to_string();
+        let session_id = match post_json(&address, "/launch", &body) {
+            Ok(reply) => match serde_json::from_str::<LaunchResponse>(&reply) {
+                Ok(response) => Some(response.session_id),
+                Err(e) => {
+                    eprintln!("[ws_client] Bad launch reply: {}", e);
+                    None
+                }
+            },
+            Err(e) => {
+                eprintln!("[ws_client] Launch failed: {}", e);
+                None
+            }
+        };
+        let _ = tx.send(LaunchResult {
+            local_session_id,
+            session_id,
+        });
+    });
+}
+
+/// Minimal blocking HTTP/1.1 POST; returns the response body on a 2xx status
+fn post_json(address: &str, path: &str, body: &str) -> Result<String, String> {
+    let mut stream = TcpStream::connect(address).map_err(|e| e.to_string())?;
+    let _ = stream.set_read_timeout(Some(LAUNCH_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(LAUNCH_TIMEOUT));
+
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        address,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).map_err(|e| e.to_string())?;
+
+    let (head, reply) = response.split_once("\r\n\r\n").ok_or("malformed response")?;
+    let status = head.lines().next().unwrap_or_default();
+    if !status.split_whitespace().nth(1).is_some_and(|code| code.starts_with('2')) {
+        return Err(format!("server replied {}", status));
+    }
+    Ok(reply.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_use_timestamp(text: &str) -> Option<String> {
+        match decode_event(text).expect("tool_use should decode") {
+            AgentEvent::ToolUse { timestamp, .. } => timestamp,
+            other => panic!("expected a tool_use, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tool_use_keeps_server_timestamp() {
+        let text = r#"{"type":"tool_use","session_id":"s1","tool_name":"Read","file_path":"/repo/src/main.rs","reason":"Reading main.rs","timestamp":"2025-01-15T10:30:45.123456+00:00"}"#;
+        assert_eq!(
+            tool_use_timestamp(text).as_deref(),
+            Some("2025-01-15T10:30:45.123456+00:00")
+        );
+    }
+
+    #[test]
+    fn tool_use_without_timestamp_defaults_to_none() {
+        let text = r#"{"type":"tool_use","session_id":"s1","tool_name":"Read","file_path":"/repo/src/main.rs"}"#;
+        assert_eq!(tool_use_timestamp(text), None);
+    }
+
+    #[test]
+    fn compact_tool_use_keeps_timestamp() {
+        let with = r#"["t","s1","Edit","/repo/a.rs",null,"2025-01-15T10:30:45+00:00"]"#;
+        let without = r#"["t","s1","Edit","/repo/a.rs",null,null]"#;
+        assert_eq!(tool_use_timestamp(with).as_deref(), Some("2025-01-15T10:30:45+00:00"));
+        assert_eq!(tool_use_timestamp(without), None);
+    }
+
+    #[test]
+    fn file_created_outside_root_is_ignored() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path().canonicalize().unwrap();
+
+        let outside = decode_file_event(r#"{"type":"file_created","path":"/elsewhere/repo/new.rs"}"#).unwrap();
+        assert!(confine_to_root(outside, &root).is_none());
+
+        let inside = format!(r#"{{"type":"file_created","path":"{}"}}"#, root.join("new.rs").display());
+        let inside = decode_file_event(&inside).unwrap();
+        assert!(matches!(
+            confine_to_root(inside, &root),
+            Some(FileSystemEvent::Created(path, false)) if path == root.join("new.rs")
+        ));
+    }
+}
@@ -22,11 +22,46 @@ case "$EVENT_NAME" in
 		"http://127.0.0.1:8080/session-start" \
 		>>"$(dirname "$0")/curl_debug.log" 2>&1
 	;;
+"SessionEnd")
+	PAYLOAD="{\"session_id\":\"$SESSION_ID\"}"
+	curl -s -X POST \
+		-H "Content-Type: application/json" \
+		-d "$PAYLOAD" \
+		"http://127.0.0.1:8080/session-end" \
+		>>"$(dirname "$0")/curl_debug.log" 2>&1
+	;;
+"UserPromptSubmit" | "Stop")
+	# Phases between tool uses: the ship shows them without flying anywhere
+	if [ "$EVENT_NAME" = "Stop" ]; then
+		STATUS="waiting for user"
+	else
+		STATUS="thinking"
+	fi
+	PAYLOAD="{\"session_id\":\"$SESSION_ID\",\"status\":\"$STATUS\"}"
+	curl -s -X POST \
+		-H "Content-Type: application/json" \
+		-d "$PAYLOAD" \
+		"http://127.0.0.1:8080/status" \
+		>>"$(dirname "$0")/curl_debug.log" 2>&1
+	;;
 "PreToolUse")
 	TOOL_NAME=$(echo "$INPUT" | grep -o '"tool_name"[^,}]*' | cut -d'"' -f4)
 	# Try different patterns for file_path
 	FILE_PATH=$(echo "$INPUT" | grep -o '"file_path"[^,}]*' | head -1 | cut -d'"' -f4)
 
+	# Grep/Glob search a directory; Bash runs from the session's cwd
+	case "$TOOL_NAME" in
+	"Grep" | "Glob")
+		FILE_PATH=$(echo "$INPUT" | grep -o '"path"[^,}]*' | head -1 | cut -d'"' -f4)
+		if [ -z "$FILE_PATH" ]; then
+			FILE_PATH=$(echo "$INPUT" | grep -o '"cwd"[^,}]*' | cut -d'"' -f4)
+		fi
+		;;
+	"Bash")
+		FILE_PATH=$(echo "$INPUT" | grep -o '"cwd"[^,}]*' | cut -d'"' -f4)
+		;;
+	esac
+
 	PAYLOAD="{\"session_id\":\"$SESSION_ID\",\"tool_name\":\"$TOOL_NAME\",\"tool_input\":{\"file_path\":\"$FILE_PATH\"}}"
 
 	case "$TOOL_NAME" in
@@ -51,6 +86,27 @@ case "$EVENT_NAME" in
 			"http://127.0.0.1:8080/edit" \
 			>>"$(dirname "$0")/curl_debug.log" 2>&1
 		;;
+	"Grep")
+		curl -s -X POST \
+			-H "Content-Type: application/json" \
+			-d "$PAYLOAD" \
+			"http://127.0.0.1:8080/grep" \
+			>>"$(dirname "$0")/curl_debug.log" 2>&1
+		;;
+	"Glob")
+		curl -s -X POST \
+			-H "Content-Type: application/json" \
+			-d "$PAYLOAD" \
+			"http://127.0.0.1:8080/glob" \
+			>>"$(dirname "$0")/curl_debug.log" 2>&1
+		;;
+	"Bash")
+		curl -s -X POST \
+			-H "Content-Type: application/json" \
+			-d "$PAYLOAD" \
+			"http://127.0.0.1:8080/bash" \
+			>>"$(dirname "$0")/curl_debug.log" 2>&1
+		;;
 	esac
 	;;
 esac
@@ -6,14 +6,18 @@ use rand::Rng;
 use rand::SeedableRng;
 use rand::rngs::StdRng;
 use rand::seq::IndexedRandom;
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::broadcast;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, mpsc, watch};
 use warp::ws::Message;
-use warp::{Filter, http::StatusCode};
+use warp::{Filter, Reply, http::StatusCode};
 
 #[derive(Parser)]
 #[command(about = "Agent visualization server")]
@@ -22,6 +26,117 @@ struct Args {
     /// Provide a directory path to use real files from that path (respects .gitignore).
     #[arg(long)]
     mock: Option<PathBuf>,
+
+    /// With --mock, also announce synthetic files being created and later deleted,
+    /// exercising the visualizer's add/remove path without touching the disk
+    #[arg(long, requires = "mock")]
+    mock_churn: bool,
+
+    /// Append every broadcast event, with the time it was sent, to this JSONL file
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Re-broadcast a file written by --record, keeping the original spacing between events
+    #[arg(long, conflicts_with = "mock")]
+    replay: Option<PathBuf>,
+
+    /// With --replay, only re-broadcast this session's events (repeat for several sessions)
+    #[arg(long = "replay-session", value_name = "SESSION_ID", requires = "replay")]
+    replay_sessions: Vec<String>,
+
+    /// Relative weight of Read in mock sessions (Grep, Glob and Bash weigh 1)
+    #[arg(long, default_value_t = 1.0)]
+    read_weight: f32,
+
+    /// Relative weight of Write in mock sessions
+    #[arg(long, default_value_t = 1.0)]
+    write_weight: f32,
+
+    /// Relative weight of Edit in mock sessions
+    #[arg(long, default_value_t = 1.0)]
+    edit_weight: f32,
+
+    /// Fewest tool uses in a mock session
+    #[arg(long, default_value_t = 4)]
+    min_actions: u32,
+
+    /// Most tool uses in a mock session
+    #[arg(long, default_value_t = 12)]
+    max_actions: u32,
+
+    /// Most POSTs accepted per session per second (all /launch requests share one
+    /// budget); the rest are answered with 429
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    rate_limit: u32,
+
+    /// Directory /launch may start sessions in, along with anything under it (repeat
+    /// for several). The --mock directory is always allowed; with neither, /launch is off.
+    #[arg(long = "launch-root", value_name = "DIR")]
+    launch_roots: Vec<PathBuf>,
+
+    /// Model reported for sessions started through /launch
+    #[arg(long, default_value = DEFAULT_LAUNCH_MODEL)]
+    launch_model: String,
+}
+
+/// Tool mix and session length for mock (and launched) sessions
+#[derive(Clone, Copy, Debug)]
+struct MockProfile {
+    read_weight: f32,
+    write_weight: f32,
+    edit_weight: f32,
+    min_actions: u32,
+    max_actions: u32,
+}
+
+/// Extra weight on Read during the first third of a session, where agents mostly explore.
+/// With all weights at 1 this makes 7 in 8 early actions reads.
+const EARLY_READ_BOOST: f32 = 35.0;
+
+const MOCK_TOOLS: [&str; 6] = ["Read", "Write", "Edit", "Grep", "Glob", "Bash"];
+
+impl MockProfile {
+    fn from_args(args: &Args) -> Result<Self, String> {
+        for (flag, weight) in [
+            ("--read-weight", args.read_weight),
+            ("--write-weight", args.write_weight),
+            ("--edit-weight", args.edit_weight),
+        ] {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(format!("{} must be a non-negative number, got {}", flag, weight));
+            }
+        }
+        if args.min_actions == 0 || args.min_actions > args.max_actions {
+            return Err(format!(
+                "need 1 <= --min-actions <= --max-actions, got {} and {}",
+                args.min_actions, args.max_actions
+            ));
+        }
+        Ok(MockProfile {
+            read_weight: args.read_weight,
+            write_weight: args.write_weight,
+            edit_weight: args.edit_weight,
+            min_actions: args.min_actions,
+            max_actions: args.max_actions,
+        })
+    }
+
+    fn tool_weight(&self, tool: &str, early: bool) -> f32 {
+        match tool {
+            "Read" if early => self.read_weight * EARLY_READ_BOOST,
+            "Read" => self.read_weight,
+            "Write" => self.write_weight,
+            "Edit" => self.edit_weight,
+            _ => 1.0,
+        }
+    }
+
+    fn choose_tool(&self, rng: &mut StdRng, early: bool) -> &'static str {
+        MOCK_TOOLS
+            .choose_weighted(rng, |tool| self.tool_weight(tool, early))
+            .copied()
+            .unwrap_or("Read")
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -31,9 +146,61 @@ struct SessionStartPayload {
     model: String,
 }
 
+#[derive(Deserialize, Debug)]
+struct SessionEndPayload {
+    session_id: String,
+}
+
+/// Body of POST /status: what the agent is doing between tool uses
+#[derive(Deserialize, Debug)]
+struct StatusPayload {
+    session_id: String,
+    /// Short phase name such as "thinking" or "waiting for user"
+    status: String,
+}
+
+/// Body of POST /launch, sent by the frontend's prompt bar
+#[derive(Deserialize, Debug)]
+struct LaunchPayload {
+    task: String,
+    /// Project root the launched session should work in
+    cwd: String,
+}
+
+/// Pacing for --mock-churn: a create or delete every few seconds, each synthetic
+/// file living for a while, with only a handful around at once
+const CHURN_INTERVAL_MS: std::ops::Range<u64> = 3000..6000;
+const CHURN_LIFETIME_MS: std::ops::Range<u64> = 8000..20000;
+const MAX_CHURN_FILES: usize = 5;
+
+/// How long shutdown waits for WebSocket clients to receive their close frame
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+/// Default for --launch-model
+const DEFAULT_LAUNCH_MODEL: &str = "claude-sonnet-4-5-20250929";
+
+/// Most files a launched session picks from; the walk stops here on huge trees
+const MAX_LAUNCH_FILES: usize = 10_000;
+
+/// Head start the frontend gets to learn the new session id before its events arrive
+const LAUNCH_START_DELAY_MS: u64 = 750;
+
 #[derive(Deserialize, Debug)]
 struct ToolInput {
+    #[serde(default)]
     file_path: String,
+    /// Search root for Grep/Glob, used when there is no file_path
+    #[serde(default)]
+    path: Option<String>,
+}
+
+impl ToolInput {
+    fn target_path(&self) -> &str {
+        match &self.path {
+            Some(path) if self.file_path.is_empty() => path,
+            _ => &self.file_path,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -45,27 +212,338 @@ struct ToolUsePayload {
     reason: Option<String>,
 }
 
-/// Collect all file paths under `root`, respecting .gitignore.
-fn collect_files(root: &PathBuf) -> Vec<String> {
-    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.clone());
-    let mut files = Vec::new();
-    for entry in WalkBuilder::new(&canonical_root).build() {
-        if let Ok(entry) = entry {
-            if entry.file_type().is_some_and(|ft| ft.is_file()) {
-                files.push(entry.path().to_string_lossy().to_string());
+/// Largest POST body accepted; bigger ones are answered with 413
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// Longest session id, tool name or model accepted
+const MAX_ID_LEN: usize = 256;
+
+/// Longest file path or cwd accepted
+const MAX_PATH_LEN: usize = 4096;
+
+/// Reasons longer than this are cut short rather than rejected
+const MAX_REASON_CHARS: usize = 500;
+
+/// Statuses are shown in a bubble above the ship, so they're cut much shorter
+const MAX_STATUS_CHARS: usize = 80;
+
+/// Reject a field longer than `max` bytes with a message naming it
+fn check_len(field: &str, value: &str, max: usize) -> Result<(), String> {
+    if value.len() > max {
+        return Err(format!("{} is {} bytes, the limit is {}", field, value.len(), max));
+    }
+    Ok(())
+}
+
+/// Cut `text` down to `max` characters, marking the cut with an ellipsis
+fn truncate_chars(text: String, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text,
+    }
+}
+
+impl SessionStartPayload {
+    fn validate(&self) -> Result<(), String> {
+        check_len("session_id", &self.session_id, MAX_ID_LEN)?;
+        check_len("cwd", &self.cwd, MAX_PATH_LEN)?;
+        check_len("model", &self.model, MAX_ID_LEN)
+    }
+}
+
+impl ToolUsePayload {
+    fn validate(&self) -> Result<(), String> {
+        check_len("session_id", &self.session_id, MAX_ID_LEN)?;
+        check_len("tool_name", &self.tool_name, MAX_ID_LEN)?;
+        check_len("file_path", self.tool_input.target_path(), MAX_PATH_LEN)
+    }
+}
+
+/// Answer for a POST: "OK", or 400 with what was wrong
+fn status_reply(result: Result<(), String>) -> warp::reply::WithStatus<String> {
+    match result {
+        Ok(()) => warp::reply::with_status("OK".to_string(), StatusCode::OK),
+        Err(e) => warp::reply::with_status(e, StatusCode::BAD_REQUEST),
+    }
+}
+
+/// What a POST body is rate limited by. Hooks all post from loopback, so the
+/// caller's address can't tell a runaway session from the rest.
+trait RateKey {
+    fn rate_key(&self) -> &str;
+}
+
+impl RateKey for SessionStartPayload {
+    fn rate_key(&self) -> &str {
+        &self.session_id
+    }
+}
+
+impl RateKey for SessionEndPayload {
+    fn rate_key(&self) -> &str {
+        &self.session_id
+    }
+}
+
+impl RateKey for StatusPayload {
+    fn rate_key(&self) -> &str {
+        &self.session_id
+    }
+}
+
+impl RateKey for ToolUsePayload {
+    fn rate_key(&self) -> &str {
+        &self.session_id
+    }
+}
+
+impl RateKey for LaunchPayload {
+    fn rate_key(&self) -> &str {
+        "launch"
+    }
+}
+
+/// Per-session POST counts over fixed one-second windows
+struct RateLimiter {
+    max_per_window: u32,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Sessions tracked before quiet ones are forgotten
+const RATE_LIMITER_MAX_SOURCES: usize = 1024;
+
+impl RateLimiter {
+    fn new(max_per_window: u32) -> Self {
+        RateLimiter {
+            max_per_window,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn allow(&self, key: &str) -> bool {
+        self.allow_at(key, Instant::now())
+    }
+
+    fn allow_at(&self, key: &str, now: Instant) -> bool {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() > RATE_LIMITER_MAX_SOURCES {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < RATE_WINDOW);
+        }
+        let (start, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        if *count == self.max_per_window + 1 {
+            eprintln!(
+                "[rate] {:?} sent more than {} requests in a second, answering 429",
+                key, self.max_per_window
+            );
+        }
+        *count <= self.max_per_window
+    }
+}
+
+#[derive(Debug)]
+struct RateLimited;
+
+impl warp::reject::Reject for RateLimited {}
+
+/// A size-capped JSON body, passed on while its session is under the rate limit
+fn limited_json<T>(limiter: Arc<RateLimiter>) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone
+where
+    T: DeserializeOwned + RateKey + Send + 'static,
+{
+    warp::body::content_length_limit(MAX_BODY_BYTES)
+        .and(warp::body::json())
+        .and_then(move |payload: T| {
+            let limiter = Arc::clone(&limiter);
+            async move {
+                if limiter.allow(payload.rate_key()) {
+                    Ok(payload)
+                } else {
+                    Err(warp::reject::custom(RateLimited))
+                }
             }
+        })
+}
+
+/// Turn a rate limit rejection into 429; everything else keeps warp's default answer
+async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, warp::Rejection> {
+    if err.find::<RateLimited>().is_some() {
+        return Ok(warp::reply::with_status("Too many requests", StatusCode::TOO_MANY_REQUESTS));
+    }
+    Err(err)
+}
+
+#[derive(Deserialize, Debug)]
+struct WsParams {
+    #[serde(default)]
+    format: Option<String>,
+}
+
+/// Wire format negotiated by each WebSocket client at connect time via `?format=`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EventFormat {
+    /// Tagged JSON objects with full field names (default)
+    Verbose,
+    /// Positional JSON arrays:
+    /// `["s", session_id, cwd, model]`,
+    /// `["t", session_id, tool_name, file_path, reason, timestamp]`,
+    /// `["p", session_id, status]` and
+    /// `["e", session_id]`
+    Compact,
+}
+
+impl EventFormat {
+    fn from_param(format: Option<&str>) -> Self {
+        match format {
+            Some("compact") => EventFormat::Compact,
+            _ => EventFormat::Verbose,
         }
     }
-    files
+
+    /// Re-encode a verbose broadcast message; unknown messages pass through unchanged.
+    fn encode(self, msg: &str) -> String {
+        if self == EventFormat::Verbose {
+            return msg.to_string();
+        }
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(msg) else {
+            return msg.to_string();
+        };
+        let compact = match value["type"].as_str() {
+            Some("session_start") => json!(["s", value["session_id"], value["cwd"], value["model"]]),
+            Some("tool_use") => json!([
+                "t",
+                value["session_id"],
+                value["tool_name"],
+                value["file_path"],
+                value["reason"],
+                value["timestamp"],
+            ]),
+            Some("status") => json!(["p", value["session_id"], value["status"]]),
+            Some("session_end") => json!(["e", value["session_id"]]),
+            _ => return msg.to_string(),
+        };
+        compact.to_string()
+    }
+}
+
+/// A broadcast message as handed to WebSocket clients. Each format is encoded at
+/// most once, however many clients asked for it.
+struct OutgoingEvent {
+    verbose: String,
+    compact: OnceLock<String>,
+}
+
+impl OutgoingEvent {
+    fn new(verbose: String) -> Self {
+        OutgoingEvent {
+            verbose,
+            compact: OnceLock::new(),
+        }
+    }
+
+    fn encoded(&self, format: EventFormat) -> &str {
+        match format {
+            EventFormat::Verbose => &self.verbose,
+            EventFormat::Compact => self.compact.get_or_init(|| format.encode(&self.verbose)),
+        }
+    }
+}
+
+/// Counters behind GET /metrics
+struct Metrics {
+    started: Instant,
+    connected_clients: AtomicUsize,
+    events_broadcast: AtomicUsize,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Metrics {
+            started: Instant::now(),
+            connected_clients: AtomicUsize::new(0),
+            events_broadcast: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// One line of a --record file
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedEvent {
+    /// Unix time in milliseconds when the event was broadcast
+    at_ms: i64,
+    event: serde_json::Value,
+}
+
+/// Collect up to `max_files` file paths under `root`, respecting .gitignore.
+fn collect_files(root: &Path, max_files: usize) -> Vec<String> {
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    WalkBuilder::new(&canonical_root)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+        .map(|entry| entry.path().to_string_lossy().to_string())
+        .take(max_files)
+        .collect()
+}
+
+/// The canonical directory a /launch `cwd` names, provided it exists and sits
+/// inside one of the `allowed` roots (themselves canonical)
+fn launch_dir(cwd: &str, allowed: &[PathBuf]) -> Result<PathBuf, (StatusCode, String)> {
+    let dir = Path::new(cwd)
+        .canonicalize()
+        .ok()
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("{} is not a directory", cwd)))?;
+    if !allowed.iter().any(|root| dir.starts_with(root)) {
+        return Err((StatusCode::FORBIDDEN, format!("{} is outside the allowed launch roots", cwd)));
+    }
+    Ok(dir)
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    let profile = MockProfile::from_args(&args).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    });
     let (tx, _rx) = broadcast::channel::<String>(256);
 
+    // Subscribed before anything can broadcast, so every event is counted
+    let metrics = Arc::new(Metrics::new());
+    tokio::spawn(count_events(tx.subscribe(), Arc::clone(&metrics)));
+
+    // WebSocket clients listen here, so an event is wrapped once rather than per client
+    let (client_tx, _) = broadcast::channel::<Arc<OutgoingEvent>>(256);
+    tokio::spawn(share_with_clients(tx.subscribe(), client_tx.clone()));
+
+    // Flipped to true on Ctrl-C so WebSocket clients get a close frame
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut background_tasks = Vec::new();
+
+    // Canonical, so launch_dir can compare them with a canonical cwd
+    let launch_roots: Vec<PathBuf> = args
+        .launch_roots
+        .iter()
+        .chain(args.mock.as_ref())
+        .filter_map(|root| match root.canonicalize() {
+            Ok(root) => Some(root),
+            Err(e) => {
+                eprintln!("[Launch] Ignoring launch root {}: {}", root.display(), e);
+                None
+            }
+        })
+        .collect();
+    let launch_roots = Arc::new(launch_roots);
+
     if let Some(mock_path) = args.mock {
-        let files = collect_files(&mock_path);
+        let files = collect_files(&mock_path, usize::MAX);
         if files.is_empty() {
             eprintln!(
                 "[mock] No files found under {:?} (check the path and .gitignore)",
@@ -80,8 +558,37 @@ async fn main() {
             .to_string();
         println!("[mock] Mock mode enabled — {} files from {}", files.len(), cwd);
         let files = Arc::new(files);
+        if args.mock_churn {
+            println!("[mock] Churn enabled — synthetic files will come and go");
+            background_tasks.push(tokio::spawn(run_mock_churn(tx.clone(), Arc::clone(&files))));
+        }
         let mock_tx = tx.clone();
-        tokio::spawn(run_mock_sessions(mock_tx, files, cwd));
+        background_tasks.push(tokio::spawn(run_mock_sessions(mock_tx, files, cwd, profile)));
+    }
+
+    if let Some(record_path) = args.record {
+        println!("[record] Appending events to {}", record_path.display());
+        tokio::spawn(record_events(tx.subscribe(), record_path));
+    }
+
+    if let Some(replay_path) = args.replay {
+        let events = load_recording(&replay_path).unwrap_or_else(|e| {
+            eprintln!("Error: cannot read {}: {}", replay_path.display(), e);
+            std::process::exit(1);
+        });
+        println!("[replay] Loaded {} events from {}", events.len(), replay_path.display());
+        let events = if args.replay_sessions.is_empty() {
+            events
+        } else {
+            let events = filter_sessions(events, &args.replay_sessions);
+            println!(
+                "[replay] Keeping {} events from sessions {}",
+                events.len(),
+                args.replay_sessions.join(", ")
+            );
+            events
+        };
+        background_tasks.push(tokio::spawn(replay_events(tx.clone(), events)));
     }
 
     let tx_filter = {
@@ -89,11 +596,19 @@ async fn main() {
         warp::any().map(move || tx.clone())
     };
 
+    // Every POST route caps the body size, then counts the parsed request against
+    // its session's rate limit. Checked after the path so a request is only counted
+    // by its own route.
+    let limiter = Arc::new(RateLimiter::new(args.rate_limit));
+
     let session_start = warp::post()
         .and(warp::path("session-start"))
-        .and(warp::body::json())
+        .and(limited_json(Arc::clone(&limiter)))
         .and(tx_filter.clone())
         .map(|payload: SessionStartPayload, tx: broadcast::Sender<String>| {
+            if let Err(e) = payload.validate() {
+                return status_reply(Err(e));
+            }
             let msg = json!({
                 "type": "session_start",
                 "session_id": payload.session_id,
@@ -103,105 +618,263 @@ async fn main() {
             .to_string();
             println!("[SessionStart] {}", msg);
             let _ = tx.send(msg);
-            warp::reply::with_status("OK", StatusCode::OK)
+            status_reply(Ok(()))
         });
 
-    let read_event = warp::post()
-        .and(warp::path("read"))
-        .and(warp::body::json())
+    let session_end = warp::post()
+        .and(warp::path("session-end"))
+        .and(limited_json(Arc::clone(&limiter)))
         .and(tx_filter.clone())
-        .map(|payload: ToolUsePayload, tx: broadcast::Sender<String>| {
+        .map(|payload: SessionEndPayload, tx: broadcast::Sender<String>| {
+            if let Err(e) = check_len("session_id", &payload.session_id, MAX_ID_LEN) {
+                return status_reply(Err(e));
+            }
             let msg = json!({
-                "type": "tool_use",
+                "type": "session_end",
                 "session_id": payload.session_id,
-                "tool_name": payload.tool_name,
-                "file_path": payload.tool_input.file_path,
-                "reason": payload.reason,
-                "timestamp": Utc::now().to_rfc3339(),
             })
             .to_string();
-            println!("[Read] {}", msg);
+            println!("[SessionEnd] {}", msg);
             let _ = tx.send(msg);
-            warp::reply::with_status("OK", StatusCode::OK)
+            status_reply(Ok(()))
         });
 
-    let write_event = warp::post()
-        .and(warp::path("write"))
-        .and(warp::body::json())
+    let status = warp::post()
+        .and(warp::path("status"))
+        .and(limited_json(Arc::clone(&limiter)))
         .and(tx_filter.clone())
-        .map(|payload: ToolUsePayload, tx: broadcast::Sender<String>| {
+        .map(|payload: StatusPayload, tx: broadcast::Sender<String>| {
+            if let Err(e) = check_len("session_id", &payload.session_id, MAX_ID_LEN) {
+                return status_reply(Err(e));
+            }
             let msg = json!({
-                "type": "tool_use",
+                "type": "status",
                 "session_id": payload.session_id,
-                "tool_name": payload.tool_name,
-                "file_path": payload.tool_input.file_path,
-                "reason": payload.reason,
-                "timestamp": Utc::now().to_rfc3339(),
+                "status": truncate_chars(payload.status, MAX_STATUS_CHARS),
             })
             .to_string();
-            println!("[Write] {}", msg);
+            println!("[Status] {}", msg);
             let _ = tx.send(msg);
-            warp::reply::with_status("OK", StatusCode::OK)
+            status_reply(Ok(()))
         });
 
-    let edit_event = warp::post()
-        .and(warp::path("edit"))
-        .and(warp::body::json())
-        .and(tx_filter)
-        .map(|payload: ToolUsePayload, tx: broadcast::Sender<String>| {
-            let msg = json!({
-                "type": "tool_use",
-                "session_id": payload.session_id,
-                "tool_name": payload.tool_name,
-                "file_path": payload.tool_input.file_path,
-                "reason": payload.reason,
-                "timestamp": Utc::now().to_rfc3339(),
-            })
-            .to_string();
-            println!("[Edit] {}", msg);
-            let _ = tx.send(msg);
-            warp::reply::with_status("OK", StatusCode::OK)
+    let launch_counter = Arc::new(AtomicU32::new(0));
+    let launch_model = args.launch_model;
+    let launch = warp::post()
+        .and(warp::path("launch"))
+        .and(limited_json(Arc::clone(&limiter)))
+        .and(tx_filter.clone())
+        .map(move |payload: LaunchPayload, tx: broadcast::Sender<String>| {
+            if let Err(e) = check_len("cwd", &payload.cwd, MAX_PATH_LEN) {
+                return status_reply(Err(e)).into_response();
+            }
+            let dir = match launch_dir(&payload.cwd, &launch_roots) {
+                Ok(dir) => dir,
+                Err((status, e)) => {
+                    eprintln!("[Launch] Rejected: {}", e);
+                    return warp::reply::with_status(e, status).into_response();
+                }
+            };
+            let n = launch_counter.fetch_add(1, Ordering::Relaxed) + 1;
+            let session_id = format!("launch-session-{}", n);
+            println!("[Launch] {} for task {:?} in {}", session_id, payload.task, dir.display());
+            tokio::spawn(run_launched_session(tx, dir, session_id.clone(), launch_model.clone(), profile));
+            warp::reply::json(&json!({ "session_id": session_id })).into_response()
         });
 
+    let read_event = tool_use_route("read", "Read", Arc::clone(&limiter), tx_filter.clone());
+    let write_event = tool_use_route("write", "Write", Arc::clone(&limiter), tx_filter.clone());
+    let edit_event = tool_use_route("edit", "Edit", Arc::clone(&limiter), tx_filter.clone());
+    let grep_event = tool_use_route("grep", "Grep", Arc::clone(&limiter), tx_filter.clone());
+    let glob_event = tool_use_route("glob", "Glob", Arc::clone(&limiter), tx_filter.clone());
+    let bash_event = tool_use_route("bash", "Bash", limiter, tx_filter);
+
     let ws_route = {
-        let tx = tx.clone();
+        let metrics = Arc::clone(&metrics);
         warp::path("ws")
             .and(warp::ws())
-            .map(move |ws: warp::ws::Ws| {
-                let rx = tx.subscribe();
-                ws.on_upgrade(move |websocket| handle_ws_client(websocket, rx))
+            .and(warp::query::<WsParams>())
+            .map(move |ws: warp::ws::Ws, params: WsParams| {
+                let rx = client_tx.subscribe();
+                let format = EventFormat::from_param(params.format.as_deref());
+                let metrics = Arc::clone(&metrics);
+                let shutdown = shutdown_rx.clone();
+                ws.on_upgrade(move |websocket| handle_ws_client(websocket, rx, format, metrics, shutdown))
+            })
+    };
+
+    let health = {
+        let metrics = Arc::clone(&metrics);
+        warp::get()
+            .and(warp::path("health"))
+            .and(warp::path::end())
+            .map(move || {
+                warp::reply::json(&json!({
+                    "status": "ok",
+                    "uptime_secs": metrics.started.elapsed().as_secs(),
+                }))
+            })
+    };
+
+    let metrics_route = {
+        let metrics = Arc::clone(&metrics);
+        warp::get()
+            .and(warp::path("metrics"))
+            .and(warp::path::end())
+            .map(move || {
+                warp::reply::json(&json!({
+                    "connected_clients": metrics.connected_clients.load(Ordering::Relaxed),
+                    "events_broadcast": metrics.events_broadcast.load(Ordering::Relaxed),
+                    "uptime_secs": metrics.started.elapsed().as_secs(),
+                }))
             })
     };
 
     let routes = session_start
+        .or(session_end)
+        .or(status)
+        .or(launch)
         .or(read_event)
         .or(write_event)
         .or(edit_event)
-        .or(ws_route);
+        .or(grep_event)
+        .or(glob_event)
+        .or(bash_event)
+        .or(health)
+        .or(metrics_route)
+        .or(ws_route)
+        .recover(handle_rejection);
 
     println!("Server starting on http://127.0.0.1:8080");
-    warp::serve(routes).run(([127, 0, 0, 1], 8080)).await;
+    let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(([127, 0, 0, 1], 8080), async {
+        let _ = tokio::signal::ctrl_c().await;
+        println!("Shutting down");
+    });
+    server.await;
+
+    // Stop generating events, then let connected clients see the close frame
+    for task in background_tasks {
+        task.abort();
+    }
+    let _ = shutdown_tx.send(true);
+    let deadline = Instant::now() + SHUTDOWN_GRACE;
+    while metrics.connected_clients.load(Ordering::Relaxed) > 0 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
 }
 
-async fn handle_ws_client(websocket: warp::ws::WebSocket, mut rx: broadcast::Receiver<String>) {
+/// POST route that relays a tool use event to all WebSocket clients.
+fn tool_use_route(
+    path: &'static str,
+    label: &'static str,
+    limiter: Arc<RateLimiter>,
+    tx_filter: impl Filter<Extract = (broadcast::Sender<String>,), Error = std::convert::Infallible>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path(path))
+        .and(limited_json(limiter))
+        .and(tx_filter)
+        .map(move |payload: ToolUsePayload, tx: broadcast::Sender<String>| {
+            if let Err(e) = payload.validate() {
+                return status_reply(Err(e));
+            }
+            let reason = payload.reason.map(|reason| truncate_chars(reason, MAX_REASON_CHARS));
+            let msg = json!({
+                "type": "tool_use",
+                "session_id": payload.session_id,
+                "tool_name": payload.tool_name,
+                "file_path": payload.tool_input.target_path(),
+                "reason": reason,
+                "timestamp": Utc::now().to_rfc3339(),
+            })
+            .to_string();
+            println!("[{}] {}", label, msg);
+            let _ = tx.send(msg);
+            status_reply(Ok(()))
+        })
+}
+
+async fn handle_ws_client(
+    websocket: warp::ws::WebSocket,
+    mut rx: broadcast::Receiver<Arc<OutgoingEvent>>,
+    format: EventFormat,
+    metrics: Arc<Metrics>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    metrics.connected_clients.fetch_add(1, Ordering::Relaxed);
     let (mut ws_tx, mut ws_rx) = websocket.split();
+    // The recv task hands ping payloads to the send task, which owns the sink
+    let (pong_tx, mut pong_rx) = mpsc::unbounded_channel::<Vec<u8>>();
 
     let send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            if ws_tx.send(Message::text(msg)).await.is_err() {
-                break;
+        loop {
+            tokio::select! {
+                msg = rx.recv() => {
+                    let Ok(msg) = msg else { break };
+                    if ws_tx.send(Message::text(msg.encoded(format))).await.is_err() {
+                        break;
+                    }
+                }
+                Some(payload) = pong_rx.recv() => {
+                    if ws_tx.send(Message::pong(payload)).await.is_err() {
+                        break;
+                    }
+                }
+                _ = shutdown.changed() => {
+                    let _ = ws_tx.send(Message::close()).await;
+                    break;
+                }
             }
         }
     });
 
     let recv_task = tokio::spawn(async move {
-        while let Some(Ok(_)) = ws_rx.next().await {}
+        // Clients ping to keep idle connections alive; everything else is ignored
+        while let Some(Ok(msg)) = ws_rx.next().await {
+            if msg.is_ping() && pong_tx.send(msg.into_bytes()).is_err() {
+                break;
+            }
+        }
     });
 
     tokio::select! {
         _ = send_task => {},
         _ = recv_task => {},
     }
+    metrics.connected_clients.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Wraps each broadcast message for the WebSocket clients' channel.
+async fn share_with_clients(
+    mut rx: broadcast::Receiver<String>,
+    client_tx: broadcast::Sender<Arc<OutgoingEvent>>,
+) {
+    loop {
+        match rx.recv().await {
+            Ok(msg) => {
+                let _ = client_tx.send(Arc::new(OutgoingEvent::new(msg)));
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Tallies every broadcast message for GET /metrics.
+async fn count_events(mut rx: broadcast::Receiver<String>, metrics: Arc<Metrics>) {
+    loop {
+        let counted = match rx.recv().await {
+            Ok(_) => 1,
+            // Lagging only drops the messages, they were still broadcast
+            Err(broadcast::error::RecvError::Lagged(skipped)) => skipped as usize,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        metrics.events_broadcast.fetch_add(counted, Ordering::Relaxed);
+    }
 }
 
 /// Manages the lifecycle of multiple concurrent mock sessions.
@@ -209,6 +882,7 @@ async fn run_mock_sessions(
     tx: broadcast::Sender<String>,
     files: Arc<Vec<String>>,
     cwd: String,
+    profile: MockProfile,
 ) {
     tokio::time::sleep(Duration::from_secs(1)).await;
 
@@ -217,12 +891,13 @@ async fn run_mock_sessions(
     let mut session_counter: u32 = 0;
 
     // Keep 2–4 sessions alive concurrently, staggering their starts.
+    // A JoinSet aborts its sessions when dropped, so aborting this task stops them too.
     let max_concurrent = 2 + (rng.random::<u32>() % 3); // 2–4
-    let mut handles: Vec<tokio::task::JoinHandle<()>> = Vec::new();
+    let mut handles = tokio::task::JoinSet::new();
 
     loop {
         // Clean up finished sessions
-        handles.retain(|h| !h.is_finished());
+        while handles.try_join_next().is_some() {}
 
         // Spawn new sessions up to the concurrent limit
         while (handles.len() as u32) < max_concurrent {
@@ -235,14 +910,15 @@ async fn run_mock_sessions(
             // Stagger the initial delay per session so they don't all fire at once
             let initial_delay = rng.random_range(0..2000u64);
 
-            handles.push(tokio::spawn(run_single_session(
+            handles.spawn(run_single_session(
                 tx,
                 files,
                 cwd,
                 session_id,
                 model.to_string(),
                 initial_delay,
-            )));
+                profile,
+            ));
         }
 
         // Check back periodically to see if we need to replace finished sessions
@@ -251,10 +927,182 @@ async fn run_mock_sessions(
     }
 }
 
+/// Appends each broadcast message to `path` as a `RecordedEvent` line.
+async fn record_events(mut rx: broadcast::Receiver<String>, path: PathBuf) {
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await;
+    let mut file = match file {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("[record] Cannot open {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    loop {
+        let msg = match rx.recv().await {
+            Ok(msg) => msg,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                eprintln!("[record] Fell behind, {} events not recorded", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let Ok(event) = serde_json::from_str(&msg) else {
+            continue;
+        };
+        let entry = RecordedEvent {
+            at_ms: Utc::now().timestamp_millis(),
+            event,
+        };
+        let mut line = json!(entry).to_string();
+        line.push('\n');
+        if let Err(e) = file.write_all(line.as_bytes()).await {
+            eprintln!("[record] Failed to write {}: {}", path.display(), e);
+            break;
+        }
+    }
+}
+
+/// Reads a --record file, skipping (with a warning) lines that don't parse.
+fn load_recording(path: &Path) -> std::io::Result<Vec<RecordedEvent>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut events = Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RecordedEvent>(line) {
+            Ok(event) => events.push(event),
+            Err(e) => eprintln!("[replay] Skipping malformed line {}: {}", line_number + 1, e),
+        }
+    }
+    Ok(events)
+}
+
+/// Drops events from sessions other than `sessions`. Events without a session
+/// (file churn) are kept; the gaps between the remaining events stay as recorded.
+fn filter_sessions(events: Vec<RecordedEvent>, sessions: &[String]) -> Vec<RecordedEvent> {
+    events
+        .into_iter()
+        .filter(|entry| match entry.event.get("session_id").and_then(|id| id.as_str()) {
+            Some(session_id) => sessions.iter().any(|wanted| wanted == session_id),
+            None => true,
+        })
+        .collect()
+}
+
+/// Re-broadcasts recorded events once a client is connected, sleeping for the
+/// recorded gap between consecutive events.
+async fn replay_events(tx: broadcast::Sender<String>, events: Vec<RecordedEvent>) {
+    // Nothing is buffered for late subscribers, so hold off until someone is listening
+    let idle_receivers = tx.receiver_count();
+    println!("[replay] Waiting for a client to connect");
+    while tx.receiver_count() <= idle_receivers {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    let mut previous_at: Option<i64> = None;
+    for entry in events {
+        if let Some(previous_at) = previous_at {
+            let gap = (entry.at_ms - previous_at).max(0) as u64;
+            tokio::time::sleep(Duration::from_millis(gap)).await;
+        }
+        previous_at = Some(entry.at_ms);
+
+        let msg = entry.event.to_string();
+        println!("[replay] {}", msg);
+        let _ = tx.send(msg);
+    }
+    println!("[replay] Finished");
+}
+
+/// Announces synthetic files next to real ones, then deletes them again once
+/// their lifetime is up. Messages go out in the verbose format for every client.
+async fn run_mock_churn(tx: broadcast::Sender<String>, files: Arc<Vec<String>>) {
+    let mut rng = StdRng::from_os_rng();
+    let mut created: Vec<(PathBuf, Instant)> = Vec::new(); // path, delete after
+    let mut counter: u32 = 0;
+
+    loop {
+        tokio::time::sleep(Duration::from_millis(rng.random_range(CHURN_INTERVAL_MS))).await;
+
+        let now = Instant::now();
+        let (expired, alive): (Vec<_>, Vec<_>) = created.into_iter().partition(|(_, until)| *until <= now);
+        created = alive;
+        for (path, _) in expired {
+            let msg = json!({
+                "type": "file_deleted",
+                "path": path.to_string_lossy(),
+            })
+            .to_string();
+            println!("[mock] {}", msg);
+            let _ = tx.send(msg);
+        }
+
+        if created.len() >= MAX_CHURN_FILES {
+            continue;
+        }
+        // Borrow a real file's directory and extension so the new star looks at home
+        let Some(template) = files.choose(&mut rng).map(Path::new) else {
+            continue;
+        };
+        let Some(dir) = template.parent() else {
+            continue;
+        };
+        counter += 1;
+        let mut path = dir.join(format!("mock-churn-{}", counter));
+        if let Some(ext) = template.extension() {
+            path.set_extension(ext);
+        }
+
+        let msg = json!({
+            "type": "file_created",
+            "path": path.to_string_lossy(),
+            "is_dir": false,
+        })
+        .to_string();
+        println!("[mock] {}", msg);
+        let _ = tx.send(msg);
+        let lifetime = Duration::from_millis(rng.random_range(CHURN_LIFETIME_MS));
+        created.push((path, now + lifetime));
+    }
+}
+
+/// Drives a session started from the frontend's prompt bar. There is no real
+/// agent behind it yet, so it plays a mock session over the requested project.
+async fn run_launched_session(
+    tx: broadcast::Sender<String>,
+    dir: PathBuf,
+    session_id: String,
+    model: String,
+    profile: MockProfile,
+) {
+    let cwd = dir.to_string_lossy().to_string();
+    let files = tokio::task::spawn_blocking(move || collect_files(&dir, MAX_LAUNCH_FILES))
+        .await
+        .unwrap_or_default();
+    if files.is_empty() {
+        eprintln!("[Launch] No files found under {} — {} not started", cwd, session_id);
+        return;
+    }
+    run_single_session(
+        tx,
+        Arc::new(files),
+        cwd,
+        session_id,
+        model,
+        LAUNCH_START_DELAY_MS,
+        profile,
+    )
+    .await;
+}
+
 /// Generate a human-readable explanation for a tool use action
 fn generate_action_explanation(tool_name: &str, file_path: &str, action_number: u32, total_actions: u32) -> String {
-    use std::path::Path;
-
     let file_name = Path::new(file_path)
         .file_name()
         .and_then(|n| n.to_str())
@@ -272,7 +1120,7 @@ fn generate_action_explanation(tool_name: &str, file_path: &str, action_number:
         "Read" => {
             if phase_ratio < 0.3 {
                 // Early exploration phase
-                vec![
+                [
                     format!("Reading {} to understand the codebase structure", file_name),
                     format!("Scanning {} to identify dependencies", file_name),
                     format!("Reviewing {} to understand the current implementation", file_name),
@@ -281,7 +1129,7 @@ fn generate_action_explanation(tool_name: &str, file_path: &str, action_number:
                 ].choose(&mut StdRng::from_os_rng()).unwrap().clone()
             } else {
                 // Later investigation phase
-                vec![
+                [
                     format!("Reading {} to verify the changes needed", file_name),
                     format!("Checking {} before making modifications", file_name),
                     format!("Reviewing {} to ensure compatibility", file_name),
@@ -291,13 +1139,13 @@ fn generate_action_explanation(tool_name: &str, file_path: &str, action_number:
         },
         "Write" => {
             if ext == "rs" {
-                vec![
+                [
                     format!("Writing {} to add new functionality", file_name),
                     format!("Creating {} with the required implementation", file_name),
                     format!("Writing {} to introduce the new module", file_name),
                 ].choose(&mut StdRng::from_os_rng()).unwrap().clone()
             } else {
-                vec![
+                [
                     format!("Writing {} to update configuration", file_name),
                     format!("Creating {} with new settings", file_name),
                     format!("Writing {} to document the changes", file_name),
@@ -306,7 +1154,7 @@ fn generate_action_explanation(tool_name: &str, file_path: &str, action_number:
         },
         "Edit" => {
             if ext == "rs" {
-                vec![
+                [
                     format!("Editing {} to fix the identified issue", file_name),
                     format!("Updating {} to improve the implementation", file_name),
                     format!("Modifying {} to add the requested feature", file_name),
@@ -314,18 +1162,45 @@ fn generate_action_explanation(tool_name: &str, file_path: &str, action_number:
                     format!("Editing {} to integrate the new functionality", file_name),
                 ].choose(&mut StdRng::from_os_rng()).unwrap().clone()
             } else {
-                vec![
+                [
                     format!("Editing {} to update configuration", file_name),
                     format!("Updating {} to fix inconsistencies", file_name),
                     format!("Modifying {} to align with requirements", file_name),
                 ].choose(&mut StdRng::from_os_rng()).unwrap().clone()
             }
         },
+        "Grep" => [
+            format!("Searching {} for usages", file_name),
+            format!("Grepping {} for the relevant symbol", file_name),
+            format!("Searching {} for related call sites", file_name),
+        ]
+        .choose(&mut StdRng::from_os_rng())
+        .unwrap()
+        .clone(),
+        "Glob" => [
+            format!("Listing files under {}", file_name),
+            format!("Finding matching files in {}", file_name),
+        ]
+        .choose(&mut StdRng::from_os_rng())
+        .unwrap()
+        .clone(),
+        "Bash" => [
+            "Running cargo build to check the changes".to_string(),
+            "Running the test suite".to_string(),
+            "Running git status to review the work".to_string(),
+        ]
+        .choose(&mut StdRng::from_os_rng())
+        .unwrap()
+        .clone(),
         _ => format!("{} {}", tool_name, file_name),
     }
 }
 
+/// Phases a mock agent announces before one of its longer pauses
+const MOCK_STATUSES: [&str; 4] = ["thinking", "planning the next step", "compiling", "waiting for user"];
+
 /// Simulates a single agent session: start → several tool uses → end.
+/// Longer pauses are announced with a status event first.
 async fn run_single_session(
     tx: broadcast::Sender<String>,
     files: Arc<Vec<String>>,
@@ -333,9 +1208,9 @@ async fn run_single_session(
     session_id: String,
     model: String,
     initial_delay: u64,
+    profile: MockProfile,
 ) {
     let mut rng = StdRng::from_os_rng();
-    let tool_names = ["Read", "Write", "Edit"];
     // Realistic timing: thinking pauses + tool execution
     let short_delays: [u64; 4] = [200, 400, 600, 900];
     let long_delays: [u64; 4] = [1500, 2500, 4000, 6000];
@@ -354,30 +1229,36 @@ async fn run_single_session(
     let _ = tx.send(start_msg);
 
     // Simulate a realistic work pattern: read several files, then edit/write a few.
-    // Total actions: 4–12
-    let num_actions = 4 + (rng.random::<u32>() % 9);
+    let num_actions = rng.random_range(profile.min_actions..=profile.max_actions);
 
     for i in 0..num_actions {
-        // Bias toward reads early in the session, writes/edits later (realistic agent behavior)
-        let tool = if i < num_actions / 3 {
-            // Early phase: mostly reads
-            if rng.random::<f32>() < 0.85 {
-                "Read"
-            } else {
-                *tool_names.choose(&mut rng).unwrap()
-            }
-        } else {
-            // Later phase: mix of all tools
-            *tool_names.choose(&mut rng).unwrap()
-        };
+        // Bias toward reads early in the session, the weighted mix later (realistic agent behavior)
+        let tool = profile.choose_tool(&mut rng, i < num_actions / 3);
 
-        let path = files.choose(&mut rng).unwrap();
+        // Grep/Glob target the file's directory, Bash runs from the project root
+        let file = files.choose(&mut rng).unwrap();
+        let path = match tool {
+            "Grep" | "Glob" => Path::new(file)
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| cwd.clone()),
+            "Bash" => cwd.clone(),
+            _ => file.clone(),
+        };
 
         // Generate explanation for this action
-        let explanation = generate_action_explanation(tool, path, i, num_actions);
+        let explanation = generate_action_explanation(tool, &path, i, num_actions);
 
         // Occasionally have a "thinking" pause (longer delay), otherwise quick succession
         let delay = if rng.random::<f32>() < 0.3 {
+            let status_msg = json!({
+                "type": "status",
+                "session_id": session_id,
+                "status": MOCK_STATUSES.choose(&mut rng).unwrap(),
+            })
+            .to_string();
+            println!("[mock] {}", status_msg);
+            let _ = tx.send(status_msg);
             *long_delays.choose(&mut rng).unwrap()
         } else {
             *short_delays.choose(&mut rng).unwrap()
@@ -400,4 +1281,124 @@ async fn run_single_session(
     // Session lives for a bit after last action before "finishing"
     let wind_down = 1000 + (rng.random::<u64>() % 3000);
     tokio::time::sleep(Duration::from_millis(wind_down)).await;
+
+    let end_msg = json!({
+        "type": "session_end",
+        "session_id": session_id,
+    })
+    .to_string();
+    println!("[mock] {}", end_msg);
+    let _ = tx.send(end_msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(at_ms: i64, event: serde_json::Value) -> RecordedEvent {
+        RecordedEvent { at_ms, event }
+    }
+
+    #[test]
+    fn filter_sessions_keeps_wanted_sessions_and_sessionless_events() {
+        let events = vec![
+            recorded(0, json!({"type": "session_start", "session_id": "a"})),
+            recorded(10, json!({"type": "tool_use", "session_id": "b", "file_path": "/x"})),
+            recorded(20, json!({"type": "file_created", "path": "/y"})),
+            recorded(30, json!({"type": "tool_use", "session_id": "a", "file_path": "/z"})),
+            recorded(40, json!({"type": "session_end", "session_id": "c"})),
+        ];
+        let kept = filter_sessions(events, &["a".to_string()]);
+        let times: Vec<i64> = kept.iter().map(|entry| entry.at_ms).collect();
+        assert_eq!(times, vec![0, 20, 30]);
+    }
+
+    #[test]
+    fn filter_sessions_accepts_several_sessions() {
+        let events = vec![
+            recorded(0, json!({"session_id": "a"})),
+            recorded(1, json!({"session_id": "b"})),
+            recorded(2, json!({"session_id": "c"})),
+        ];
+        let kept = filter_sessions(events, &["a".to_string(), "c".to_string()]);
+        let times: Vec<i64> = kept.iter().map(|entry| entry.at_ms).collect();
+        assert_eq!(times, vec![0, 2]);
+    }
+
+    #[test]
+    fn compact_encoding_is_shared_between_clients() {
+        let msg = json!({"type": "session_end", "session_id": "a"}).to_string();
+        let event = OutgoingEvent::new(msg.clone());
+
+        assert_eq!(event.encoded(EventFormat::Verbose), msg);
+        let first = event.encoded(EventFormat::Compact);
+        assert_eq!(first, r#"["e","a"]"#);
+        assert!(std::ptr::eq(first, event.encoded(EventFormat::Compact)));
+    }
+
+    #[test]
+    fn launch_dir_must_sit_under_an_allowed_root() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("project")).unwrap();
+        let allowed = vec![root.clone()];
+
+        let inside = root.join("project");
+        assert_eq!(launch_dir(&inside.to_string_lossy(), &allowed), Ok(inside));
+        assert_eq!(launch_dir("/", &allowed).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert_eq!(launch_dir(&root.to_string_lossy(), &[]).unwrap_err().0, StatusCode::FORBIDDEN);
+        let missing = root.join("missing");
+        assert_eq!(launch_dir(&missing.to_string_lossy(), &allowed).unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn collect_files_stops_at_the_cap() {
+        let root = tempfile::tempdir().unwrap();
+        for i in 0..5 {
+            std::fs::write(root.path().join(format!("{}.txt", i)), "").unwrap();
+        }
+        assert_eq!(collect_files(root.path(), 3).len(), 3);
+        assert_eq!(collect_files(root.path(), usize::MAX).len(), 5);
+    }
+
+    #[test]
+    fn rate_limit_counts_each_session_separately() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        assert!(limiter.allow_at("a", start));
+        assert!(limiter.allow_at("a", start));
+        assert!(!limiter.allow_at("a", start));
+        assert!(limiter.allow_at("b", start));
+
+        // A fresh window starts a second later
+        assert!(limiter.allow_at("a", start + RATE_WINDOW));
+    }
+
+    #[test]
+    fn truncate_chars_cuts_on_character_boundaries() {
+        assert_eq!(truncate_chars("short".to_string(), 10), "short");
+        assert_eq!(truncate_chars("exactly".to_string(), 7), "exactly");
+        assert_eq!(truncate_chars("héllo wörld".to_string(), 4), "héll…");
+    }
+
+    #[test]
+    fn validate_rejects_overlong_fields() {
+        let tool_use = |session_id: &str, file_path: &str| ToolUsePayload {
+            session_id: session_id.to_string(),
+            tool_name: "Read".to_string(),
+            tool_input: ToolInput { file_path: file_path.to_string(), path: None },
+            reason: None,
+        };
+        assert!(tool_use("a", "/repo/main.rs").validate().is_ok());
+        assert!(tool_use(&"x".repeat(MAX_ID_LEN + 1), "/repo/main.rs").validate().is_err());
+        let error = tool_use("a", &"x".repeat(MAX_PATH_LEN + 1)).validate().unwrap_err();
+        assert!(error.starts_with("file_path"), "{}", error);
+
+        let start = SessionStartPayload {
+            session_id: "a".to_string(),
+            cwd: "/repo".to_string(),
+            model: "x".repeat(MAX_ID_LEN + 1),
+        };
+        assert!(start.validate().unwrap_err().starts_with("model"));
+    }
 }